@@ -1,13 +1,11 @@
 use std::collections::HashSet;
 mod common;
 
-fn main() {
-    let numbers: Vec<i32> = common::get_lines()
-        .into_iter()
-        .map(|l| l.parse::<i32>().expect("could not parse number"))
-        .collect();
+fn main() -> Result<(), common::InputError> {
+    let report = common::Report::new(2018, 1)?;
+    let numbers: Vec<i32> = common::get_input()?;
     let result: i32 = numbers.iter().sum();
-    println!("Resulting frequency: {}", result);
+    report.part(1, result, format!("Resulting frequency: {}", result));
 
     let mut visited = HashSet::new();
     let mut x = 0;
@@ -18,5 +16,6 @@ fn main() {
             break;
         }
     }
-    println!("First frequency reached twice: {}", x);
+    report.part(2, x, format!("First frequency reached twice: {}", x));
+    Ok(())
 }
@@ -1,8 +1,9 @@
 use std::collections::HashMap;
 mod common;
 
-fn main() {
-    let boxes: Vec<String> = common::get_lines();
+fn main() -> Result<(), common::InputError> {
+    let report = common::Report::new(2018, 2)?;
+    let boxes: Vec<String> = common::get_lines()?;
     let mut two = 0;
     let mut three = 0;
     for x in &boxes {
@@ -16,7 +17,7 @@ fn main() {
         three += letters.values().any(|&n| n == 3) as i32;
     }
     let result = two * three;
-    println!("Checksum: {}", result);
+    report.part(1, result, format!("Checksum: {}", result));
 
     for (i, x) in boxes.iter().enumerate() {
         for y in boxes.iter().skip(i + 1) {
@@ -26,8 +27,13 @@ fn main() {
                 .filter_map(|(c, d)| if c == d { Some(c) } else { None })
                 .collect();
             if common_letters.len() + 1 == x.len() {
-                println!("Common letters: {}", common_letters);
+                report.part(
+                    2,
+                    &common_letters,
+                    format!("Common letters: {}", common_letters),
+                );
             }
         }
     }
+    Ok(())
 }
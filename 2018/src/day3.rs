@@ -1,4 +1,8 @@
+use std::str::FromStr;
 mod common;
+use common::geometry::Point2D;
+use common::grid::Grid;
+use common::InputError;
 
 struct Cut {
     id: usize,
@@ -8,55 +12,64 @@ struct Cut {
     height: usize,
 }
 
-impl Cut {
-    fn parse(s: String) -> Cut {
+impl FromStr for Cut {
+    type Err = InputError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         let e: Vec<usize> = s
             .split(['#', '@', ',', ':', 'x'].as_ref())
             .filter(|t| !t.is_empty())
-            .map(|t| t.trim().parse::<usize>().expect("Could not parse number"))
-            .collect();
-        Cut {
+            .map(|t| {
+                let t = t.trim();
+                t.parse::<usize>()
+                    .map_err(|_| InputError::spanning(s, t, "could not parse number"))
+            })
+            .collect::<Result<_, _>>()?;
+        if e.len() != 5 {
+            return Err(InputError::spanning(s, s, "cut does not have five numbers"));
+        }
+        Ok(Cut {
             id: e[0],
             left: e[1],
             top: e[2],
             width: e[3],
             height: e[4],
-        }
+        })
     }
 }
 
-fn main() {
-    let cuts: Vec<Cut> = common::get_lines().into_iter().map(Cut::parse).collect();
+impl Cut {
+    fn points(&self) -> impl Iterator<Item = Point2D> + '_ {
+        (self.top..self.top + self.height).flat_map(move |y| {
+            (self.left..self.left + self.width).map(move |x| Point2D::new(x as i64, y as i64))
+        })
+    }
+}
 
-    const N: usize = 1000;
-    let mut fabric: [[u32; N]; N] = [[0; N]; N];
+fn main() -> Result<(), InputError> {
+    let report = common::Report::new(2018, 3)?;
+    let cuts: Vec<Cut> = common::get_input()?;
+
+    let width = cuts.iter().map(|c| c.left + c.width).max().unwrap_or(0);
+    let height = cuts.iter().map(|c| c.top + c.height).max().unwrap_or(0);
+    let mut fabric = Grid::new(width, height, 0u32);
 
     for c in &cuts {
-        for x in 0..c.width {
-            for y in 0..c.height {
-                fabric[c.left + x][c.top + y] += 1;
-            }
+        for p in c.points() {
+            fabric[p] += 1;
         }
     }
-    let overlapping = fabric
-        .iter()
-        .flat_map(|r| r.iter())
-        .filter(|&&i| i >= 2)
-        .count();
-    println!("Overlapping inches: {}", overlapping);
+    let overlapping = fabric.values().filter(|&&i| i >= 2).count();
+    report.part(
+        1,
+        overlapping,
+        format!("Overlapping inches: {}", overlapping),
+    );
 
     for c in &cuts {
-        let mut non_overlapping = true;
-        'cut_loop: for x in 0..c.width {
-            for y in 0..c.height {
-                if fabric[c.left + x][c.top + y] > 1 {
-                    non_overlapping = false;
-                    break 'cut_loop;
-                }
-            }
-        }
-        if non_overlapping {
-            println!("ID of non-overlapping cut: {}", c.id);
+        if c.points().all(|p| fabric[p] == 1) {
+            report.part(2, c.id, format!("ID of non-overlapping cut: {}", c.id));
         }
     }
+    Ok(())
 }
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::hash::Hash;
 use std::str::FromStr;
 mod common;
+use common::InputError;
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 enum Event {
@@ -26,37 +27,37 @@ struct Entry {
 }
 
 impl FromStr for Event {
-    type Err = &'static str;
+    type Err = InputError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "wakes up" => Ok(Event::WakeUp),
             "falls asleep" => Ok(Event::FallAsleep),
             _ if s.starts_with("Guard #") && s.ends_with(" begins shift") => {
-                let id = s[7..s.len() - 13]
+                let id_str = &s[7..s.len() - 13];
+                let id = id_str
                     .parse::<u32>()
-                    .map_err(|_| "could not parse guard id")?;
+                    .map_err(|_| InputError::spanning(s, id_str, "could not parse guard id"))?;
                 Ok(Event::Begin { id })
             }
-            _ => Err("unknown event"),
+            _ => Err(InputError::spanning(s, s, "unknown event")),
         }
     }
 }
 
 impl FromStr for Date {
-    type Err = &'static str;
+    type Err = InputError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let e: Vec<_> = s
             .split(['[', ']', '-', ' ', ':'].as_ref())
             .filter(|t| !t.is_empty())
             .map(|t| {
-                t.trim()
-                    .parse::<u32>()
-                    .map_err(|_| "Could not parse number")
+                let t = t.trim();
+                t.parse::<u32>()
+                    .map_err(|_| InputError::spanning(s, t, "could not parse number"))
             })
-            .flatten()
-            .collect();
+            .collect::<Result<_, _>>()?;
         if e.len() < 5 {
-            Err("date has not enough elements")
+            Err(InputError::spanning(s, s, "date has not enough elements"))
         } else {
             Ok(Date {
                 year: e[0],
@@ -70,13 +71,15 @@ impl FromStr for Date {
 }
 
 impl FromStr for Entry {
-    type Err = &'static str;
+    type Err = InputError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() < 20 {
-            Err("entry too short")
+        if s.len() < 20 || !s.is_char_boundary(17) || !s.is_char_boundary(19) {
+            Err(InputError::spanning(s, s, "entry too short"))
         } else {
-            let date = Date::from_str(&s[1..17])?;
-            let event = Event::from_str(&s[19..])?;
+            let date_str = &s[1..17];
+            let date = Date::from_str(date_str).map_err(|e| e.within(s, date_str))?;
+            let event_str = &s[19..];
+            let event = Event::from_str(event_str).map_err(|e| e.within(s, event_str))?;
             Ok(Entry { date, event })
         }
     }
@@ -96,7 +99,7 @@ fn build_map(entries: Vec<Entry>) -> SleepMap {
                 for minute in sleep_begin..e.date.minute {
                     sleeping_minutes
                         .entry(guard)
-                        .or_insert_with(|| vec![])
+                        .or_insert_with(Vec::new)
                         .push(minute);
                 }
             }
@@ -140,23 +143,30 @@ fn choose_guard_strategy2(map: &SleepMap) -> u32 {
     chosen_guard * chosen_minute
 }
 
-fn main() {
-    let mut entries: Vec<Entry> = common::get_lines()
-        .into_iter()
-        .map(|l| l.parse::<Entry>().expect("could not parse entry"))
-        .collect();
+fn main() -> Result<(), InputError> {
+    let report = common::Report::new(2018, 4)?;
+    let mut entries: Vec<Entry> = common::get_input()?;
     entries.sort();
     let map = build_map(entries);
 
     let result1 = choose_guard_strategy1(&map);
-    println!(
-        "Strategy 1: ID of chosen guard * chosen minute: {}",
-        result1
+    report.part(
+        1,
+        result1,
+        format!(
+            "Strategy 1: ID of chosen guard * chosen minute: {}",
+            result1
+        ),
     );
 
     let result2 = choose_guard_strategy2(&map);
-    println!(
-        "Strategy 2: ID of chosen guard * chosen minute: {}",
-        result2
+    report.part(
+        2,
+        result2,
+        format!(
+            "Strategy 2: ID of chosen guard * chosen minute: {}",
+            result2
+        ),
     );
+    Ok(())
 }
@@ -6,10 +6,7 @@ fn react(polymer: &str) -> String {
         // add end of polymer character
         let mut t = String::new();
         let mut skip = false;
-        for (c, d) in s
-            .chars()
-            .zip(s.chars().skip(1).chain(vec!['#'].into_iter()))
-        {
+        for (c, d) in s.chars().zip(s.chars().skip(1).chain(vec!['#'])) {
             if skip {
                 skip = false;
                 continue;
@@ -41,13 +38,26 @@ fn shortest_with_deletion(polymer: &str) -> String {
     reacted.min_by_key(|s| s.len()).unwrap()
 }
 
-fn main() {
-    let input: Vec<String> = common::get_lines();
+fn main() -> Result<(), common::InputError> {
+    let report = common::Report::new(2018, 5)?;
+    let input: Vec<String> = common::get_lines()?;
 
     for polymer in input {
         let result1 = react(&polymer);
-        println!("Polymer after reaction has size: {}", result1.len());
+        report.part(
+            1,
+            result1.len(),
+            format!("Polymer after reaction has size: {}", result1.len()),
+        );
         let result2 = shortest_with_deletion(&polymer);
-        println!("Shortest polymer after deletion and reaction has size: {}", result2.len());
+        report.part(
+            2,
+            result2.len(),
+            format!(
+                "Shortest polymer after deletion and reaction has size: {}",
+                result2.len()
+            ),
+        );
     }
+    Ok(())
 }
@@ -0,0 +1,91 @@
+#![allow(dead_code)]
+use super::report::json_string;
+use super::InputError;
+
+/// Terminal recording in the asciicast v2 format, which consists of a
+/// header line followed by one line per output event, all in JSON.
+#[derive(Debug, Clone)]
+pub struct Recording {
+    width: usize,
+    height: usize,
+    title: Option<String>,
+    events: Vec<(f64, String)>,
+}
+
+impl Recording {
+    pub fn new(width: usize, height: usize) -> Recording {
+        Recording {
+            width,
+            height,
+            title: None,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn with_title(self, title: &str) -> Recording {
+        Recording {
+            title: Some(title.to_string()),
+            ..self
+        }
+    }
+
+    /// Records output at the time in seconds since the start, which must
+    /// not be before the previous event.
+    pub fn output(&mut self, time: f64, data: &str) {
+        let last = self.events.last().map_or(0.0, |&(t, _)| t);
+        assert!(
+            time >= last,
+            "event at {} is before event at {}",
+            time,
+            last
+        );
+        self.events.push((time, data.to_string()));
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn encode(&self) -> String {
+        let mut s = format!(
+            "{{\"version\": 2, \"width\": {}, \"height\": {}",
+            self.width, self.height
+        );
+        if let Some(title) = &self.title {
+            s.push_str(&format!(", \"title\": {}", json_string(title)));
+        }
+        s.push_str("}\n");
+        for (time, data) in &self.events {
+            s.push_str(&format!("[{:.6}, \"o\", {}]\n", time, json_string(data)));
+        }
+        s
+    }
+
+    pub fn write(&self, filename: &str) -> Result<(), InputError> {
+        super::write_file(filename, self.encode().as_bytes())
+    }
+}
+
+/// Recording time in seconds after executing a number of instructions.
+pub fn instruction_time(steps: u64, steps_per_second: u64) -> f64 {
+    steps as f64 / steps_per_second as f64
+}
+
+/// Instructions per second of recording time from the `--cast-speed` option.
+pub fn speed_from_args(default: u64) -> Result<u64, InputError> {
+    match super::option("cast-speed") {
+        None => Ok(default),
+        Some(s) => match s.parse() {
+            Ok(speed) if speed > 0 => Ok(speed),
+            _ => Err(InputError::spanning(
+                &s,
+                &s,
+                "speed must be a positive number of instructions per second",
+            )),
+        },
+    }
+}
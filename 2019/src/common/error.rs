@@ -0,0 +1,282 @@
+#![allow(dead_code)]
+use std::fmt;
+use std::num::ParseIntError;
+
+/// Error for malformed puzzle input.
+///
+/// Besides the message, the error remembers the text that was being parsed
+/// together with the offending span inside of it. Parsers create it for the
+/// string they got, callers relocate it into the enclosing string with
+/// `within`, and the input functions finally attach line number and file.
+#[derive(PartialEq, Eq, Clone)]
+pub struct InputError {
+    message: String,
+    file: Option<String>,
+    text: String,
+    first_line: Option<usize>,
+    offset: usize,
+    len: usize,
+}
+
+// byte offset of inner in outer, if inner is a slice of outer
+fn offset_in(outer: &str, inner: &str) -> Option<usize> {
+    let outer_start = outer.as_ptr() as usize;
+    let inner_start = inner.as_ptr() as usize;
+    if inner_start >= outer_start && inner_start + inner.len() <= outer_start + outer.len() {
+        Some(inner_start - outer_start)
+    } else {
+        None
+    }
+}
+
+impl InputError {
+    /// Creates an error without any location.
+    pub fn new<S: Into<String>>(message: S) -> InputError {
+        InputError {
+            message: message.into(),
+            file: None,
+            text: String::new(),
+            first_line: None,
+            offset: 0,
+            len: 0,
+        }
+    }
+
+    /// Creates an error for `len` bytes at byte `offset` of `text`.
+    pub fn at<S: Into<String>>(text: &str, offset: usize, len: usize, message: S) -> InputError {
+        let offset = std::cmp::min(offset, text.len());
+        InputError {
+            text: text.to_string(),
+            offset,
+            len: std::cmp::min(len, text.len() - offset),
+            ..InputError::new(message)
+        }
+    }
+
+    /// Creates an error for `span`, which has to be a slice of `text`.
+    /// If it is not, the whole text is marked.
+    pub fn spanning<S: Into<String>>(text: &str, span: &str, message: S) -> InputError {
+        match offset_in(text, span) {
+            Some(offset) => InputError::at(text, offset, span.len(), message),
+            None => InputError::at(text, 0, text.len(), message),
+        }
+    }
+
+    /// Moves an error produced while parsing `inner` into `outer`,
+    /// of which `inner` has to be a slice. Errors without location
+    /// are attributed to the whole of `inner`.
+    pub fn within(self, outer: &str, inner: &str) -> InputError {
+        if self.text.is_empty() {
+            InputError::spanning(outer, inner, self.message)
+        } else {
+            let shift = offset_in(outer, inner).unwrap_or(0);
+            InputError {
+                text: outer.to_string(),
+                offset: self.offset + shift,
+                ..self
+            }
+        }
+    }
+
+    /// Sets the line number of the first line of the parsed text.
+    pub fn on_line(self, line: usize) -> InputError {
+        InputError {
+            first_line: Some(line),
+            ..self
+        }
+    }
+
+    /// Sets the file the parsed text was read from.
+    pub fn in_file(self, file: &str) -> InputError {
+        InputError {
+            file: Some(file.to_string()),
+            ..self
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    // start of the line containing the offending span
+    fn line_start(&self) -> usize {
+        self.text[..self.offset].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    /// Line number of the offending span, counted from 1.
+    pub fn line(&self) -> Option<usize> {
+        if self.text.is_empty() {
+            self.first_line
+        } else {
+            let newlines = self.text[..self.offset].matches('\n').count();
+            Some(self.first_line.unwrap_or(1) + newlines)
+        }
+    }
+
+    /// Column of the offending span, counted in characters from 1.
+    pub fn column(&self) -> Option<usize> {
+        if self.text.is_empty() {
+            None
+        } else {
+            Some(self.text[self.line_start()..self.offset].chars().count() + 1)
+        }
+    }
+
+    /// The line containing the offending span.
+    pub fn line_text(&self) -> &str {
+        let start = self.line_start();
+        let end = self.text[start..]
+            .find('\n')
+            .map_or(self.text.len(), |i| start + i);
+        self.text[start..end].trim_end_matches('\r')
+    }
+}
+
+impl From<ParseIntError> for InputError {
+    fn from(err: ParseIntError) -> InputError {
+        InputError::new(err.to_string())
+    }
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.message)?;
+        let file = self.file().unwrap_or("<input>");
+        match (self.line(), self.column()) {
+            (Some(line), Some(column)) => {
+                let line_text = self.line_text();
+                let number = line.to_string();
+                let gutter = " ".repeat(number.len());
+                let before = self.text[self.line_start()..self.offset].chars().count();
+                let marked = self.text[self.offset..self.offset + self.len]
+                    .lines()
+                    .next()
+                    .map_or(0, |s| s.chars().count());
+                writeln!(f, "{}--> {}:{}:{}", gutter, file, line, column)?;
+                writeln!(f, "{} |", gutter)?;
+                writeln!(f, "{} | {}", number, line_text)?;
+                write!(
+                    f,
+                    "{} | {}{}",
+                    gutter,
+                    " ".repeat(before),
+                    "^".repeat(std::cmp::max(marked, 1))
+                )
+            }
+            (Some(line), None) => write!(f, " --> {}:{}", file, line),
+            _ => write!(f, " --> {}", file),
+        }
+    }
+}
+
+// main functions print errors with Debug, so render the full report there
+impl fmt::Debug for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for InputError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_without_location() {
+        let error = InputError::new("no rows");
+        assert_eq!(error.message(), "no rows");
+        assert_eq!(
+            (error.line(), error.column(), error.file()),
+            (None, None, None)
+        );
+        assert_eq!(error.to_string(), "no rows\n --> <input>");
+        let error = error.on_line(3);
+        assert_eq!(error.to_string(), "no rows\n --> <input>:3");
+        let error = error.in_file("3.in");
+        assert_eq!(error.file(), Some("3.in"));
+        assert_eq!(error.to_string(), "no rows\n --> 3.in:3");
+        assert_eq!(format!("{:?}", error), error.to_string());
+    }
+
+    #[test]
+    fn test_report() {
+        let text = "1,2,x";
+        let error = InputError::spanning(text, &text[4..], "could not parse number");
+        assert_eq!((error.line(), error.column()), (Some(1), Some(5)));
+        assert_eq!(
+            error.to_string(),
+            "could not parse number\n --> <input>:1:5\n  |\n1 | 1,2,x\n  |     ^"
+        );
+        let error = error.on_line(12).in_file("2.in");
+        assert_eq!(
+            error.to_string(),
+            "could not parse number\n  --> 2.in:12:5\n   |\n12 | 1,2,x\n   |     ^"
+        );
+    }
+
+    #[test]
+    fn test_multi_line() {
+        let text = "a\nbb cc\r\nd";
+        let error = InputError::spanning(text, &text[5..10], "bad span");
+        assert_eq!((error.line(), error.column()), (Some(2), Some(4)));
+        assert_eq!(error.line_text(), "bb cc");
+        // only the part on the first line is marked
+        assert_eq!(
+            error.to_string(),
+            "bad span\n --> <input>:2:4\n  |\n2 | bb cc\n  |    ^^"
+        );
+        let error = InputError::at(text, text.len(), 0, "unexpected end");
+        assert_eq!((error.line(), error.column()), (Some(3), Some(2)));
+        assert_eq!(error.line_text(), "d");
+        assert!(error.to_string().ends_with("3 | d\n  |  ^"));
+    }
+
+    #[test]
+    fn test_at() {
+        let error = InputError::at("é,x", 3, 1, "bad");
+        assert_eq!(error.column(), Some(3));
+        assert!(error.to_string().ends_with("1 | é,x\n  |   ^"));
+        // offset and length are clamped to the text
+        let error = InputError::at("abc", 10, 5, "bad");
+        assert_eq!(error.column(), Some(4));
+        assert!(error.to_string().ends_with("1 | abc\n  |    ^"));
+        let error = InputError::at("abc", 1, 5, "bad");
+        assert!(error.to_string().ends_with("1 | abc\n  |  ^^"));
+    }
+
+    #[test]
+    fn test_spanning_other_string() {
+        let other = String::from("x");
+        let error = InputError::spanning("abc", &other, "bad");
+        assert_eq!(error.column(), Some(1));
+        assert!(error.to_string().ends_with("1 | abc\n  | ^^^"));
+    }
+
+    #[test]
+    fn test_within() {
+        let outer = "a\nbb cc\nd";
+        let inner = &outer[5..7];
+        let error = InputError::at(inner, 1, 1, "bad c").within(outer, inner);
+        assert_eq!((error.line(), error.column()), (Some(2), Some(5)));
+        assert!(error.to_string().ends_with("2 | bb cc\n  |     ^"));
+        // errors without location mark all of the inner string
+        let error = InputError::new("bad cc").within(outer, inner);
+        assert_eq!((error.line(), error.column()), (Some(2), Some(4)));
+        assert!(error.to_string().ends_with("2 | bb cc\n  |    ^^"));
+        let error = error.on_line(5).in_file("4.in");
+        assert_eq!(error.line(), Some(6));
+        assert!(error.to_string().contains(" --> 4.in:6:4\n"));
+    }
+
+    #[test]
+    fn test_from_parse_int_error() {
+        let error: InputError = "x".parse::<i64>().unwrap_err().into();
+        assert_eq!(error.message(), "invalid digit found in string");
+        assert_eq!(error.line(), None);
+    }
+}
@@ -0,0 +1,332 @@
+#![allow(dead_code)]
+use super::geometry::Point2D;
+use super::grid::Grid;
+use super::InputError;
+use std::fmt::Write;
+use std::str::FromStr;
+
+pub type Rgb = [u8; 3];
+
+pub const BLACK: Rgb = [0x00, 0x00, 0x00];
+pub const WHITE: Rgb = [0xff, 0xff, 0xff];
+
+/// Colours indexed by the values of the cells of a grid.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Palette(pub Vec<Rgb>);
+
+impl Palette {
+    pub fn colour(&self, index: usize) -> Rgb {
+        *self
+            .0
+            .get(index)
+            .unwrap_or_else(|| panic!("palette has no colour {}", index))
+    }
+}
+
+/// Parses comma separated colours in hexadecimal notation, like `#000000,#ffcc00`.
+impl FromStr for Palette {
+    type Err = InputError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(|c| {
+                let hex = c.trim().trim_start_matches('#');
+                let value = u32::from_str_radix(hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == 6)
+                    .ok_or_else(|| InputError::spanning(s, c, "could not parse colour"))?;
+                Ok([(value >> 16) as u8, (value >> 8) as u8, value as u8])
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Palette)
+    }
+}
+
+/// Image file formats, chosen by the extension of the file name.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ImageFormat {
+    Ppm,
+    Pgm,
+    Png,
+    Svg,
+}
+
+impl ImageFormat {
+    pub fn from_filename(filename: &str) -> Option<ImageFormat> {
+        let extension = filename.rsplit_once('.')?.1.to_ascii_lowercase();
+        match extension.as_str() {
+            "ppm" => Some(ImageFormat::Ppm),
+            "pgm" => Some(ImageFormat::Pgm),
+            "png" => Some(ImageFormat::Png),
+            "svg" => Some(ImageFormat::Svg),
+            _ => None,
+        }
+    }
+}
+
+/// Grid of colours, where every cell becomes a square of `scale` pixels.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Raster {
+    width: usize,
+    height: usize,
+    scale: usize,
+    cells: Vec<Rgb>,
+}
+
+// CRC-32 as used by PNG chunks
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, &b| {
+        (0..8).fold(crc ^ u32::from(b), |c, _| {
+            if c & 1 == 1 {
+                (c >> 1) ^ 0xedb8_8320
+            } else {
+                c >> 1
+            }
+        })
+    })
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (a, b) = bytes.iter().fold((1, 0), |(a, b), &x| {
+        let a = (a + u32::from(x)) % 65521;
+        (a, (b + a) % 65521)
+    });
+    (b << 16) | a
+}
+
+// zlib stream with the data in uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(0xffff).peekable();
+    if blocks.peek().is_none() {
+        out.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+        out.push(if blocks.peek().is_none() { 1 } else { 0 });
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend(block);
+    }
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+// perceived brightness of a colour, as in ITU-R BT.601
+fn luma(c: Rgb) -> u8 {
+    ((299 * c[0] as u32 + 587 * c[1] as u32 + 114 * c[2] as u32) / 1000) as u8
+}
+
+impl Raster {
+    pub fn from_fn<F: Fn(Point2D) -> Rgb>(
+        width: usize,
+        height: usize,
+        scale: usize,
+        colour: F,
+    ) -> Raster {
+        let mut cells = Vec::with_capacity(width * height);
+        for y in 0..height as i64 {
+            for x in 0..width as i64 {
+                cells.push(colour(Point2D { x, y }));
+            }
+        }
+        Raster {
+            width,
+            height,
+            scale: scale.max(1),
+            cells,
+        }
+    }
+
+    pub fn from_grid<T, F: Fn(&T) -> Rgb>(grid: &Grid<T>, scale: usize, colour: F) -> Raster {
+        Raster::from_fn(grid.width(), grid.height(), scale, |p| colour(&grid[p]))
+    }
+
+    /// Width of the image in pixels.
+    pub fn width(&self) -> usize {
+        self.width * self.scale
+    }
+
+    /// Height of the image in pixels.
+    pub fn height(&self) -> usize {
+        self.height * self.scale
+    }
+
+    // pixel rows of the image, with each cell repeated scale times
+    fn pixel_rows(&self) -> impl Iterator<Item = Vec<Rgb>> + '_ {
+        self.cells.chunks(self.width.max(1)).flat_map(move |row| {
+            let pixels: Vec<Rgb> = row
+                .iter()
+                .flat_map(|&c| std::iter::repeat_n(c, self.scale))
+                .collect();
+            std::iter::repeat_n(pixels, self.scale)
+        })
+    }
+
+    /// Binary portable pixmap (P6).
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut data = format!("P6\n{} {}\n255\n", self.width(), self.height()).into_bytes();
+        for row in self.pixel_rows() {
+            data.extend(row.iter().flatten());
+        }
+        data
+    }
+
+    /// Binary portable graymap (P5) with the brightness of the colours.
+    pub fn to_pgm(&self) -> Vec<u8> {
+        let mut data = format!("P5\n{} {}\n255\n", self.width(), self.height()).into_bytes();
+        for row in self.pixel_rows() {
+            data.extend(row.iter().map(|&c| luma(c)));
+        }
+        data
+    }
+
+    /// Portable network graphic, stored without compression so that it
+    /// needs no external crate.
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        let mut header = Vec::with_capacity(13);
+        header.extend((self.width() as u32).to_be_bytes());
+        header.extend((self.height() as u32).to_be_bytes());
+        // 8 bits per channel, RGB, no interlacing
+        header.extend([8, 2, 0, 0, 0]);
+        png_chunk(&mut png, b"IHDR", &header);
+        let mut data = Vec::with_capacity((3 * self.width() + 1) * self.height());
+        for row in self.pixel_rows() {
+            // no filter
+            data.push(0);
+            data.extend(row.iter().flatten());
+        }
+        png_chunk(&mut png, b"IDAT", &zlib_stored(&data));
+        png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    /// Scalable vector graphic with one rectangle per run of equal cells in a row.
+    pub fn to_svg(&self) -> String {
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+             viewBox=\"0 0 {0} {1}\" shape-rendering=\"crispEdges\">",
+            self.width(),
+            self.height()
+        )
+        .unwrap();
+        for (y, row) in self.cells.chunks(self.width.max(1)).enumerate() {
+            let mut x = 0;
+            while x < row.len() {
+                let c = row[x];
+                let run = row[x..].iter().take_while(|&&d| d == c).count();
+                writeln!(
+                    svg,
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#{:02x}{:02x}{:02x}\"/>",
+                    x * self.scale,
+                    y * self.scale,
+                    run * self.scale,
+                    self.scale,
+                    c[0],
+                    c[1],
+                    c[2]
+                )
+                .unwrap();
+                x += run;
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn encode(&self, format: ImageFormat) -> Vec<u8> {
+        match format {
+            ImageFormat::Ppm => self.to_ppm(),
+            ImageFormat::Pgm => self.to_pgm(),
+            ImageFormat::Png => self.to_png(),
+            ImageFormat::Svg => self.to_svg().into_bytes(),
+        }
+    }
+
+    /// Writes the image in the format given by the extension of the file name.
+    pub fn write(&self, filename: &str) -> Result<(), InputError> {
+        let format = ImageFormat::from_filename(filename).ok_or_else(|| {
+            InputError::new("unknown image format, expected .ppm, .pgm, .png or .svg")
+                .in_file(filename)
+        })?;
+        super::write_file(filename, &self.encode(format))
+    }
+}
+
+/// File name of a frame in a sequence, with its number before the extension.
+pub fn frame_filename(filename: &str, frame: usize) -> String {
+    match filename.rsplit_once('.') {
+        Some((stem, extension)) => format!("{}-{:05}.{}", stem, frame, extension),
+        None => format!("{}-{:05}", filename, frame),
+    }
+}
+
+/// Scale and palette for images, from the `--scale` and `--palette` options.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Style {
+    pub scale: usize,
+    pub palette: Palette,
+}
+
+impl Style {
+    pub const DEFAULT_SCALE: usize = 10;
+
+    pub fn from_args(default_palette: Palette) -> Result<Style, InputError> {
+        let scale = match super::option("scale") {
+            None => Style::DEFAULT_SCALE,
+            Some(s) => match s.parse() {
+                Ok(scale) if scale > 0 => scale,
+                _ => {
+                    return Err(InputError::spanning(
+                        &s,
+                        &s,
+                        "scale must be a positive number",
+                    ))
+                }
+            },
+        };
+        let palette = match super::option("palette") {
+            None => default_palette,
+            Some(p) => {
+                let palette: Palette = p.parse()?;
+                if palette.0.len() < default_palette.0.len() {
+                    return Err(InputError::spanning(
+                        &p,
+                        &p,
+                        format!("palette needs {} colours", default_palette.0.len()),
+                    ));
+                }
+                palette
+            }
+        };
+        Ok(Style { scale, palette })
+    }
+
+    /// Image of the grid, where `index` gives the palette index of a cell,
+    /// or an error if a cell has no colour in the palette.
+    pub fn render<T, F: Fn(&T) -> usize>(
+        &self,
+        grid: &Grid<T>,
+        index: F,
+    ) -> Result<Raster, InputError> {
+        let colours = self.palette.0.len();
+        if let Some(i) = grid.values().map(&index).find(|&i| i >= colours) {
+            return Err(InputError::new(format!("palette has no colour {}", i)));
+        }
+        Ok(Raster::from_grid(grid, self.scale, |t| {
+            self.palette.colour(index(t))
+        }))
+    }
+}
@@ -1,4 +1,6 @@
 #![allow(dead_code)]
+use std::collections::VecDeque;
+use std::convert::TryFrom;
 use std::sync::mpsc::Receiver;
 use std::sync::mpsc::Sender;
 
@@ -34,24 +36,123 @@ enum Mode {
     Relative,
 }
 
+/// Reason why a program can not continue, like an unknown opcode.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Fault(pub String);
+
+impl std::fmt::Display for Fault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// addresses beyond this are treated as faults instead of growing memory
+const MAX_ADDRESS: usize = 1 << 24;
+
+// result of executing a single instruction
+enum Step {
+    Continue,
+    Output(i64),
+    NeedsInput,
+    Halt,
+}
+
+#[derive(Clone)]
 struct ProgramState {
     mem: Vec<i64>,
     pointer: usize,
-    relative_base: usize,
+    relative_base: i64,
 }
 
 impl ProgramState {
-    fn from_program(program: &Program) -> ProgramState {
+    fn new(program: &[i64]) -> ProgramState {
         ProgramState {
-            mem: program.program.clone(),
+            mem: program.to_vec(),
             pointer: 0,
             relative_base: 0,
         }
     }
 
-    fn fetch_opcode(&self) -> OpCode {
-        let opcode = self.mem[self.pointer] % 100;
-        match opcode {
+    // executes the instruction at the pointer, where input gives the next
+    // input if there is one; the pointer stays at an input without one
+    fn step<F: FnMut() -> Option<i64>>(&mut self, mut input: F) -> Result<Step, Fault> {
+        match self.fetch_opcode()? {
+            OpCode::Arith(op) => {
+                let x = self.fetch_value(0)?;
+                let y = self.fetch_value(1)?;
+                let z = match op {
+                    Op::Add => x.checked_add(y),
+                    Op::Mul => x.checked_mul(y),
+                };
+                let z = z.ok_or_else(|| self.fault("arithmetic overflow"))?;
+                self.write_value(2, z)?;
+                self.increase_pointer(4);
+            }
+            OpCode::Input => match input() {
+                Some(i) => {
+                    self.write_value(0, i)?;
+                    self.increase_pointer(2);
+                }
+                None => return Ok(Step::NeedsInput),
+            },
+            OpCode::Output => {
+                let o = self.fetch_value(0)?;
+                self.increase_pointer(2);
+                return Ok(Step::Output(o));
+            }
+            OpCode::JumpIf(condition) => {
+                let x = self.fetch_value(0)?;
+                let matched = match condition {
+                    Cnd::True => x != 0,
+                    Cnd::False => x == 0,
+                };
+                if matched {
+                    let y = self.fetch_value(1)?;
+                    let y = self.address(y)?;
+                    self.set_pointer(y);
+                } else {
+                    self.increase_pointer(3);
+                }
+            }
+            OpCode::Compare(comparison) => {
+                let x = self.fetch_value(0)?;
+                let y = self.fetch_value(1)?;
+                let result = match comparison {
+                    Cmp::LessThan => x < y,
+                    Cmp::Equal => x == y,
+                };
+                self.write_value(2, result as i64)?;
+                self.increase_pointer(4);
+            }
+            OpCode::AdjustRelativeBase => {
+                let x = self.fetch_value(0)?;
+                self.increase_relative_base(x)?;
+                self.increase_pointer(2);
+            }
+            OpCode::Halt => return Ok(Step::Halt),
+        }
+        Ok(Step::Continue)
+    }
+
+    fn fault(&self, message: &str) -> Fault {
+        Fault(format!("{} at position {}", message, self.pointer))
+    }
+
+    // memory cell at the position, where cells beyond the memory are 0
+    fn read(&self, position: usize) -> i64 {
+        self.mem.get(position).copied().unwrap_or(0)
+    }
+
+    fn address(&self, value: i64) -> Result<usize, Fault> {
+        usize::try_from(value)
+            .ok()
+            .filter(|&a| a <= MAX_ADDRESS)
+            .ok_or_else(|| self.fault(&format!("invalid address {}", value)))
+    }
+
+    fn fetch_opcode(&self) -> Result<OpCode, Fault> {
+        let opcode = self.read(self.pointer) % 100;
+        Ok(match opcode {
             1 => OpCode::Arith(Op::Add),
             2 => OpCode::Arith(Op::Mul),
             3 => OpCode::Input,
@@ -62,17 +163,17 @@ impl ProgramState {
             8 => OpCode::Compare(Cmp::Equal),
             9 => OpCode::AdjustRelativeBase,
             99 => OpCode::Halt,
-            _ => panic!("unknown opcode"),
-        }
+            _ => return Err(self.fault(&format!("unknown opcode {}", opcode))),
+        })
     }
 
-    fn fetch_mode(&self, arg: usize) -> Mode {
-        let mode = (self.mem[self.pointer] / 10i64.pow(arg as u32 + 2)) % 10;
+    fn fetch_mode(&self, arg: usize) -> Result<Mode, Fault> {
+        let mode = (self.read(self.pointer) / 10i64.pow(arg as u32 + 2)) % 10;
         match mode {
-            0 => Mode::Position,
-            1 => Mode::Immediate,
-            2 => Mode::Relative,
-            _ => panic!("unknown mode"),
+            0 => Ok(Mode::Position),
+            1 => Ok(Mode::Immediate),
+            2 => Ok(Mode::Relative),
+            _ => Err(self.fault(&format!("unknown mode {}", mode))),
         }
     }
 
@@ -84,8 +185,12 @@ impl ProgramState {
         self.pointer = position;
     }
 
-    fn increase_relative_base(&mut self, adjustment: i64) {
-        self.relative_base = (self.relative_base as i64 + adjustment) as usize;
+    fn increase_relative_base(&mut self, adjustment: i64) -> Result<(), Fault> {
+        self.relative_base = self
+            .relative_base
+            .checked_add(adjustment)
+            .ok_or_else(|| self.fault("relative base overflows"))?;
+        Ok(())
     }
 
     fn ensure_memory_available(&mut self, position: usize) {
@@ -94,26 +199,37 @@ impl ProgramState {
         }
     }
 
-    fn fetch_position(&mut self, arg: usize) -> usize {
-        let mode = self.fetch_mode(arg);
+    fn fetch_position(&mut self, arg: usize) -> Result<usize, Fault> {
+        let mode = self.fetch_mode(arg)?;
         let base = self.pointer + arg + 1;
         let position = match mode {
-            Mode::Position => self.mem[base] as usize,
+            Mode::Position => self.address(self.read(base))?,
             Mode::Immediate => base,
-            Mode::Relative => (self.relative_base as i64 + self.mem[base]) as usize,
+            Mode::Relative => {
+                let offset = self.read(base);
+                let address = self.relative_base.checked_add(offset).ok_or_else(|| {
+                    let message = format!(
+                        "relative address {} + {} overflows",
+                        self.relative_base, offset
+                    );
+                    self.fault(&message)
+                })?;
+                self.address(address)?
+            }
         };
         self.ensure_memory_available(position);
-        position
+        Ok(position)
     }
 
-    fn fetch_value(&mut self, arg: usize) -> i64 {
-        let position = self.fetch_position(arg);
-        self.mem[position]
+    fn fetch_value(&mut self, arg: usize) -> Result<i64, Fault> {
+        let position = self.fetch_position(arg)?;
+        Ok(self.mem[position])
     }
 
-    fn write_value(&mut self, arg: usize, value: i64) {
-        let position = self.fetch_position(arg);
+    fn write_value(&mut self, arg: usize, value: i64) -> Result<(), Fault> {
+        let position = self.fetch_position(arg)?;
         self.mem[position] = value;
+        Ok(())
     }
 }
 
@@ -147,8 +263,8 @@ impl ProgramIO {
     pub fn next_output(&self) -> Option<i64> {
         self.or.recv().expect("could not receive output")
     }
-    pub fn output_iter(&self) -> OutputIterator {
-        OutputIterator { io: &self }
+    pub fn output_iter(&self) -> OutputIterator<'_> {
+        OutputIterator { io: self }
     }
     pub fn collect_outputs(&self) -> Vec<i64> {
         self.output_iter().collect()
@@ -171,58 +287,14 @@ impl Program {
     }
 
     pub fn run(&self) {
-        let mut state = ProgramState::from_program(&self);
+        let mut state = ProgramState::new(&self.program);
         loop {
-            match state.fetch_opcode() {
-                OpCode::Arith(op) => {
-                    let x = state.fetch_value(0);
-                    let y = state.fetch_value(1);
-                    let z = match op {
-                        Op::Add => x + y,
-                        Op::Mul => x * y,
-                    };
-                    state.write_value(2, z);
-                    state.increase_pointer(4);
-                }
-                OpCode::Input => {
-                    let i = self.ir.recv().unwrap();
-                    state.write_value(0, i);
-                    state.increase_pointer(2);
-                }
-                OpCode::Output => {
-                    let o = state.fetch_value(0);
-                    self.os.send(Some(o)).unwrap();
-                    state.increase_pointer(2);
-                }
-                OpCode::JumpIf(condition) => {
-                    let x = state.fetch_value(0);
-                    let matched = match condition {
-                        Cnd::True => x != 0,
-                        Cnd::False => x == 0,
-                    };
-                    if matched {
-                        let y = state.fetch_value(1);
-                        state.set_pointer(y as usize);
-                    } else {
-                        state.increase_pointer(3);
-                    }
-                }
-                OpCode::Compare(comparison) => {
-                    let x = state.fetch_value(0);
-                    let y = state.fetch_value(1);
-                    let result = match comparison {
-                        Cmp::LessThan => x < y,
-                        Cmp::Equal => x == y,
-                    };
-                    state.write_value(2, result as i64);
-                    state.increase_pointer(4);
-                }
-                OpCode::AdjustRelativeBase => {
-                    let x = state.fetch_value(0);
-                    state.increase_relative_base(x);
-                    state.increase_pointer(2);
-                }
-                OpCode::Halt => {
+            let step = state.step(|| Some(self.ir.recv().unwrap()));
+            match step.unwrap_or_else(|fault| panic!("{}", fault)) {
+                Step::Continue => (),
+                Step::Output(o) => self.os.send(Some(o)).unwrap(),
+                Step::NeedsInput => unreachable!("input is always available"),
+                Step::Halt => {
                     self.os.send(None).expect("could not send halt output");
                     break;
                 }
@@ -253,4 +325,175 @@ impl ProgramRunner {
         self.program.run();
         self.io.collect_outputs()
     }
-}
\ No newline at end of file
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Event {
+    Output(i64),
+    NeedsInput,
+    Halted,
+}
+
+/// Intcode computer running in the calling thread, which returns control
+/// whenever it produces output, waits for input or halts. It can then be
+/// resumed, or cloned to take a snapshot of its complete state.
+#[derive(Clone)]
+pub struct Machine {
+    state: ProgramState,
+    inputs: VecDeque<i64>,
+    steps: u64,
+    halted: bool,
+}
+
+impl Machine {
+    pub fn new(program: &[i64]) -> Machine {
+        Machine {
+            state: ProgramState::new(program),
+            inputs: VecDeque::new(),
+            steps: 0,
+            halted: false,
+        }
+    }
+
+    pub fn push_input(&mut self, i: i64) {
+        self.inputs.push_back(i);
+    }
+
+    /// Number of instructions executed so far.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Runs until the next event, consuming the pushed inputs as needed.
+    pub fn run(&mut self) -> Event {
+        self.try_run().unwrap_or_else(|fault| panic!("{}", fault))
+    }
+
+    /// Like `run`, but returns a fault instead of panicking. The machine
+    /// counts as halted after a fault.
+    pub fn try_run(&mut self) -> Result<Event, Fault> {
+        if self.halted {
+            return Ok(Event::Halted);
+        }
+        loop {
+            let inputs = &mut self.inputs;
+            let step = self.state.step(|| inputs.pop_front());
+            if step.is_err() {
+                self.halted = true;
+            }
+            match step? {
+                Step::Continue => self.steps += 1,
+                Step::Output(o) => {
+                    self.steps += 1;
+                    return Ok(Event::Output(o));
+                }
+                Step::NeedsInput => return Ok(Event::NeedsInput),
+                Step::Halt => {
+                    self.halted = true;
+                    return Ok(Event::Halted);
+                }
+            }
+        }
+    }
+
+    /// Runs until the machine halts or needs input that was not pushed,
+    /// returning the outputs produced on the way.
+    pub fn run_to_input(&mut self) -> Vec<i64> {
+        self.try_run_to_input()
+            .unwrap_or_else(|fault| panic!("{}", fault))
+    }
+
+    /// Like `run_to_input`, but returns a fault instead of panicking.
+    pub fn try_run_to_input(&mut self) -> Result<Vec<i64>, Fault> {
+        let mut outputs = Vec::new();
+        while let Event::Output(o) = self.try_run()? {
+            outputs.push(o);
+        }
+        Ok(outputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fault(program: &[i64]) -> Fault {
+        let mut machine = Machine::new(program);
+        let fault = machine.try_run().unwrap_err();
+        assert!(machine.is_halted());
+        assert_eq!(machine.try_run(), Ok(Event::Halted));
+        fault
+    }
+
+    #[test]
+    fn test_try_run() {
+        let mut machine = Machine::new(&[3, 0, 4, 0, 99]);
+        assert_eq!(machine.try_run(), Ok(Event::NeedsInput));
+        machine.push_input(7);
+        assert_eq!(machine.try_run(), Ok(Event::Output(7)));
+        assert_eq!(machine.try_run(), Ok(Event::Halted));
+        assert!(machine.is_halted());
+        assert_eq!(machine.steps(), 2);
+
+        let mut machine = Machine::new(&[3, 0, 4, 0, 4, 0, 3, 0, 99]);
+        machine.push_input(5);
+        assert_eq!(machine.try_run_to_input(), Ok(vec![5, 5]));
+        assert!(!machine.is_halted());
+        let mut machine = Machine::new(&[4, 0, 42]);
+        assert_eq!(
+            machine.try_run_to_input(),
+            Err(Fault("unknown opcode 42 at position 2".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_faults() {
+        assert_eq!(fault(&[42]).to_string(), "unknown opcode 42 at position 0");
+        assert_eq!(fault(&[301, 0, 0, 0]).0, "unknown mode 3 at position 0");
+        assert_eq!(
+            fault(&[1002, 5, 2, 5, 99, i64::MAX]).0,
+            "arithmetic overflow at position 0"
+        );
+        assert_eq!(fault(&[4, -1, 99]).0, "invalid address -1 at position 0");
+        assert_eq!(fault(&[1105, 1, -3]).0, "invalid address -3 at position 0");
+    }
+
+    #[test]
+    fn test_address_limit() {
+        let limit = MAX_ADDRESS as i64;
+        assert_eq!(
+            fault(&[1, 0, 0, limit + 1, 99]).0,
+            format!("invalid address {} at position 0", limit + 1)
+        );
+        // reading beyond the memory gives 0 without growing it
+        let mut machine = Machine::new(&[4, 1000, 99]);
+        assert_eq!(machine.try_run(), Ok(Event::Output(0)));
+        assert_eq!(machine.try_run(), Ok(Event::Halted));
+    }
+
+    #[test]
+    fn test_relative_overflow() {
+        assert_eq!(
+            fault(&[109, i64::MAX, 204, 1, 99]).0,
+            format!("relative address {} + 1 overflows at position 2", i64::MAX)
+        );
+        assert_eq!(
+            fault(&[109, i64::MAX, 109, 1, 99]).0,
+            "relative base overflows at position 2"
+        );
+        assert_eq!(
+            fault(&[109, -5, 204, 1, 99]).0,
+            "invalid address -4 at position 2"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown opcode 42 at position 0")]
+    fn test_run_panics() {
+        Machine::new(&[42]).run();
+    }
+}
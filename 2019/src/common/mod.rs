@@ -1,26 +1,120 @@
 use std::env;
-use std::io::BufRead;
 use std::fs;
+use std::str::FromStr;
+pub mod asciicast;
+mod error;
+pub mod geometry;
+pub mod graph;
+pub mod grid;
+pub mod image;
 pub mod intcode;
+pub mod ocr;
+mod report;
+pub mod terminal;
+pub use error::InputError;
+#[allow(unused_imports)]
+pub use report::{Format, Report};
 
 fn get_filename() -> String {
     env::args().nth(1).expect("no filename given")
 }
 
+// options follow the filename, as `--name value` or `--name=value`
 #[allow(dead_code)]
-pub fn get_content() -> String {
-    let filename = get_filename();
-    fs::read_to_string(filename).expect("could not read file")
+pub fn option(name: &str) -> Option<String> {
+    let flag = format!("--{}", name);
+    let mut args = env::args().skip(2);
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return Some(
+                args.next()
+                    .unwrap_or_else(|| panic!("no value given for {}", flag)),
+            );
+        } else if let Some(value) = arg.strip_prefix(&flag).and_then(|v| v.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+#[allow(dead_code)]
+pub fn flag(name: &str) -> bool {
+    let flag = format!("--{}", name);
+    env::args().skip(2).any(|arg| arg == flag)
 }
 
 #[allow(dead_code)]
-pub fn get_lines() -> Vec<String> {
+pub fn read_file(filename: &str) -> Result<String, InputError> {
+    fs::read_to_string(filename)
+        .map_err(|err| InputError::new(format!("could not read file: {}", err)).in_file(filename))
+}
+
+#[allow(dead_code)]
+pub fn get_content() -> Result<String, InputError> {
+    read_file(&get_filename())
+}
+
+#[allow(dead_code)]
+pub fn get_lines() -> Result<Vec<String>, InputError> {
+    let content = get_content()?;
+    Ok(content.lines().map(|l| l.to_string()).collect())
+}
+
+#[allow(dead_code)]
+pub fn write_file(filename: &str, content: &[u8]) -> Result<(), InputError> {
+    fs::write(filename, content)
+        .map_err(|err| InputError::new(format!("could not write file: {}", err)).in_file(filename))
+}
+
+// parses each line with f, locating errors in the input file
+fn parse_lines<T, F>(f: F) -> Result<Vec<T>, InputError>
+where
+    F: Fn(&str) -> Result<T, InputError>,
+{
     let filename = get_filename();
-    let file = std::fs::File::open(filename).expect("could not open file");
-    let buffer = std::io::BufReader::new(file);
+    get_lines()?
+        .iter()
+        .enumerate()
+        .map(|(i, l)| f(l).map_err(|e| e.on_line(i + 1).in_file(&filename)))
+        .collect()
+}
+
+#[allow(dead_code)]
+pub fn get_input<T: FromStr>() -> Result<Vec<T>, InputError>
+where
+    <T as FromStr>::Err: Into<InputError>,
+{
+    parse_lines(|l| l.parse::<T>().map_err(|e| e.into().within(l, l)))
+}
+
+#[allow(dead_code)]
+pub fn get_separated_input<T: FromStr>(separator: char) -> Result<Vec<Vec<T>>, InputError>
+where
+    <T as FromStr>::Err: Into<InputError>,
+{
+    parse_lines(|l| parse_separated(l, separator))
+}
 
-    buffer
-        .lines()
-        .map(|l| l.expect("could not read line"))
+#[allow(dead_code)]
+pub fn parse_separated<T: FromStr>(s: &str, separator: char) -> Result<Vec<T>, InputError>
+where
+    <T as FromStr>::Err: Into<InputError>,
+{
+    s.split(separator)
+        .map(|i| i.parse::<T>().map_err(|e| e.into().within(s, i)))
         .collect()
 }
+
+#[allow(dead_code)]
+pub fn parse_content_with<T, F>(f: F) -> Result<T, InputError>
+where
+    F: Fn(&str) -> Result<T, InputError>,
+{
+    let content = get_content()?;
+    f(&content).map_err(|e| e.in_file(&get_filename()))
+}
+
+#[allow(dead_code)]
+pub fn parse_content<T: FromStr<Err = InputError>>() -> Result<T, InputError> {
+    parse_content_with(|s| s.parse::<T>())
+}
@@ -0,0 +1,189 @@
+#![allow(dead_code)]
+use super::geometry::Point2D;
+use super::grid::Grid;
+use std::fmt;
+
+/// Height of the letters, which are at most 5 and mostly 4 pixels wide.
+pub const HEIGHT: usize = 6;
+
+/// Width of the cell of a letter, including the space after it. Letters as
+/// wide as the cell touch the next one.
+pub const CELL_WIDTH: usize = 5;
+
+// letters of the puzzle font, with the rows separated by newlines
+const FONT: [(char, &str); 18] = [
+    ('A', ".##.\n#..#\n#..#\n####\n#..#\n#..#"),
+    ('B', "###.\n#..#\n###.\n#..#\n#..#\n###."),
+    ('C', ".##.\n#..#\n#...\n#...\n#..#\n.##."),
+    ('E', "####\n#...\n###.\n#...\n#...\n####"),
+    ('F', "####\n#...\n###.\n#...\n#...\n#..."),
+    ('G', ".##.\n#..#\n#...\n#.##\n#..#\n.###"),
+    ('H', "#..#\n#..#\n####\n#..#\n#..#\n#..#"),
+    ('I', "###\n.#.\n.#.\n.#.\n.#.\n###"),
+    ('J', "..##\n...#\n...#\n...#\n#..#\n.##."),
+    ('K', "#..#\n#.#.\n##..\n#.#.\n#.#.\n#..#"),
+    ('L', "#...\n#...\n#...\n#...\n#...\n####"),
+    ('O', ".##.\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('P', "###.\n#..#\n#..#\n###.\n#...\n#..."),
+    ('R', "###.\n#..#\n#..#\n###.\n#.#.\n#..#"),
+    ('S', ".###\n#...\n#...\n.##.\n...#\n###."),
+    ('U', "#..#\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('Y', "#...#\n#...#\n.#.#.\n..#..\n..#..\n..#.."),
+    ('Z', "####\n...#\n..#.\n.#..\n#...\n####"),
+];
+
+/// Glyph that is not a letter of the font, with the position of its top
+/// left corner in the grid and its pixels in the notation of the font.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct UnknownGlyph {
+    pub position: Point2D,
+    pub pixels: String,
+}
+
+/// Error for text that could not be read completely.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum OcrError {
+    /// No lit pixels at all.
+    Empty,
+    /// The lit pixels do not span the height of the font.
+    Height(usize),
+    /// The text read so far with `?` for each unknown glyph, and the unknown glyphs.
+    Unknown(String, Vec<UnknownGlyph>),
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OcrError::Empty => write!(f, "no letters found"),
+            OcrError::Height(h) => write!(f, "letters have height {} instead of {}", h, HEIGHT),
+            OcrError::Unknown(text, glyphs) => {
+                write!(f, "could not read all letters of {}", text)?;
+                for g in glyphs {
+                    write!(f, "\nunknown glyph at {}:\n{}", g.position, g.pixels)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Reads the letters in the grid, where `lit` tells which cells belong to
+/// the letters. Letters are separated by columns without lit cells, and
+/// runs of lit columns wider than a cell are split into cells.
+pub fn read<T, F: Fn(&T) -> bool>(grid: &Grid<T>, lit: F) -> Result<String, OcrError> {
+    let is_lit = |x: usize, y: usize| lit(&grid[Point2D::new(x as i64, y as i64)]);
+    let rows: Vec<usize> = (0..grid.height())
+        .filter(|&y| (0..grid.width()).any(|x| is_lit(x, y)))
+        .collect();
+    let (top, bottom) = match (rows.first(), rows.last()) {
+        (Some(&top), Some(&bottom)) => (top, bottom),
+        _ => return Err(OcrError::Empty),
+    };
+    if bottom - top + 1 != HEIGHT {
+        return Err(OcrError::Height(bottom - top + 1));
+    }
+    let column_lit = |x: usize| (top..=bottom).any(|y| is_lit(x, y));
+
+    let mut text = String::new();
+    let mut unknown = Vec::new();
+    let mut x = 0;
+    while x < grid.width() {
+        if !column_lit(x) {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < grid.width() && column_lit(x) {
+            x += 1;
+        }
+        let run = start..x;
+        let cell = if run.len() > CELL_WIDTH {
+            CELL_WIDTH
+        } else {
+            run.len()
+        };
+        for start in run.clone().step_by(cell) {
+            let end = (start + cell).min(run.end);
+            let pixels = (top..=bottom)
+                .map(|y| {
+                    (start..end)
+                        .map(|x| if is_lit(x, y) { '#' } else { '.' })
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            match FONT.iter().find(|&&(_, glyph)| glyph == pixels) {
+                Some(&(c, _)) => text.push(c),
+                None => {
+                    text.push('?');
+                    unknown.push(UnknownGlyph {
+                        position: Point2D::new(start as i64, top as i64),
+                        pixels,
+                    });
+                }
+            }
+        }
+    }
+    if unknown.is_empty() {
+        Ok(text)
+    } else {
+        Err(OcrError::Unknown(text, unknown))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // grid of the text, with rows separated by newlines
+    fn grid(text: &str) -> Grid<bool> {
+        let rows: Vec<&str> = text.lines().collect();
+        let mut grid = Grid::new(rows[0].len(), rows.len(), false);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                grid[Point2D::new(x as i64, y as i64)] = c == '#';
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn test_wide_letters() {
+        let text = "\
+#...##...
+#...##...
+.#.#.#...
+..#..#...
+..#..#...
+..#..####";
+        assert_eq!(read(&grid(text), |&p| p), Ok("YL".to_string()));
+        let text = "\
+#...##...#.##.
+#...##...##..#
+.#.#..#.#.#..#
+..#....#..#..#
+..#....#..#..#
+..#....#...##.";
+        assert_eq!(read(&grid(text), |&p| p), Ok("YYO".to_string()));
+    }
+
+    #[test]
+    fn test_unknown() {
+        let text = "\
+#....##.
+#...#..#
+#...#..#
+#...#..#
+#...#..#
+###..##.";
+        match read(&grid(text), |&p| p) {
+            Err(OcrError::Unknown(text, glyphs)) => {
+                assert_eq!(text, "?O");
+                assert_eq!(glyphs[0].position, Point2D::new(0, 0));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(read(&grid("...\n..."), |&p| p), Err(OcrError::Empty));
+        assert_eq!(read(&grid(".#.\n.#."), |&p| p), Err(OcrError::Height(2)));
+    }
+}
@@ -0,0 +1,104 @@
+#![allow(dead_code)]
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Escape,
+    /// Ctrl-C, which does not interrupt the program in raw mode.
+    Interrupt,
+    Char(char),
+}
+
+/// Parses the bytes read from a terminal into keys, ignoring unknown escape sequences.
+pub fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            0x1b if bytes.get(i + 1) == Some(&b'[') => {
+                match bytes.get(i + 2) {
+                    Some(b'A') => keys.push(Key::Up),
+                    Some(b'B') => keys.push(Key::Down),
+                    Some(b'C') => keys.push(Key::Right),
+                    Some(b'D') => keys.push(Key::Left),
+                    _ => (),
+                }
+                i += 3;
+            }
+            0x1b => {
+                keys.push(Key::Escape);
+                i += 1;
+            }
+            0x03 => {
+                keys.push(Key::Interrupt);
+                i += 1;
+            }
+            b => {
+                keys.push(Key::Char(b as char));
+                i += 1;
+            }
+        }
+    }
+    keys
+}
+
+/// Escape sequence moving the cursor to the zero-based column and row.
+pub fn move_to(x: usize, y: usize) -> String {
+    format!("\x1b[{};{}H", y + 1, x + 1)
+}
+
+pub const CLEAR: &str = "\x1b[2J\x1b[H";
+pub const HIDE_CURSOR: &str = "\x1b[?25l";
+pub const SHOW_CURSOR: &str = "\x1b[?25h";
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+/// Terminal in raw mode, where keys are read without waiting for a newline
+/// and without echo. Reads return immediately. Ctrl-C is read as
+/// `Key::Interrupt` instead of killing the program, so that the previous
+/// settings are always restored when it is dropped.
+pub struct RawTerminal {
+    saved: String,
+}
+
+impl RawTerminal {
+    pub fn enable() -> io::Result<RawTerminal> {
+        let saved = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "-isig", "min", "0", "time", "0"])?;
+        print!("{}", HIDE_CURSOR);
+        io::stdout().flush()?;
+        Ok(RawTerminal { saved })
+    }
+
+    /// Keys pressed since the last call, without waiting for one.
+    pub fn read_keys(&mut self) -> io::Result<Vec<Key>> {
+        let mut buffer = [0; 64];
+        let n = io::stdin().read(&mut buffer)?;
+        Ok(parse_keys(&buffer[..n]))
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        print!("{}", SHOW_CURSOR);
+        let _ = io::stdout().flush();
+        let _ = stty(&[&self.saved]);
+    }
+}
@@ -23,15 +23,22 @@ fn part2(masses: &[i32]) -> i32 {
     masses.iter().map(|&m| fuel_for_mass(m)).sum()
 }
 
-fn main() {
-    let input: Vec<i32> = common::get_lines()
-        .into_iter()
-        .map(|l| l.parse::<i32>().expect("could not parse number"))
-        .collect();
+fn main() -> Result<(), common::InputError> {
+    let report = common::Report::new(2019, 1)?;
+    let input: Vec<i32> = common::get_input()?;
     let result1 = part1(&input);
+    report.part(
+        1,
+        result1,
+        format!("Part1: Total fuel required: {}", result1),
+    );
     let result2 = part2(&input);
-    println!("Part1: Total fuel required: {}", result1);
-    println!("Part2: Total fuel required: {}", result2);
+    report.part(
+        2,
+        result2,
+        format!("Part2: Total fuel required: {}", result2),
+    );
+    Ok(())
 }
 
 #[cfg(test)]
@@ -1,98 +1,14 @@
+use num::integer::gcd;
 use std::cmp::Ordering;
-use std::collections::HashSet;
-use std::convert::TryFrom;
-use std::fmt;
+use std::collections::{HashMap, HashSet};
 mod common;
-
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Hash)]
-struct Point2D {
-    x: i64,
-    y: i64,
-}
-
-impl fmt::Display for Point2D {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "({}, {})", self.x, self.y)
-    }
-}
-
-impl std::ops::Sub for Point2D {
-    type Output = Point2D;
-
-    fn sub(self, other: Point2D) -> Point2D {
-        Point2D {
-            x: self.x - other.x,
-            y: self.y - other.y,
-        }
-    }
-}
-
-impl Point2D {
-    fn inner_product(self, other: Point2D) -> i64 {
-        self.x * other.x + self.y * other.y
-    }
-}
-
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Hash)]
-struct Point3D {
-    x: i64,
-    y: i64,
-    z: i64,
-}
-
-impl fmt::Display for Point3D {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "({}, {}, {})", self.x, self.y, self.z)
-    }
-}
-
-impl TryFrom<Point3D> for Point2D {
-    type Error = &'static str;
-
-    fn try_from(p: Point3D) -> Result<Self, Self::Error> {
-        if p.z == 0 {
-            Err("3d point is a point in infinity")
-        } else if p.x % p.z != 0 || p.y % p.z != 0 {
-            Err("3d point is a non-integer 2d point")
-        } else {
-            Ok(Point2D {
-                x: p.x / p.z,
-                y: p.y / p.z,
-            })
-        }
-    }
-}
-
-impl From<Point2D> for Point3D {
-    fn from(p: Point2D) -> Point3D {
-        Point3D {
-            x: p.x,
-            y: p.y,
-            z: 1,
-        }
-    }
-}
-
-impl Point3D {
-    fn inner_product(self, other: Point3D) -> i64 {
-        self.x * other.x + self.y * other.y + self.z * other.z
-    }
-
-    fn cross_product(self, other: Point3D) -> Point3D {
-        let x = self.y * other.z - self.z * other.y;
-        let y = self.z * other.x - self.x * other.z;
-        let z = self.x * other.y - self.y * other.x;
-        Point3D { x, y, z }
-    }
-}
+use common::geometry::{Point2D, Point3D};
+use common::grid::Grid;
+use common::image::{Palette, Style};
+use common::InputError;
 
 fn triple_product<P: Into<Point3D>, Q: Into<Point3D>, R: Into<Point3D>>(p: P, q: Q, r: R) -> i64 {
-    p.into().cross_product(q.into()).inner_product(r.into())
-}
-
-// relative length of (p, r) in terms of vector (p, q)
-fn rel_length(p: Point2D, q: Point2D, r: Point2D) -> i64 {
-    (q - p).inner_product(r - p)
+    p.into().cross(q.into()).dot(r.into())
 }
 
 // tests if point r is on the line segment (p, q)
@@ -108,55 +24,7 @@ fn point_on_segment(p: Point2D, q: Point2D, r: Point2D) -> bool {
     }
 }
 
-// compares angle (p, q, a) with angle (p, q, b)
-// if angle is same, compare by distance to p
-fn compare_by_angle(p: Point2D, q: Point2D, a: Point2D, b: Point2D) -> Ordering {
-    assert!(p != q);
-    assert!(a != p);
-    assert!(b != p);
-    if a == b {
-        return Ordering::Equal;
-    }
-    let pqa = triple_product(p, q, a);
-    let pqb = triple_product(p, q, b);
-
-    if pqa == 0 && pqb == 0 {
-        // a and b both on line (p, q)
-        let da = rel_length(p, q, a);
-        let db = rel_length(p, q, b);
-        if da > 0 && db < 0 {
-            // a in direction of (p, q), but b not
-            Ordering::Less
-        } else if db > 0 && da < 0 {
-            // b in direction of (p, q), but a not
-            Ordering::Greater
-        } else {
-            // compare by distance
-            da.abs().cmp(&db.abs())
-        }
-    } else if pqa < 0 && pqb >= 0 {
-        // a on left side and b on right side of (p, q)
-        Ordering::Greater
-    } else if pqa >= 0 && pqb < 0 {
-        // a on right side and b on left side of (p, q)
-        Ordering::Less
-    } else {
-        // a and b on same side of (p, q)
-        let pba = triple_product(p, b, a);
-        if pba < 0 {
-            Ordering::Less
-        } else if pba > 0 {
-            Ordering::Greater
-        } else {
-            // a on edge (p, b)
-            let da = rel_length(p, b, a);
-            let db = rel_length(p, b, b);
-            // compare by distance
-            da.abs().cmp(&db.abs())
-        }
-    }
-}
-
+#[allow(dead_code)]
 fn can_detect(origin: Point2D, target: Point2D, asteroids: &[Point2D]) -> bool {
     asteroids
         .iter()
@@ -167,70 +35,180 @@ fn can_detect(origin: Point2D, target: Point2D, asteroids: &[Point2D]) -> bool {
 fn find_detect_slow(origin: Point2D, asteroids: &[Point2D]) -> Vec<Point2D> {
     asteroids
         .iter()
-        .filter(|&&target| origin != target && can_detect(origin, target, &asteroids))
+        .filter(|&&target| origin != target && can_detect(origin, target, asteroids))
         .copied()
         .collect()
 }
 
-fn find_detect(origin: Point2D, asteroids: &[Point2D]) -> Vec<Point2D> {
-    let up = Point2D {
-        x: origin.x,
-        y: origin.y - 1,
-    };
-    let mut sorted: Vec<_> = asteroids
-        .iter()
-        .filter(|&&p| p != origin)
-        .copied()
-        .collect();
-    sorted.sort_by(|&a, &b| compare_by_angle(origin, up, a, b));
-    let mut result = Vec::with_capacity(asteroids.len());
-    let mut last_point = origin;
-    for a in sorted {
-        if last_point == origin || !point_on_segment(origin, a, last_point) {
-            last_point = a;
-            result.push(a);
+// direction from the origin to the target, reduced so that all asteroids
+// on the same line of sight share it
+fn direction(origin: Point2D, target: Point2D) -> Point2D {
+    let d = target - origin;
+    d / gcd(d.x, d.y)
+}
+
+// clockwise order of directions starting with up, where y points down
+fn compare_directions(a: Point2D, b: Point2D) -> Ordering {
+    // directions from up to just before down come first
+    let left_half = |d: Point2D| d.x < 0 || (d.x == 0 && d.y > 0);
+    left_half(a)
+        .cmp(&left_half(b))
+        .then_with(|| 0.cmp(&(a.x * b.y - a.y * b.x)))
+}
+
+/// Asteroids seen from the station grouped by direction, in the order in
+/// which the laser passes the directions, and each group by distance.
+fn lines_of_sight(station: Point2D, asteroids: &[Point2D]) -> Vec<Vec<Point2D>> {
+    let mut groups: HashMap<Point2D, Vec<Point2D>> = HashMap::new();
+    for &a in asteroids {
+        if a != station {
+            groups.entry(direction(station, a)).or_default().push(a);
         }
     }
-    result
+    let mut groups: Vec<_> = groups.into_iter().collect();
+    groups.sort_by(|(a, _), (b, _)| compare_directions(*a, *b));
+    groups
+        .into_iter()
+        .map(|(_, mut group)| {
+            group.sort_by_key(|&p| (p - station).l1_norm());
+            group
+        })
+        .collect()
+}
+
+// the direction tables are only used if they are at most this many times
+// larger than the number of asteroids, as their size grows with the area
+const MAX_TABLE_FACTOR: u128 = 16;
+
+// applies the count to chunks of the stations on all available threads
+fn count_in_parallel<F>(stations: &[Point2D], count: F) -> Vec<usize>
+where
+    F: Fn(&[Point2D]) -> Vec<usize> + Sync,
+{
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = stations.len().div_ceil(threads).max(1);
+    let count = &count;
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = stations
+            .chunks(chunk)
+            .map(|stations| scope.spawn(move || count(stations)))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("could not join thread"))
+            .collect()
+    })
+}
+
+/// Number of asteroids detected from each asteroid, one per direction,
+/// counted on all available threads.
+fn detection_counts(asteroids: &[Point2D]) -> Vec<usize> {
+    let min_x = asteroids.iter().map(|p| p.x).min().unwrap_or(0);
+    let max_x = asteroids.iter().map(|p| p.x).max().unwrap_or(0);
+    let min_y = asteroids.iter().map(|p| p.y).min().unwrap_or(0);
+    let max_y = asteroids.iter().map(|p| p.y).max().unwrap_or(0);
+    let (dx, dy) = (max_x - min_x, max_y - min_y);
+    let area = (dx as u128 + 1) * (dy as u128 + 1);
+    if area > MAX_TABLE_FACTOR * asteroids.len() as u128 {
+        // few asteroids spread over a large area
+        return count_in_parallel(asteroids, |stations| {
+            stations
+                .iter()
+                .map(|&s| {
+                    let directions: HashSet<_> = asteroids
+                        .iter()
+                        .filter(|&&a| a != s)
+                        .map(|&a| direction(s, a))
+                        .collect();
+                    directions.len()
+                })
+                .collect()
+        });
+    }
+
+    // directions have components between -dx and dx, and -dy and dy
+    let index = move |d: Point2D| ((d.y + dy) * (2 * dx + 1) + d.x + dx) as usize;
+    let size = index(Point2D::new(dx, dy)) + 1;
+    // greatest common divisors of all absolute components, looked up
+    // instead of computed for every pair of asteroids
+    let gcds: Vec<i64> = (0..=dy)
+        .flat_map(|y| (0..=dx).map(move |x| gcd(x, y)))
+        .collect();
+    let reduce = |d: Point2D| d / gcds[(d.y.abs() * (dx + 1) + d.x.abs()) as usize];
+    count_in_parallel(asteroids, |stations| {
+        // the station that last saw each direction, plus one
+        let mut seen = vec![0; size];
+        stations
+            .iter()
+            .enumerate()
+            .map(|(i, &s)| {
+                let mut count = 0;
+                for &a in asteroids {
+                    if a != s {
+                        let d = index(reduce(a - s));
+                        if seen[d] != i + 1 {
+                            seen[d] = i + 1;
+                            count += 1;
+                        }
+                    }
+                }
+                count
+            })
+            .collect()
+    })
 }
 
-fn max_detect(asteroids: &[Point2D]) -> (Point2D, usize) {
+/// Asteroid detecting the most other asteroids, given the counts of all asteroids.
+fn max_detect(asteroids: &[Point2D], counts: &[usize]) -> (Point2D, usize) {
     asteroids
         .iter()
-        .map(|&origin| (origin, find_detect(origin, &asteroids).len()))
+        .copied()
+        .zip(counts.iter().copied())
         .max_by_key(|(_, n)| *n)
         .unwrap()
 }
 
-fn vaporize_once(origin: Point2D, asteroids: &[Point2D]) -> Vec<Point2D> {
-    let up = Point2D {
-        x: origin.x,
-        y: origin.y - 1,
-    };
-    let mut detectable = find_detect(origin, asteroids);
-    // sort by angle (i.e. time hit by laser)
-    detectable.sort_by(|&a, &b| compare_by_angle(origin, up, a, b));
-    detectable
-}
-
+// the laser hits the closest remaining asteroid of each direction per turn
 fn vaporize_all(origin: Point2D, asteroids: &[Point2D]) -> Vec<Point2D> {
-    let mut remaining: HashSet<Point2D> = asteroids.iter().copied().collect();
-    let mut destroyed = Vec::with_capacity(asteroids.len());
-    loop {
-        let current: Vec<_> = remaining.iter().copied().collect();
-        let vaporized = vaporize_once(origin, &current);
-        if vaporized.is_empty() {
+    let groups = lines_of_sight(origin, asteroids);
+    let total = groups.iter().map(|g| g.len()).sum();
+    let mut destroyed = Vec::with_capacity(total);
+    for turn in 0.. {
+        if destroyed.len() == total {
             break;
-        } else {
-            for p in &vaporized {
-                remaining.remove(p);
-            }
-            destroyed.extend(vaporized.into_iter());
         }
+        destroyed.extend(groups.iter().filter_map(|g| g.get(turn)));
     }
     destroyed
 }
 
+// number of colours of the heat map besides the one for empty cells
+const HEAT_LEVELS: usize = 10;
+
+// colours from dark red over yellow to white, after the dark blue of empty cells
+fn heat_palette() -> Palette {
+    let mut colours = vec![[0x10, 0x10, 0x30]];
+    colours.extend((0..HEAT_LEVELS).map(|i| {
+        let t = (i * 3 * 255 / (HEAT_LEVELS - 1)) as u32;
+        let channel = |offset: u32| t.saturating_sub(offset).min(255) as u8;
+        [channel(0).max(0x40), channel(255), channel(510)]
+    }));
+    Palette(colours)
+}
+
+/// Heat level of each cell, with 0 for cells without asteroid and levels
+/// from 1 up to `HEAT_LEVELS` by the number of asteroids detected.
+fn heat_levels(asteroids: &[Point2D], counts: &[usize]) -> Grid<usize> {
+    let width = asteroids.iter().map(|p| p.x + 1).max().unwrap_or(0);
+    let height = asteroids.iter().map(|p| p.y + 1).max().unwrap_or(0);
+    let max = counts.iter().copied().max().unwrap_or(0).max(1);
+    let mut grid = Grid::new(width as usize, height as usize, 0);
+    for (&p, &count) in asteroids.iter().zip(counts) {
+        grid[p] = 1 + count * (HEAT_LEVELS - 1) / max;
+    }
+    grid
+}
+
 fn parse_input(input: &str) -> Vec<Point2D> {
     let vec: Vec<Vec<_>> = input.split('\n').map(|l| l.chars().collect()).collect();
     let mut result = Vec::new();
@@ -247,20 +225,51 @@ fn parse_input(input: &str) -> Vec<Point2D> {
     result
 }
 
-fn main() {
-    let input = common::get_content();
+fn main() -> Result<(), InputError> {
+    let report = common::Report::new(2019, 10)?;
+    let input = common::get_content()?;
     let asteroids = parse_input(&input);
 
-    let (p, result1) = max_detect(&asteroids);
-    println!(
-        "Part1: Maximum number of asteroids detected by {}: {}",
-        p, result1
+    if asteroids.is_empty() {
+        return Err(InputError::new("no asteroids found"));
+    }
+    let counts = detection_counts(&asteroids);
+    let (p, result1) = max_detect(&asteroids, &counts);
+    report.part(
+        1,
+        result1,
+        format!(
+            "Part1: Maximum number of asteroids detected by {}: {}",
+            p, result1
+        ),
     );
 
     let vaporized = vaporize_all(p, &asteroids);
-    let q = vaporized[199];
+    let q = *vaporized
+        .get(199)
+        .ok_or_else(|| InputError::new("fewer than 200 asteroids to vaporize"))?;
     let result2 = 100 * q.x + q.y;
-    println!("Part2: 200th vaporized asteroid: {} => {}", q, result2);
+    report.part(
+        2,
+        result2,
+        format!("Part2: 200th vaporized asteroid: {} => {}", q, result2),
+    );
+
+    if let Some(station) = common::option("station") {
+        let station: Point2D = station.parse()?;
+        if report.format() == common::Format::Text {
+            println!("Vaporization order from {}:", station);
+            for (i, a) in vaporize_all(station, &asteroids).iter().enumerate() {
+                println!("{:4}: {}", i + 1, a);
+            }
+        }
+    }
+    if let Some(filename) = common::option("heatmap") {
+        let style = Style::from_args(heat_palette())?;
+        let raster = style.render(&heat_levels(&asteroids, &counts), |&l| l)?;
+        raster.write(&filename)?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -295,8 +304,8 @@ mod tests {
                      #####\n\
                      ....#\n\
                      ...##";
-        let asteroids = parse_input(&input);
-        let (p, n) = max_detect(&asteroids);
+        let asteroids = parse_input(input);
+        let (p, n) = max_detect(&asteroids, &detection_counts(&asteroids));
         assert_eq!(p, Point2D { x: 3, y: 4 });
         assert_eq!(n, 8);
     }
@@ -313,8 +322,8 @@ mod tests {
                      .##.#..###\n\
                      ##...#..#.\n\
                      .#....####";
-        let asteroids = parse_input(&input);
-        let (p, n) = max_detect(&asteroids);
+        let asteroids = parse_input(input);
+        let (p, n) = max_detect(&asteroids, &detection_counts(&asteroids));
         assert_eq!(p, Point2D { x: 5, y: 8 });
         assert_eq!(n, 33);
     }
@@ -331,8 +340,8 @@ mod tests {
                      ..##....##\n\
                      ......#...\n\
                      .####.###.";
-        let asteroids = parse_input(&input);
-        let (p, n) = max_detect(&asteroids);
+        let asteroids = parse_input(input);
+        let (p, n) = max_detect(&asteroids, &detection_counts(&asteroids));
         assert_eq!(p, Point2D { x: 1, y: 2 });
         assert_eq!(n, 35);
     }
@@ -349,17 +358,17 @@ mod tests {
                      #..#.#.###\n\
                      .##...##.#\n\
                      .....#.#..";
-        let asteroids = parse_input(&input);
-        let (p, n) = max_detect(&asteroids);
+        let asteroids = parse_input(input);
+        let (p, n) = max_detect(&asteroids, &detection_counts(&asteroids));
         assert_eq!(p, Point2D { x: 6, y: 3 });
         assert_eq!(n, 41);
     }
 
     #[test]
     fn test_example5() {
-        let asteroids = parse_input(&EXAMPLE_LARGE);
+        let asteroids = parse_input(EXAMPLE_LARGE);
 
-        let (p, n) = max_detect(&asteroids);
+        let (p, n) = max_detect(&asteroids, &detection_counts(&asteroids));
         assert_eq!(p, Point2D { x: 11, y: 13 });
         assert_eq!(n, 210);
     }
@@ -371,10 +380,9 @@ mod tests {
                      ##...#...#.#####.\n\
                      ..#.....X...###..\n\
                      ..#.#.....#....##";
-        let asteroids = parse_input(&input);
+        let asteroids = parse_input(input);
         let station = Point2D { x: 8, y: 3 };
         let vaporized = vaporize_all(station, &asteroids);
-        println!("Vap: {:?}", vaporized);
         let expected = vec![
             Point2D { x: 8, y: 1 },
             Point2D { x: 9, y: 0 },
@@ -418,7 +426,7 @@ mod tests {
 
     #[test]
     fn test_vaporize_large() {
-        let asteroids = parse_input(&EXAMPLE_LARGE);
+        let asteroids = parse_input(EXAMPLE_LARGE);
         let station = Point2D { x: 11, y: 13 };
         let vaporized = vaporize_all(station, &asteroids);
         assert_eq!(vaporized.len(), 299);
@@ -433,4 +441,61 @@ mod tests {
         assert_eq!(vaporized[200], Point2D { x: 10, y: 9 });
         assert_eq!(vaporized[298], Point2D { x: 11, y: 1 });
     }
+
+    #[test]
+    fn test_detect_slow() {
+        let asteroids = parse_input(EXAMPLE_LARGE);
+        let counts = detection_counts(&asteroids);
+        for (i, &station) in asteroids.iter().enumerate().step_by(7) {
+            assert_eq!(counts[i], find_detect_slow(station, &asteroids).len());
+        }
+    }
+
+    #[test]
+    fn test_detect_sparse() {
+        let asteroids = parse_input(EXAMPLE_LARGE);
+        // same lines of sight, spread too far apart for the direction tables
+        let spread: Vec<_> = asteroids.iter().map(|&p| p * 1_000_000).collect();
+        assert_eq!(detection_counts(&spread), detection_counts(&asteroids));
+        let (p, n) = max_detect(&spread, &detection_counts(&spread));
+        assert_eq!(p, Point2D::new(11_000_000, 13_000_000));
+        assert_eq!(n, 210);
+    }
+
+    #[test]
+    fn test_directions() {
+        let o = Point2D::zero();
+        assert_eq!(direction(o, Point2D::new(4, -6)), Point2D::new(2, -3));
+        assert_eq!(direction(o, Point2D::new(0, 5)), Point2D::new(0, 1));
+        let clockwise = [
+            (0, -1),
+            (1, -3),
+            (1, 0),
+            (2, 1),
+            (0, 1),
+            (-1, 1),
+            (-1, 0),
+            (-1, -5),
+        ];
+        let clockwise: Vec<_> = clockwise.iter().map(|&(x, y)| Point2D::new(x, y)).collect();
+        for (i, &a) in clockwise.iter().enumerate() {
+            for (j, &b) in clockwise.iter().enumerate() {
+                assert_eq!(compare_directions(a, b), i.cmp(&j));
+            }
+        }
+    }
+
+    #[test]
+    fn test_heat_levels() {
+        let asteroids = parse_input(".#..#\n.....\n#####\n....#\n...##");
+        let counts = detection_counts(&asteroids);
+        let levels = heat_levels(&asteroids, &counts);
+        assert_eq!((levels.width(), levels.height()), (5, 5));
+        assert_eq!(levels[Point2D::new(0, 0)], 0);
+        // 8 asteroids from (3, 4), the most, and 5 from (4, 2)
+        assert_eq!(levels[Point2D::new(3, 4)], HEAT_LEVELS);
+        assert_eq!(levels[Point2D::new(4, 2)], 1 + 5 * 9 / 8);
+        assert_eq!(heat_palette().0.len(), HEAT_LEVELS + 1);
+        assert_eq!(heat_palette().colour(HEAT_LEVELS), [0xff, 0xff, 0xff]);
+    }
 }
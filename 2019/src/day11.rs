@@ -1,8 +1,13 @@
-use std::collections::HashMap;
-use std::fmt;
-use ansi_term::Colour::{Black, White};
+use ansi_term::ANSIString;
+use ansi_term::Colour::{Black, Red, White};
 mod common;
+use common::asciicast::{self, Recording};
+use common::geometry::Point2D;
+use common::grid::SparseGrid;
+use common::image::{Palette, Style, BLACK, WHITE};
 use common::intcode;
+use common::ocr::{self, OcrError};
+use common::terminal;
 
 #[derive(PartialEq, Eq, Copy, Clone)]
 enum Direction {
@@ -29,27 +34,12 @@ impl Direction {
             Direction::R => Direction::D,
         };
     }
-}
-
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Hash)]
-struct Point2D {
-    x: i64,
-    y: i64,
-}
-
-impl fmt::Display for Point2D {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "({}, {})", self.x, self.y)
-    }
-}
-
-impl Point2D {
-    fn add_direction(&mut self, dir: Direction) {
-        match dir {
-            Direction::U => self.y -= 1,
-            Direction::D => self.y += 1,
-            Direction::L => self.x -= 1,
-            Direction::R => self.x += 1,
+    fn offset(self) -> Point2D {
+        match self {
+            Direction::U => Point2D::new(0, -1),
+            Direction::D => Point2D::new(0, 1),
+            Direction::L => Point2D::new(-1, 0),
+            Direction::R => Point2D::new(1, 0),
         }
     }
 }
@@ -60,90 +50,198 @@ enum Color {
     White,
 }
 
-fn print_hull(hull: &HashMap<Point2D, Color>) {
-    let min_x = hull.keys().map(|&p| p.x).min().unwrap_or(0);
-    let max_x = hull.keys().map(|&p| p.x).max().unwrap_or(0);
-    let min_y = hull.keys().map(|&p| p.y).min().unwrap_or(0);
-    let max_y = hull.keys().map(|&p| p.y).max().unwrap_or(0);
-
-    for y in min_y..=max_y {
-        for x in min_x..=max_x {
-            let point = Point2D { x, y };
-            let color = hull.get(&point).copied().unwrap_or(Color::Black);
-            let out = match color {
-                Color::Black => White.on(Black).paint(" "),
-                Color::White => Black.on(White).paint(" "),
-            };
-            print!("{}", out);
-        }
-        println!();
-    }
+/// Step of the robot, which painted a panel and then moved.
+#[derive(Copy, Clone)]
+struct RobotStep {
+    painted: Point2D,
+    color: Color,
+    position: Point2D,
+    direction: Direction,
+    // instructions executed so far
+    steps: u64,
 }
 
-fn run_robot(code: &[i64], initial: Option<Color>) -> HashMap<Point2D, Color> {
-    let mut hull: HashMap<Point2D, Color> = HashMap::new();
+fn run_robot_with<F: FnMut(&RobotStep)>(
+    code: &[i64],
+    initial: Option<Color>,
+    mut on_step: F,
+) -> SparseGrid<Color> {
+    let mut hull = SparseGrid::new(Color::Black);
     let mut position = Point2D { x: 0, y: 0 };
     let mut direction = Direction::U;
 
     if let Some(initial_color) = initial {
-        hull.insert(position, initial_color);
+        hull.set(position, initial_color);
     }
 
-    let intcode::ProgramRunner { program, io } = intcode::ProgramRunner::new(code);
-    let program_thread = std::thread::spawn(move || {
-        program.run();
-    });
-
-    loop {
-        let color = hull.get(&position).copied().unwrap_or(Color::Black);
+    let mut machine = intcode::Machine::new(code);
+    while !machine.is_halted() {
+        let color = *hull.get(position);
         let input = match color {
             Color::Black => 0,
             Color::White => 1,
         };
-        io.send_input_unchecked(input);
-        if let Some(paint) = io.next_output() {
+        machine.push_input(input);
+        // the robot may paint and move several times without checking the color
+        for c in machine.run_to_input().chunks(2) {
+            let (paint, turn) = match *c {
+                [paint, turn] => (paint, turn),
+                _ => panic!("expected color and turn direction"),
+            };
             let new_color = match paint {
                 0 => Color::Black,
                 1 => Color::White,
                 _ => panic!("unknown color for painting"),
             };
-            hull.insert(position, new_color);
-            if let Some(turn) = io.next_output() {
-                match turn {
-                    0 => direction.turn_left(),
-                    1 => direction.turn_right(),
-                    _ => panic!("unknown turn direction"),
-                }
-                position.add_direction(direction);
-            } else {
-                break;
+            hull.set(position, new_color);
+            let painted = position;
+            match turn {
+                0 => direction.turn_left(),
+                1 => direction.turn_right(),
+                _ => panic!("unknown turn direction"),
             }
-        } else {
-            break;
+            position += direction.offset();
+            on_step(&RobotStep {
+                painted,
+                color: new_color,
+                position,
+                direction,
+                steps: machine.steps(),
+            });
         }
     }
-    program_thread.join().expect("could not join thread");
 
     hull
 }
 
-fn main() {
-    let input: Vec<Vec<i64>> = common::get_lines()
-        .into_iter()
-        .map(|l| {
-            l.split(',')
-                .map(|i| i.parse::<i64>().expect("could not parse number"))
-                .collect()
-        })
-        .collect();
+fn run_robot(code: &[i64], initial: Option<Color>) -> SparseGrid<Color> {
+    run_robot_with(code, initial, |_| ())
+}
+
+fn glyph(color: Color) -> ANSIString<'static> {
+    match color {
+        Color::Black => White.on(Black).paint(" "),
+        Color::White => Black.on(White).paint(" "),
+    }
+}
+
+fn robot_glyph(direction: Direction, color: Color) -> ANSIString<'static> {
+    let arrow = match direction {
+        Direction::U => "^",
+        Direction::D => "v",
+        Direction::L => "<",
+        Direction::R => ">",
+    };
+    let background = match color {
+        Color::Black => Black,
+        Color::White => White,
+    };
+    Red.on(background).bold().paint(arrow)
+}
+
+// instructions per second of recording time, which shows about 20 steps of
+// the robot per second
+const CAST_SPEED: u64 = 600;
+
+/// Records the steps of the robot, drawing the panel it painted and the
+/// robot itself, with the panels fitted into the recording.
+fn record_robot(initial: Option<Color>, robot_steps: &[RobotStep], speed: u64) -> Recording {
+    let start = Point2D::zero();
+    let points = robot_steps.iter().flat_map(|s| vec![s.painted, s.position]);
+    let (min, max) = points.fold((start, start), |(min, max), p| {
+        (
+            Point2D::new(min.x.min(p.x), min.y.min(p.y)),
+            Point2D::new(max.x.max(p.x), max.y.max(p.y)),
+        )
+    });
+    let (width, height) = ((max.x - min.x + 1) as usize, (max.y - min.y + 1) as usize);
+    let mut recording = Recording::new(width.max(30), height + 1).with_title("2019 day 11");
+    let move_to = |p: Point2D| terminal::move_to((p.x - min.x) as usize, (p.y - min.y) as usize);
+
+    let mut hull = SparseGrid::new(Color::Black);
+    if let Some(initial_color) = initial {
+        hull.set(start, initial_color);
+    }
+    let mut out = terminal::CLEAR.to_string();
+    for y in 0..height {
+        out.push_str(&glyph(Color::Black).to_string().repeat(width));
+        if y + 1 < height {
+            out.push_str("\r\n");
+        }
+    }
+    out.push_str(&move_to(start));
+    out.push_str(&robot_glyph(Direction::U, *hull.get(start)).to_string());
+    recording.output(0.0, &out);
+
+    let mut painted = 0;
+    for s in robot_steps {
+        if !hull.is_set(s.painted) {
+            painted += 1;
+        }
+        hull.set(s.painted, s.color);
+        let mut out = move_to(s.painted);
+        out.push_str(&glyph(s.color).to_string());
+        out.push_str(&move_to(s.position));
+        out.push_str(&robot_glyph(s.direction, *hull.get(s.position)).to_string());
+        out.push_str(&terminal::move_to(0, height));
+        out.push_str(&format!("Panels painted: {}", painted));
+        recording.output(asciicast::instruction_time(s.steps, speed), &out);
+    }
+    recording
+}
+
+// letters painted in white on the hull
+fn registration_identifier(hull: &SparseGrid<Color>) -> Result<String, OcrError> {
+    ocr::read(&hull.to_dense(), |&color| color == Color::White)
+}
+
+fn main() -> Result<(), common::InputError> {
+    let report = common::Report::new(2019, 11)?;
+    let input: Vec<Vec<i64>> = common::get_separated_input(',')?;
     for program in input {
         let result1 = run_robot(&program, None);
-        println!("Part1: panels painted on: {}", result1.len());
+        report.part(
+            1,
+            result1.len(),
+            format!("Part1: panels painted on: {}", result1.len()),
+        );
 
-        let result2 = run_robot(&program, Some(Color::White));
-        println!("Part2:");
-        print_hull(&result2);
+        let cast_filename = common::option("cast");
+        let mut robot_steps = Vec::new();
+        let result2 = run_robot_with(&program, Some(Color::White), |&s| {
+            if cast_filename.is_some() {
+                robot_steps.push(s);
+            }
+        });
+        let grid = result2.render(|color| match color {
+            Color::Black => '.',
+            Color::White => '#',
+        });
+        let painted = result2.render(|&color| glyph(color));
+        let identifier = registration_identifier(&result2)
+            .map_err(|e| eprintln!("Warning: {}", e))
+            .ok();
+        report.grid_part(
+            2,
+            &grid,
+            identifier.as_deref(),
+            format!(
+                "Part2: registration identifier {}\n{}",
+                identifier.as_deref().unwrap_or("?"),
+                painted
+            ),
+        );
+        if let Some(filename) = common::option("image") {
+            let style = Style::from_args(Palette(vec![BLACK, WHITE]))?;
+            let raster = style.render(&result2.to_dense(), |&color| color as usize)?;
+            raster.write(&filename)?;
+        }
+        if let Some(filename) = cast_filename {
+            let speed = asciicast::speed_from_args(CAST_SPEED)?;
+            record_robot(Some(Color::White), &robot_steps, speed).write(&filename)?;
+        }
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -159,4 +257,24 @@ mod tests {
         let result = run_robot(&program, None);
         assert_eq!(result.len(), 6);
     }
+
+    #[test]
+    fn test_record_robot() {
+        let program = vec![
+            3, 0, 104, 1, 104, 0, 3, 0, 104, 0, 104, 0, 3, 0, 104, 1, 104, 0, 104, 1, 104, 0, 3, 1,
+            104, 0, 104, 1, 3, 2, 104, 1, 104, 0, 3, 2, 104, 1, 104, 0, 99,
+        ];
+        let mut robot_steps = Vec::new();
+        run_robot_with(&program, None, |&s| robot_steps.push(s));
+        assert_eq!(robot_steps.len(), 7);
+        let cast = record_robot(None, &robot_steps, 10).encode();
+        let lines: Vec<_> = cast.lines().collect();
+        assert_eq!(
+            lines[0],
+            "{\"version\": 2, \"width\": 30, \"height\": 4, \"title\": \"2019 day 11\"}"
+        );
+        assert_eq!(lines.len(), 9);
+        assert!(lines[1].starts_with("[0.000000, \"o\", \"\\u001b[2J"));
+        assert!(lines[8].ends_with("Panels painted: 6\"]"));
+    }
 }
@@ -1,211 +1,413 @@
-use num::integer::lcm;
-use regex::Regex;
+use num::integer::gcd;
 use std::fmt;
-use std::str::FromStr;
 mod common;
+use common::InputError;
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
-struct Point3D {
-    x: i64,
-    y: i64,
-    z: i64,
+/// Pull of one body on another along an axis, given the difference of the
+/// coordinate of the pulling body and the pulled one. As the pull only
+/// depends on a single axis, the axes move independently of each other.
+#[derive(Debug, Copy, Clone)]
+enum Gravity {
+    /// Pull of 1 towards the other body, as in the puzzle.
+    Unit,
+    /// Pull of the difference, limited to the given maximum.
+    Clamped(i64),
+    /// Any pull computed from the difference.
+    Custom(fn(i64) -> i64),
 }
 
-impl std::ops::Add for Point3D {
-    type Output = Point3D;
-
-    fn add(self, other: Point3D) -> Point3D {
-        Point3D {
-            x: self.x + other.x,
-            y: self.y + other.y,
-            z: self.z + other.z,
+impl Gravity {
+    fn pull(self, difference: i64) -> i64 {
+        match self {
+            Gravity::Unit => difference.signum(),
+            Gravity::Clamped(max) => difference.clamp(-max, max),
+            Gravity::Custom(f) => f(difference),
         }
     }
 }
 
-impl std::ops::AddAssign for Point3D {
-    fn add_assign(&mut self, other: Point3D) {
-        self.x += other.x;
-        self.y += other.y;
-        self.z += other.z;
+impl std::str::FromStr for Gravity {
+    type Err = InputError;
+
+    // unit, repulsive or clamped:<max>
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "unit" => Ok(Gravity::Unit),
+            None if s == "repulsive" => Ok(Gravity::Custom(|d| -d.signum())),
+            Some(("clamped", max)) => match max.parse() {
+                Ok(max) if max > 0 => Ok(Gravity::Clamped(max)),
+                _ => Err(InputError::spanning(s, max, "expected a positive maximum")),
+            },
+            _ => Err(InputError::spanning(s, s, "unknown gravity")),
+        }
     }
 }
 
-impl std::ops::Sub for Point3D {
-    type Output = Point3D;
+/// Positions and velocities of all bodies along one axis.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct Axis {
+    positions: Vec<i64>,
+    velocities: Vec<i64>,
+}
+
+// default number of steps after which the search for the cycle of an
+// axis gives up
+const MAX_CYCLE_STEPS: u64 = 1 << 22;
 
-    fn sub(self, other: Point3D) -> Point3D {
-        Point3D {
-            x: self.x - other.x,
-            y: self.y - other.y,
-            z: self.z - other.z,
+impl Axis {
+    // none if a position or velocity overflows
+    fn step(&mut self, gravity: Gravity) -> Option<()> {
+        for (i, v) in self.velocities.iter_mut().enumerate() {
+            let p = self.positions[i];
+            for &q in &self.positions {
+                *v = v.checked_add(gravity.pull(q.checked_sub(p)?))?;
+            }
+        }
+        for (p, v) in self.positions.iter_mut().zip(&self.velocities) {
+            *p = p.checked_add(*v)?;
         }
+        Some(())
     }
-}
 
-impl std::ops::SubAssign for Point3D {
-    fn sub_assign(&mut self, other: Point3D) {
-        self.x -= other.x;
-        self.y -= other.y;
-        self.z -= other.z;
+    fn after(&self, steps: u64, gravity: Gravity) -> Option<Axis> {
+        let mut axis = self.clone();
+        for _ in 0..steps {
+            axis.step(gravity)?;
+        }
+        Some(axis)
     }
-}
 
-impl fmt::Display for Point3D {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "({}, {}, {})", self.x, self.y, self.z)
+    // cycle with Brent's algorithm, which needs no memory for the states,
+    // or none if there is none within the limit or the axis overflows
+    fn cycle(&self, gravity: Gravity, limit: u64) -> Option<Cycle> {
+        let mut power = 1;
+        let mut period = 1;
+        let mut steps = 1;
+        let mut tortoise = self.clone();
+        let mut hare = self.after(1, gravity)?;
+        while tortoise != hare {
+            if steps >= limit {
+                return None;
+            }
+            if power == period {
+                tortoise = hare.clone();
+                power *= 2;
+                period = 0;
+            }
+            hare.step(gravity)?;
+            period += 1;
+            steps += 1;
+        }
+        // the start is at most the number of steps taken so far
+        let mut tortoise = self.clone();
+        let mut hare = self.after(period, gravity)?;
+        let mut start = 0;
+        while tortoise != hare {
+            tortoise.step(gravity)?;
+            hare.step(gravity)?;
+            start += 1;
+        }
+        Some(Cycle { start, period })
     }
 }
 
-impl FromStr for Point3D {
-    type Err = &'static str;
+// cycle of a system from the cycles of its axes, if every axis has one and
+// the period fits into 64 bits
+fn combined_cycle(cycles: &[Option<Cycle>]) -> Option<Cycle> {
+    cycles.iter().try_fold(
+        Cycle {
+            start: 0,
+            period: 1,
+        },
+        |c, &a| {
+            let a = a?;
+            Some(Cycle {
+                start: c.start.max(a.start),
+                period: (c.period / gcd(c.period, a.period)).checked_mul(a.period)?,
+            })
+        },
+    )
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let re = Regex::new("<x=(?P<x>[-0-9]*), *y=(?P<y>[-0-9]*), *z=(?P<z>[-0-9]*)>").unwrap();
-        let caps = re.captures(s).ok_or("could not parse 3d point")?;
-        let x = caps["x"]
-            .parse::<i64>()
-            .map_err(|_| "could not parse x coordinate")?;
-        let y = caps["y"]
-            .parse::<i64>()
-            .map_err(|_| "could not parse y coordinate")?;
-        let z = caps["z"]
-            .parse::<i64>()
-            .map_err(|_| "could not parse z coordinate")?;
-        Ok(Point3D { x, y, z })
-    }
+// sum of the absolute values, if it fits into 64 bits
+fn l1_norm(mut values: impl Iterator<Item = i64>) -> Option<i64> {
+    values.try_fold(0i64, |sum, v| sum.checked_add(v.checked_abs()?))
 }
 
-impl Point3D {
-    fn zero() -> Point3D {
-        Point3D { x: 0, y: 0, z: 0 }
-    }
-    fn l1_norm(&self) -> i64 {
-        self.x.abs() + self.y.abs() + self.z.abs()
-    }
-    fn signum(&self) -> Point3D {
-        Point3D {
-            x: self.x.signum(),
-            y: self.y.signum(),
-            z: self.z.signum(),
+fn overflow(steps: u64) -> InputError {
+    InputError::new(format!("coordinates overflow within {} steps", steps))
+}
+
+/// States repeat every `period` steps from step `start` on.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+struct Cycle {
+    start: u64,
+    period: u64,
+}
+
+impl Cycle {
+    // earlier step with the same state
+    fn reduce(self, steps: u64) -> u64 {
+        if steps <= self.start {
+            steps
+        } else {
+            self.start + (steps - self.start) % self.period
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
-struct Moon {
-    position: Point3D,
-    velocity: Point3D,
+/// Bodies in any number of dimensions that pull each other together.
+#[derive(Debug, Clone)]
+struct Universe {
+    names: Vec<String>,
+    axes: Vec<Axis>,
+    gravity: Gravity,
+    cycle_limit: u64,
 }
 
-impl fmt::Display for Moon {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "<pos={}, vel={}>", self.position, self.velocity)
+impl PartialEq for Universe {
+    fn eq(&self, other: &Universe) -> bool {
+        self.axes == other.axes
     }
 }
 
-impl Moon {
-    fn new(position: Point3D) -> Moon {
-        Moon {
-            position,
-            velocity: Point3D::zero(),
+impl fmt::Display for Universe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let vector = |values: Vec<i64>| {
+            let c: Vec<_> = self
+                .names
+                .iter()
+                .zip(values)
+                .map(|(n, v)| format!("{}={}", n, v))
+                .collect();
+            format!("<{}>", c.join(", "))
+        };
+        for b in 0..self.bodies() {
+            let position = self.axes.iter().map(|a| a.positions[b]).collect();
+            let velocity = self.axes.iter().map(|a| a.velocities[b]).collect();
+            writeln!(f, "pos={}, vel={}", vector(position), vector(velocity))?;
         }
+        Ok(())
     }
+}
+
+// position of a body like <x=-1, y=0, z=2>, with the names of the axes
+fn parse_body(s: &str) -> Result<(Vec<String>, Vec<i64>), InputError> {
+    let inner = s
+        .trim()
+        .strip_prefix('<')
+        .and_then(|t| t.strip_suffix('>'))
+        .ok_or_else(|| InputError::spanning(s, s, "expected a position in angle brackets"))?;
+    inner
+        .split(',')
+        .map(|c| {
+            let (name, value) = c
+                .split_once('=')
+                .ok_or_else(|| InputError::spanning(s, c, "expected a coordinate like x=1"))?;
+            let value = value
+                .trim()
+                .parse::<i64>()
+                .map_err(|_| InputError::spanning(s, value, "could not parse coordinate"))?;
+            Ok((name.trim().to_string(), value))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(|c| c.into_iter().unzip())
+}
 
-    fn add_velocity_from(&mut self, other: &Moon) {
-        self.velocity += (other.position - self.position).signum();
+impl Universe {
+    /// Bodies at rest at the positions, with the puzzle's gravity.
+    fn new(names: &[&str], positions: &[Vec<i64>]) -> Universe {
+        let axes = (0..names.len())
+            .map(|a| Axis {
+                positions: positions.iter().map(|p| p[a]).collect(),
+                velocities: vec![0; positions.len()],
+            })
+            .collect();
+        Universe {
+            names: names.iter().map(|n| n.to_string()).collect(),
+            axes,
+            gravity: Gravity::Unit,
+            cycle_limit: MAX_CYCLE_STEPS,
+        }
     }
 
-    fn apply_velocity(&mut self) {
-        self.position += self.velocity;
+    fn parse(lines: &[&str]) -> Result<Universe, InputError> {
+        let mut names = Vec::new();
+        let mut positions = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            let (n, p) = parse_body(line).map_err(|e| e.on_line(i + 1))?;
+            if i == 0 {
+                names = n;
+            } else if n != names {
+                return Err(InputError::spanning(
+                    line,
+                    line,
+                    "body has different axes than the first one",
+                )
+                .on_line(i + 1));
+            }
+            positions.push(p);
+        }
+        let names: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+        Ok(Universe::new(&names, &positions))
     }
-}
 
-fn parse_moons(input: Vec<String>) -> Vec<Moon> {
-    input
-        .into_iter()
-        .map(|l| l.parse::<Point3D>().expect("could not parse moon"))
-        .map(Moon::new)
-        .collect()
-}
+    fn with_gravity(self, gravity: Gravity) -> Universe {
+        Universe { gravity, ..self }
+    }
 
-fn change_velocity(moons: &mut Vec<Moon>) {
-    let mcopy = moons.to_vec();
-    for m in moons {
-        for other in &mcopy {
-            m.add_velocity_from(other);
+    /// Steps after which the search for the cycle of an axis gives up.
+    fn with_cycle_limit(self, cycle_limit: u64) -> Universe {
+        Universe {
+            cycle_limit,
+            ..self
         }
     }
-}
 
-fn apply_velocity(moons: &mut Vec<Moon>) {
-    for m in moons {
-        m.apply_velocity();
+    fn bodies(&self) -> usize {
+        self.axes.first().map_or(0, |a| a.positions.len())
     }
-}
 
-fn simulate_moons(init: &[Moon], steps: usize) -> Vec<Moon> {
-    let mut moons = init.to_vec();
-    for _ in 0..steps {
-        change_velocity(&mut moons);
-        apply_velocity(&mut moons);
+    fn step(&mut self) -> Option<()> {
+        for axis in &mut self.axes {
+            axis.step(self.gravity)?;
+        }
+        Some(())
     }
-    moons
-}
 
-fn find_repeat(init: &[Moon]) -> usize {
-    let mut moons = init.to_vec();
-    let mut repeat_x: Option<usize> = None;
-    let mut repeat_y: Option<usize> = None;
-    let mut repeat_z: Option<usize> = None;
-    for s in 1.. {
-        change_velocity(&mut moons);
-        apply_velocity(&mut moons);
+    /// State after the steps, simulating every one of them.
+    fn simulate(&self, steps: u64) -> Result<Universe, InputError> {
+        let mut universe = self.clone();
+        for _ in 0..steps {
+            universe.step().ok_or_else(|| overflow(steps))?;
+        }
+        Ok(universe)
+    }
 
-        let (rx, ry, rz): (bool, bool, bool) = init
+    /// State after the steps, where each axis with a cycle only simulates
+    /// up to the end of its first cycle, and any other axis every step.
+    fn state_at(&self, steps: u64) -> Result<Universe, InputError> {
+        let axes = self
+            .axes
             .iter()
-            .zip(moons.iter())
-            .map(|(&m1, &m2)| {
-                (
-                    m1.position.x == m2.position.x && m1.velocity.x == m2.velocity.x,
-                    m1.position.y == m2.position.y && m1.velocity.y == m2.velocity.y,
-                    m1.position.z == m2.position.z && m1.velocity.z == m2.velocity.z,
-                )
+            .map(|a| {
+                let steps = a
+                    .cycle(self.gravity, self.cycle_limit)
+                    .map_or(steps, |c| c.reduce(steps));
+                a.after(steps, self.gravity).ok_or_else(|| overflow(steps))
             })
-            .fold((true, true, true), |(rx, ry, rz), (x, y, z)| {
-                (rx && x, ry && y, rz && z)
-            });
-        if repeat_x == None && rx {
-            repeat_x = Some(s);
-        }
-        if repeat_y == None && ry {
-            repeat_y = Some(s);
-        }
-        if repeat_z == None && rz {
-            repeat_z = Some(s);
-        }
-        if let (Some(x), Some(y), Some(z)) = (repeat_x, repeat_y, repeat_z) {
-            return lcm(lcm(x, y), z);
+            .collect::<Result<_, _>>()?;
+        Ok(Universe {
+            axes,
+            ..self.clone()
+        })
+    }
+
+    fn axis_periods(&self) -> Vec<Option<Cycle>> {
+        self.axes
+            .iter()
+            .map(|a| a.cycle(self.gravity, self.cycle_limit))
+            .collect()
+    }
+
+    /// Cycle of the whole system, if every axis has one.
+    fn cycle(&self) -> Option<Cycle> {
+        combined_cycle(&self.axis_periods())
+    }
+
+    /// Sum over the bodies of the product of potential and kinetic energy.
+    fn energy(&self) -> Result<i64, InputError> {
+        (0..self.bodies())
+            .try_fold(0i64, |sum, b| {
+                let potential = l1_norm(self.axes.iter().map(|a| a.positions[b]))?;
+                let kinetic = l1_norm(self.axes.iter().map(|a| a.velocities[b]))?;
+                sum.checked_add(potential.checked_mul(kinetic)?)
+            })
+            .ok_or_else(|| InputError::new("energy overflows"))
+    }
+
+    /// Energy at each step from the start up to the given step.
+    fn energy_history(&self, steps: u64) -> Result<Vec<i64>, InputError> {
+        let mut universe = self.clone();
+        let mut history = vec![universe.energy()?];
+        for _ in 0..steps {
+            universe.step().ok_or_else(|| overflow(steps))?;
+            history.push(universe.energy()?);
         }
+        Ok(history)
     }
-    unreachable!()
 }
 
-fn total_energy(moons: &[Moon]) -> i64 {
-    moons
-        .iter()
-        .map(|m| m.position.l1_norm() * m.velocity.l1_norm())
-        .sum()
+// non-negative number of steps given by an option
+fn steps_option(name: &str) -> Result<Option<u64>, InputError> {
+    common::option(name)
+        .map(|s| {
+            s.parse()
+                .map_err(|_| InputError::spanning(&s, &s, "expected a number of steps"))
+        })
+        .transpose()
 }
 
-fn main() {
-    let moons = parse_moons(common::get_lines());
+fn main() -> Result<(), InputError> {
+    let report = common::Report::new(2019, 12)?;
+    let mut universe = common::parse_content_with(|content| {
+        let lines: Vec<&str> = content.lines().collect();
+        Universe::parse(&lines)
+    })?;
+    if let Some(gravity) = common::option("gravity") {
+        universe = universe.with_gravity(gravity.parse()?);
+    }
+    if let Some(limit) = steps_option("cycle-limit")? {
+        universe = universe.with_cycle_limit(limit);
+    }
+
+    let result1 = universe.simulate(1000)?.energy()?;
+    report.part(
+        1,
+        result1,
+        format!("Part1: total energy after 1000 steps is {}", result1),
+    );
+
+    // independent of the search for the cycles, which may not find any
+    if report.format() == common::Format::Text {
+        if let Some(steps) = steps_option("history")? {
+            for (step, energy) in universe.energy_history(steps)?.iter().enumerate() {
+                println!("{:8} {}", step, energy);
+            }
+        }
+        if let Some(steps) = steps_option("at")? {
+            let state = universe.state_at(steps)?;
+            let energy = state.energy()?;
+            print!("After {} steps:\n{}", steps, state);
+            println!("Total energy: {}", energy);
+        }
+    }
+
+    let cycle = universe.cycle();
+    if report.format() == common::Format::Text && common::flag("periods") {
+        let describe = |c: Option<Cycle>| match c {
+            Some(c) => format!("period {} from step {}", c.period, c.start),
+            None => "no cycle found".to_string(),
+        };
+        for (name, c) in universe.names.iter().zip(universe.axis_periods()) {
+            println!("Axis {}: {}", name, describe(c));
+        }
+        println!("System: {}", describe(cycle));
+    }
 
-    let result_moons = simulate_moons(&moons, 1000);
-    let result1 = total_energy(&result_moons);
-    println!("Part1: total energy after 100 steps is {}", result1);
-    let result2 = find_repeat(&moons);
-    println!("Part2: universe repeats after {} steps", result2);
+    let cycle = cycle.ok_or_else(|| {
+        InputError::new(format!(
+            "universe does not repeat within {} steps per axis",
+            universe.cycle_limit
+        ))
+    })?;
+    let result2 = cycle.start + cycle.period;
+    report.part(
+        2,
+        result2,
+        format!("Part2: universe repeats after {} steps", result2),
+    );
+    Ok(())
 }
 
 #[cfg(test)]
@@ -214,35 +416,107 @@ mod tests {
 
     #[test]
     fn test_example1() {
-        let input = vec![
+        let input = [
             "<x=-1, y=0, z=2>",
             "<x=2, y=-10, z=-7>",
             "<x=4, y=-8, z=8>",
             "<x=3, y=5, z=-1>",
         ];
-        let init = parse_moons(input.iter().map(|s| s.to_string()).collect());
-        let moons = simulate_moons(&init, 10);
-        let result = total_energy(&moons);
+        let init = Universe::parse(&input).unwrap();
+        let moons = init.simulate(10).unwrap();
+        let result = moons.energy().unwrap();
         assert_eq!(result, 179);
-        let repeat_num = find_repeat(&init);
-        assert_eq!(repeat_num, 2772);
-        let repeat = simulate_moons(&init, 2772);
+        let repeat_num = init.cycle().unwrap();
+        assert_eq!(
+            repeat_num,
+            Cycle {
+                start: 0,
+                period: 2772
+            }
+        );
+        let repeat = init.simulate(2772).unwrap();
         assert_eq!(init, repeat);
+        assert_eq!(
+            moons.to_string().lines().next(),
+            Some("pos=<x=2, y=1, z=-3>, vel=<x=-3, y=-2, z=1>")
+        );
     }
 
     #[test]
     fn test_example2() {
-        let input = vec![
+        let input = [
             "<x=-8, y=-10, z=0>",
             "<x=5, y=5, z=10>",
             "<x=2, y=-7, z=3>",
             "<x=9, y=-8, z=-3>",
         ];
-        let moons = parse_moons(input.iter().map(|s| s.to_string()).collect());
-        let result_moons = simulate_moons(&moons, 100);
-        let result = total_energy(&result_moons);
+        let moons = Universe::parse(&input).unwrap();
+        let result_moons = moons.simulate(100).unwrap();
+        let result = result_moons.energy().unwrap();
         assert_eq!(result, 1940);
-        let repeat_num = find_repeat(&moons);
-        assert_eq!(repeat_num, 4_686_774_924);
+        let repeat_num = moons.cycle().unwrap();
+        assert_eq!(repeat_num.period, 4_686_774_924);
+        assert_eq!(moons.state_at(4_686_774_924 + 100).unwrap(), result_moons);
+    }
+
+    #[test]
+    fn test_dimensions() {
+        let universe = Universe::new(&["w"], &[vec![0], vec![4]]);
+        let periods = universe.axis_periods();
+        assert_eq!(periods.len(), 1);
+        // the bodies swap places after 4 steps and are back after 8
+        assert_eq!(periods[0].unwrap().period, 8);
+        assert_eq!(universe.energy_history(2).unwrap(), [0, 4, 8]);
+
+        let universe = Universe::parse(&["<a=1, b=2, c=3, d=4>", "<a=-1, b=0, c=3, d=9>"]).unwrap();
+        assert_eq!(universe.bodies(), 2);
+        let cycle = universe.cycle().unwrap();
+        assert_eq!(universe.simulate(cycle.period).unwrap(), universe);
+        assert_eq!(
+            universe.state_at(1000).unwrap(),
+            universe.simulate(1000).unwrap()
+        );
+
+        let error = Universe::parse(&["<x=1, y=2>", "<x=1, z=2>"]).unwrap_err();
+        assert_eq!(
+            error.message(),
+            "body has different axes than the first one"
+        );
+        assert_eq!(error.line(), Some(2));
+    }
+
+    #[test]
+    fn test_gravity() {
+        let input = ["<x=-1, y=0>", "<x=2, y=-10>", "<x=4, y=-8>"];
+        let universe = Universe::parse(&input)
+            .unwrap()
+            .with_gravity("clamped:3".parse().unwrap());
+        assert_eq!(
+            universe.state_at(5000).unwrap(),
+            universe.simulate(5000).unwrap()
+        );
+
+        // pulls in the opposite direction, so the bodies fly apart
+        let universe = Universe::parse(&input)
+            .unwrap()
+            .with_gravity("repulsive".parse().unwrap())
+            .with_cycle_limit(10_000);
+        let energy = universe.energy_history(20).unwrap();
+        assert!(energy[20] > energy[10]);
+        assert_eq!(universe.cycle(), None);
+        assert_eq!(universe.axis_periods(), [None, None]);
+        assert_eq!(
+            universe.state_at(20_000).unwrap(),
+            universe.simulate(20_000).unwrap()
+        );
+
+        // the pull grows with the distance, so the coordinates overflow
+        let universe = Universe::parse(&input)
+            .unwrap()
+            .with_gravity(Gravity::Custom(|d| -d));
+        assert_eq!(universe.cycle(), None);
+        let error = universe.state_at(1000).unwrap_err();
+        assert_eq!(error.message(), "coordinates overflow within 1000 steps");
+        assert!("clamped:0".parse::<Gravity>().is_err());
     }
 }
@@ -1,12 +1,14 @@
-use ansi_term::{Colour, Style};
-use std::collections::HashMap;
-use std::iter;
-use std::sync::mpsc::Receiver;
-use std::sync::mpsc::Sender;
-use std::sync::{Arc, Mutex};
+use ansi_term::{ANSIString, Colour, Style};
+use std::io::{self, Write};
 use std::{thread, time};
 mod common;
+use common::asciicast::{self, Recording};
+use common::geometry::Point2D;
+use common::grid::{Grid, SparseGrid};
+use common::image::{self, Palette, Raster, BLACK, WHITE};
 use common::intcode;
+use common::terminal::{self, Key, RawTerminal};
+use common::InputError;
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 enum Tile {
@@ -17,69 +19,91 @@ enum Tile {
     Ball,
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Hash)]
-struct Point2D {
-    x: i64,
-    y: i64,
-}
-
+#[derive(Clone)]
 struct Screen {
-    board: Vec<Vec<Tile>>,
+    board: Grid<Tile>,
 }
 
 impl Screen {
     fn new(width: usize, height: usize) -> Screen {
         Screen {
-            board: vec![vec![Tile::Empty; width]; height],
+            board: Grid::new(width, height, Tile::Empty),
         }
     }
     #[allow(dead_code)]
     fn get(&self, x: usize, y: usize) -> Tile {
-        self.board[y][x]
+        self.board[Point2D::new(x as i64, y as i64)]
     }
     fn set(&mut self, x: usize, y: usize, t: Tile) {
-        self.board[y][x] = t;
+        self.board[Point2D::new(x as i64, y as i64)] = t;
     }
     fn count(&self, t: Tile) -> usize {
-        self.board
-            .iter()
-            .flat_map(|r| r.iter())
-            .filter(|&&u| u == t)
-            .count()
+        self.board.values().filter(|&&u| u == t).count()
     }
     fn get_position_of(&self, t: Tile) -> Option<Point2D> {
-        for y in 0..self.board.len() {
-            for x in 0..self.board[y].len() {
-                if self.board[y][x] == t {
-                    return Some(Point2D {
-                        x: x as i64,
-                        y: y as i64,
-                    });
-                }
-            }
+        self.board.position(|&u| u == t)
+    }
+    fn glyph(t: Tile) -> ANSIString<'static> {
+        match t {
+            Tile::Empty => Style::new().on(Colour::White).paint(" "),
+            Tile::Wall => Style::new().on(Colour::Black).paint(" "),
+            Tile::Block => Style::new()
+                .fg(Colour::Blue)
+                .on(Colour::White)
+                .paint("\u{25A0}"),
+            Tile::Paddle => Style::new()
+                .fg(Colour::Red)
+                .on(Colour::White)
+                .bold()
+                .paint("\u{2015}"),
+            Tile::Ball => Style::new()
+                .fg(Colour::Red)
+                .on(Colour::White)
+                .paint("\u{25CF}"),
         }
-        None
     }
     fn print(&self) {
-        let empty_style = Style::new().on(Colour::White);
-        let block_style = Style::new().fg(Colour::Blue).on(Colour::White);
-        let wall_style = Style::new().on(Colour::Black);
-        let paddle_style = Style::new().fg(Colour::Red).on(Colour::White).bold();
-        let ball_style = Style::new().fg(Colour::Red).on(Colour::White);
-        for row in &self.board {
-            for t in row {
-                let out = match t {
-                    Tile::Empty => empty_style.paint(" "),
-                    Tile::Wall => wall_style.paint(" "),
-                    Tile::Block => block_style.paint("\u{25A0}"),
-                    Tile::Paddle => paddle_style.paint("\u{2015}"),
-                    Tile::Ball => ball_style.paint("\u{25CF}"),
-                };
-                print!("{}", out);
+        print!("{}", self.board.render(|&t| Screen::glyph(t)));
+    }
+
+    // terminal output that turns the previously drawn screen into this one,
+    // redrawing everything if there is none
+    fn draw_changes(&self, previous: Option<&Screen>) -> String {
+        match previous {
+            Some(previous) if previous.board.width() == self.board.width() => {
+                let mut out = String::new();
+                for (p, &t) in self.board.iter() {
+                    if previous.board[p] != t {
+                        out.push_str(&terminal::move_to(p.x as usize, p.y as usize));
+                        out.push_str(&Screen::glyph(t).to_string());
+                    }
+                }
+                out
             }
-            println!();
+            _ => format!(
+                "{}{}",
+                terminal::CLEAR,
+                self.board
+                    .render(|&t| Screen::glyph(t))
+                    .replace('\n', "\r\n")
+            ),
         }
     }
+
+    // empty, wall, block, paddle and ball tiles
+    fn default_palette() -> Palette {
+        Palette(vec![
+            WHITE,
+            BLACK,
+            [0x1f, 0x4e, 0xc8],
+            [0xc8, 0x1f, 0x1f],
+            [0xe8, 0x8a, 0x10],
+        ])
+    }
+
+    fn to_image(&self, style: &image::Style) -> Result<Raster, InputError> {
+        style.render(&self.board, |&t| t as usize)
+    }
 }
 
 fn parse_tile(x: i64) -> Tile {
@@ -94,23 +118,20 @@ fn parse_tile(x: i64) -> Tile {
 }
 
 fn construct_screen(output: &[i64]) -> Screen {
-    let mut tiles: HashMap<Point2D, Tile> = HashMap::new();
+    let mut tiles = SparseGrid::new(Tile::Empty);
 
-    for c in output.chunks(3) {
+    // skip the score, which is output at (-1, 0)
+    for c in output.chunks(3).filter(|c| c[0] != -1 || c[1] != 0) {
         let p = Point2D { x: c[0], y: c[1] };
         let t = parse_tile(c[2]);
-        tiles.insert(p, t);
+        tiles.set(p, t);
     }
 
-    let min_x = tiles.keys().map(|&p| p.x).min().unwrap_or(0);
-    let max_x = tiles.keys().map(|&p| p.x).max().unwrap_or(0);
-    let min_y = tiles.keys().map(|&p| p.y).min().unwrap_or(0);
-    let max_y = tiles.keys().map(|&p| p.y).max().unwrap_or(0);
-
-    assert!(min_x >= 0);
-    assert!(min_y >= 0);
+    let (min, max) = tiles.bounds().unwrap_or_default();
+    assert!(min.x >= 0);
+    assert!(min.y >= 0);
 
-    let mut screen = Screen::new((max_x + 1) as usize, (max_y + 1) as usize);
+    let mut screen = Screen::new((max.x + 1) as usize, (max.y + 1) as usize);
 
     for (p, &t) in tiles.iter() {
         screen.set(p.x as usize, p.y as usize, t);
@@ -124,162 +145,291 @@ fn empty_run(code: &[i64]) -> Screen {
     construct_screen(&output)
 }
 
-fn outputter(or: Receiver<Option<i64>>, screen: Arc<Mutex<Screen>>, mut score: i64) {
-    screen.lock().unwrap().print();
-    println!("Score: {}", score);
+/// Outcome of a game played by the autopilot.
+#[derive(Debug, PartialEq, Eq)]
+struct GameResult {
+    score: i64,
+    blocks_broken: usize,
+    frames: usize,
+}
 
-    while let Ok(Some(x)) = or.recv() {
-        let y = or.recv().unwrap().unwrap();
-        let z = or.recv().unwrap().unwrap();
-        if x == -1 && y == 0 {
-            score = z;
+// joystick input that moves the paddle below the ball
+fn follow_ball(screen: &Screen) -> i64 {
+    let ball = screen.get_position_of(Tile::Ball);
+    let paddle = screen.get_position_of(Tile::Paddle);
+    match (ball, paddle) {
+        (Some(b), Some(p)) => (b.x - p.x).signum(),
+        _ => 0,
+    }
+}
+
+// draws the tiles and updates the score from output triples
+fn apply_output(output: &[i64], screen: &mut Screen, score: &mut i64) {
+    for c in output.chunks(3) {
+        if c[0] == -1 && c[1] == 0 {
+            *score = c[2];
         } else {
-            let t = parse_tile(z);
-            screen.lock().unwrap().set(x as usize, y as usize, t);
+            screen.set(c[0] as usize, c[1] as usize, parse_tile(c[2]));
         }
-        print!("{}[2J", 27 as char);
-        screen.lock().unwrap().print();
-        println!("Score: {}", score);
     }
 }
 
-fn inputter(is: Sender<i64>, screen: Arc<Mutex<Screen>>) {
-    let mut inputs = Vec::new();
-    loop {
-        let update_time = time::Duration::from_millis(100);
-        thread::sleep(update_time);
-        let paddle;
-        let ball;
-        {
-            let guard = screen.lock().unwrap();
-            paddle = guard.get_position_of(Tile::Paddle);
-            ball = guard.get_position_of(Tile::Ball);
-        }
-        let signal = match (ball, paddle) {
-            (Some(pb), Some(pp)) => Some((pb.x - pp.x).signum()),
-            _ => None,
-        };
-        if let Some(x) = signal {
-            let r = is.send(x);
-            if r.is_err() {
-                println!("Set of inputs: {:?}", inputs);
-                println!("Error while sending");
-                return;
-            }
-            inputs.push(x);
+/// Running game with coins inserted and the joystick inputs so far.
+#[derive(Clone)]
+struct Game {
+    machine: intcode::Machine,
+    screen: Screen,
+    score: i64,
+    inputs: Vec<i64>,
+}
+
+impl Game {
+    fn new(code: &[i64]) -> Game {
+        let mut with_coins = code.to_vec();
+        with_coins[0] = 2;
+        let mut machine = intcode::Machine::new(&with_coins);
+        let output = machine.run_to_input();
+        let mut screen = construct_screen(&output);
+        let mut score = 0;
+        apply_output(&output, &mut screen, &mut score);
+        Game {
+            machine,
+            screen,
+            score,
+            inputs: Vec::new(),
         }
     }
-}
 
-#[allow(dead_code)]
-fn play_game(code: &[i64]) {
-    let mut with_coins = code.to_vec();
-    with_coins[0] = 2;
-    let intcode::ProgramRunner { program, io } = intcode::ProgramRunner::new(&with_coins);
-    let intcode::ProgramIO { is, or } = io;
+    fn is_over(&self) -> bool {
+        self.machine.is_halted()
+    }
 
-    let program_thread = std::thread::spawn(move || {
-        program.run();
-    });
-    let mut initial_output = Vec::new();
-    let score;
+    // terminal output for the game with a status line below the screen,
+    // redrawing only what changed since the previously drawn screen
+    fn draw(&self, previous: Option<&Screen>, status: &str) -> String {
+        let mut out = self.screen.draw_changes(previous);
+        out.push_str(&terminal::move_to(0, self.screen.board.height()));
+        out.push_str(&format!("Score: {:<8} {}", self.score, status));
+        out
+    }
 
+    // runs the game until it needs the next joystick input
+    fn step(&mut self, joystick: i64) {
+        self.machine.push_input(joystick);
+        self.inputs.push(joystick);
+        let output = self.machine.run_to_input();
+        apply_output(&output, &mut self.screen, &mut self.score);
+    }
+}
+
+/// Plays the game with the inputs chosen by `joystick`. `frame` gets the
+/// game whenever it waits for the joystick and once more after it has ended.
+fn play<J, F>(code: &[i64], mut joystick: J, mut frame: F) -> Result<GameResult, InputError>
+where
+    J: FnMut(&Screen) -> i64,
+    F: FnMut(&Game) -> Result<(), InputError>,
+{
+    let mut game = Game::new(code);
+    let blocks = game.screen.count(Tile::Block);
+    let mut frames = 0;
     loop {
-        match or.recv() {
-            Err(_) => {
-                println!("Error receiving output");
-                return;
-            }
-            Ok(None) => {
-                println!("Game halted prematurely");
-                return;
-            }
-            Ok(Some(x)) => {
-                if x == -1 {
-                    let _ = or.recv();
-                    score = or.recv().unwrap().expect("expected score");
-                    break;
-                } else {
-                    initial_output.push(x);
-                }
-            }
+        frames += 1;
+        frame(&game)?;
+        if game.is_over() {
+            break;
         }
+        let input = joystick(&game.screen);
+        game.step(input);
     }
+    Ok(GameResult {
+        score: game.score,
+        blocks_broken: blocks - game.screen.count(Tile::Block),
+        frames,
+    })
+}
 
-    let screen = construct_screen(&initial_output);
+/// Plays the game always moving the paddle towards the ball.
+fn autopilot<F>(code: &[i64], frame: F) -> Result<GameResult, InputError>
+where
+    F: FnMut(&Game) -> Result<(), InputError>,
+{
+    play(code, follow_ball, frame)
+}
+
+/// Plays the game with logged joystick inputs, keeping the joystick in the
+/// middle once the log is exhausted.
+fn replay<F>(code: &[i64], log: &[i64], frame: F) -> Result<GameResult, InputError>
+where
+    F: FnMut(&Game) -> Result<(), InputError>,
+{
+    let mut inputs = log.iter();
+    play(code, |_| inputs.next().copied().unwrap_or(0), frame)
+}
 
-    let mutex = std::sync::Mutex::new(screen);
-    let arc = std::sync::Arc::new(mutex);
+// instructions per second of recording time, which shows about 40 frames
+// of the game per second
+const CAST_SPEED: u64 = 5_000;
+// recordings are at least as wide as the score line
+const CAST_MIN_WIDTH: usize = 40;
 
-    let arc_out = arc.clone();
-    let output_thread = std::thread::spawn(move || {
-        outputter(or, arc_out, score);
-    });
-    let arc_in = arc.clone();
-    let input_thread = std::thread::spawn(move || {
-        inputter(is, arc_in);
-    });
+// milliseconds between frames when playing in the terminal
+const TICK: u64 = 100;
+// number of frames to go back when rewinding while running
+const REWIND_FRAMES: usize = 20;
 
-    program_thread.join().expect("could not join thread");
-    output_thread.join().expect("could not join thread");
-    input_thread.join().expect("could not join thread");
+fn terminal_error(e: io::Error) -> InputError {
+    InputError::new(format!("terminal error: {}", e))
 }
 
-fn winning() -> Vec<i64> {
-    // winning sequence obtained by playing the game
-    let compressed = vec![
-        4, 23, 27, 7, 8, 28, 14, 7, 30, 3, 3, 4, 1, 1, 2, 9, 11, 7, 7, 1, 1, 22, 1, 2, 23, 23, 14,
-        13, 13, 15, 1, 7, 1, 1, 2, 3, 1, 2, 3, 4, 6, 5, 24, 10, 2, 17, 19, 20, 1, 2, 19, 18, 17,
-        17, 18, 2, 11, 11, 14, 15, 19, 11, 11, 3, 3, 12, 3, 28, 28, 17, 19, 16, 17, 31, 13, 13, 1,
-        1, 14, 1, 3, 3, 16, 5, 2, 4, 15, 31, 14, 20, 37, 4, 4, 16, 16, 4, 4, 18, 18, 29, 22, 30,
-        14, 14, 7, 7, 15, 3, 17, 29, 13, 13, 11, 11, 14, 14, 31, 20, 1, 2, 20, 20, 22, 32, 16, 16,
-        10, 10, 16, 16, 14, 3, 5, 6, 27, 17, 17, 2, 2, 18, 1, 17, 18, 21, 5, 5, 21, 21, 31, 28, 3,
-        6, 21, 21, 6, 6, 22, 1, 2, 23, 21, 21, 25, 25, 1, 1, 26, 26, 2, 2, 27, 27, 2, 2, 27, 27, 3,
-        3, 28, 2, 1, 3, 4, 3, 3, 28, 3, 3, 29, 29, 6, 2, 4, 5, 8, 11, 31, 31, 1, 1, 32, 6, 7, 33,
-        37, 18, 18, 14, 14, 19, 1, 2, 20, 7, 7, 21, 21, 37, 8, 8, 27, 27, 10, 10, 29, 29, 11, 11,
-        30, 30, 4, 4, 31, 31, 5, 5, 5, 5, 22, 22, 6, 6, 31, 31, 34, 34, 30, 8, 15, 22, 2, 2, 4, 19,
-        14, 14, 37, 22, 22, 37, 15, 15, 37, 37, 30, 30, 14, 14, 31, 31, 37, 37, 37, 23, 23, 37, 37,
-        37, 32, 32, 37, 37, 37, 27, 1, 2, 3, 12, 32, 32, 16, 16, 20, 20, 37, 37, 32, 32, 37, 37,
-        33, 33, 35, 35, 33, 33, 37, 37, 37, 3, 3, 5, 5, 37, 34, 34, 37, 37, 37, 37, 37, 37, 37, 3,
-    ];
-    let mut winning = vec![0, 0, 0];
-    let mut direction = 1;
-    for n in compressed {
-        winning.extend(iter::repeat(direction).take(n));
-        direction = -direction;
+fn tick_from_args() -> Result<time::Duration, InputError> {
+    match common::option("tick") {
+        None => Ok(time::Duration::from_millis(TICK)),
+        Some(s) => match s.parse() {
+            Ok(tick) if tick > 0 => Ok(time::Duration::from_millis(tick)),
+            _ => Err(InputError::spanning(
+                &s,
+                &s,
+                "tick must be a positive number of milliseconds",
+            )),
+        },
     }
-    winning
 }
 
-fn winning_run(code: &[i64]) -> i64 {
-    let mut with_coins = code.to_vec();
-    with_coins[0] = 2;
-    let p = intcode::ProgramRunner::new(&with_coins);
-    let w = winning();
-    let output = p.run_with(&w);
-    *output.last().unwrap()
+/// Lets a human play the game in the terminal. The game advances one frame
+/// per tick, with the paddle following the arrow keys. `p` pauses, `r`
+/// rewinds and `q` or Ctrl-C quits. Returns the joystick inputs that were played.
+fn play_interactive(code: &[i64], tick: time::Duration) -> Result<Vec<i64>, InputError> {
+    let mut terminal = RawTerminal::enable().map_err(terminal_error)?;
+    let mut game = Game::new(code);
+    // snapshots of the game before each input, to rewind to
+    let mut snapshots: Vec<Game> = Vec::new();
+    let mut drawn: Option<Screen> = None;
+    let mut paused = false;
+    loop {
+        let status = if game.is_over() {
+            "game over, r to rewind or q to quit"
+        } else if paused {
+            "paused, p to continue, r to step back"
+        } else {
+            "\u{2190} \u{2192} to move, p to pause, r to rewind, q to quit"
+        };
+        print!("{}", game.draw(drawn.as_ref(), &format!("{:<60}", status)));
+        io::stdout().flush().map_err(terminal_error)?;
+        drawn = Some(game.screen.clone());
+
+        let start = time::Instant::now();
+        let mut joystick = 0;
+        for key in terminal.read_keys().map_err(terminal_error)? {
+            match key {
+                Key::Left => joystick = -1,
+                Key::Right => joystick = 1,
+                Key::Char('p') | Key::Char(' ') => paused = !paused,
+                Key::Char('r') => {
+                    let frames = if paused || game.is_over() {
+                        1
+                    } else {
+                        REWIND_FRAMES
+                    };
+                    let keep = snapshots.len().saturating_sub(frames);
+                    if let Some(snapshot) = snapshots.drain(keep..).next() {
+                        game = snapshot;
+                    }
+                }
+                Key::Char('q') | Key::Escape | Key::Interrupt => {
+                    drop(terminal);
+                    println!();
+                    return Ok(game.inputs);
+                }
+                _ => (),
+            }
+        }
+        if !paused && !game.is_over() {
+            snapshots.push(game.clone());
+            game.step(joystick);
+        }
+        if let Some(rest) = tick.checked_sub(start.elapsed()) {
+            thread::sleep(rest);
+        }
+    }
 }
 
-fn main() {
-    let input: Vec<Vec<i64>> = common::get_lines()
-        .into_iter()
-        .map(|l| {
-            l.split(',')
-                .map(|i| i.parse::<i64>().expect("could not parse number"))
-                .collect()
-        })
-        .collect();
+fn parse_log(s: &str) -> Result<Vec<i64>, InputError> {
+    common::parse_separated(s.trim(), ',')
+}
+
+fn main() -> Result<(), InputError> {
+    let report = common::Report::new(2019, 13)?;
+    let input: Vec<Vec<i64>> = common::get_separated_input(',')?;
+    let style = image::Style::from_args(Screen::default_palette())?;
+    let frames = common::option("frames");
+    let cast_speed = asciicast::speed_from_args(CAST_SPEED)?;
     for program in input {
+        if common::flag("play") {
+            let inputs = play_interactive(&program, tick_from_args()?)?;
+            if let Some(filename) = common::option("log") {
+                let log: Vec<_> = inputs.iter().map(|i| i.to_string()).collect();
+                common::write_file(&filename, log.join(",").as_bytes())?;
+            }
+            continue;
+        }
         let screen = empty_run(&program);
-        screen.print();
+        if report.format() == common::Format::Text {
+            screen.print();
+        }
         let result1 = screen.count(Tile::Block);
-        println!("Part1: screen has {} block tiles", result1);
-
-        //play_game(&program);
+        report.part(
+            1,
+            result1,
+            format!("Part1: screen has {} block tiles", result1),
+        );
+        if let Some(filename) = common::option("image") {
+            screen.to_image(&style)?.write(&filename)?;
+        }
 
-        let result2 = winning_run(&program);
-        println!("Part2: winning run has score {}", result2);
+        let cast_filename = common::option("cast");
+        let mut cast = cast_filename.as_ref().map(|_| {
+            let width = screen.board.width().max(CAST_MIN_WIDTH);
+            Recording::new(width, screen.board.height() + 1).with_title("2019 day 13")
+        });
+        let mut drawn: Option<Screen> = None;
+        let mut start_steps = None;
+        let mut frame = 0;
+        let on_frame = |game: &Game| {
+            frame += 1;
+            if let Some(filename) = &frames {
+                let frame_filename = image::frame_filename(filename, frame);
+                game.screen.to_image(&style)?.write(&frame_filename)?;
+            }
+            if let Some(cast) = &mut cast {
+                // the recording starts with the first frame
+                let steps = game.machine.steps() - *start_steps.get_or_insert(game.machine.steps());
+                let time = asciicast::instruction_time(steps, cast_speed);
+                cast.output(time, &game.draw(drawn.as_ref(), ""));
+                drawn = Some(game.screen.clone());
+            }
+            Ok(())
+        };
+        let (player, result2) = match common::option("replay") {
+            Some(filename) => {
+                let log =
+                    parse_log(&common::read_file(&filename)?).map_err(|e| e.in_file(&filename))?;
+                ("replay", replay(&program, &log, on_frame)?)
+            }
+            None => ("autopilot", autopilot(&program, on_frame)?),
+        };
+        report.part(
+            2,
+            result2.score,
+            format!(
+                "Part2: {} ended with score {} after breaking {} blocks in {} frames",
+                player, result2.score, result2.blocks_broken, result2.frames
+            ),
+        );
+        if let (Some(cast), Some(filename)) = (cast, cast_filename) {
+            cast.write(&filename)?;
+        }
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -289,7 +439,7 @@ mod tests {
     #[test]
     fn test() {
         let program = vec![104, 1, 104, 2, 104, 3, 104, 6, 104, 5, 104, 4, 99];
-        let screen = construct_screen(&program);
+        let screen = empty_run(&program);
         let paddles = screen.count(Tile::Paddle);
         let balls = screen.count(Tile::Ball);
         assert_eq!(paddles, 1);
@@ -297,4 +447,122 @@ mod tests {
         assert_eq!(screen.get(1, 2), Tile::Paddle);
         assert_eq!(screen.get(6, 5), Tile::Ball);
     }
+
+    #[test]
+    fn test_autopilot() {
+        // overwrites the coin slot at address 0, draws a paddle and a ball
+        // to its right, then outputs the joystick input as score
+        let program = vec![
+            1, 0, 0, 0, 104, 0, 104, 0, 104, 3, 104, 2, 104, 0, 104, 4, 104, -1, 104, 0, 104, 0, 3,
+            100, 104, -1, 104, 0, 4, 100, 99,
+        ];
+        let mut scores = Vec::new();
+        let result = autopilot(&program, |game| {
+            assert_eq!(game.screen.get(2, 0), Tile::Ball);
+            scores.push(game.score);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(scores, vec![0, 1]);
+        assert_eq!(
+            result,
+            GameResult {
+                score: 1,
+                blocks_broken: 0,
+                frames: 2
+            }
+        );
+
+        let style = image::Style {
+            scale: 2,
+            palette: Screen::default_palette(),
+        };
+        let mut raster = None;
+        autopilot(&program, |game| {
+            raster = Some(game.screen.to_image(&style).unwrap());
+            Ok(())
+        })
+        .unwrap();
+        let raster = raster.unwrap();
+        assert_eq!(raster.width(), 6);
+        assert!(raster.to_ppm().starts_with(b"P6\n6 2\n255\n"));
+    }
+
+    #[test]
+    fn test_replay() {
+        let program = vec![
+            1, 0, 0, 0, 104, 0, 104, 0, 104, 3, 104, 2, 104, 0, 104, 4, 104, -1, 104, 0, 104, 0, 3,
+            100, 104, -1, 104, 0, 4, 100, 99,
+        ];
+        let log = parse_log("-1,0\n").unwrap();
+        let result = replay(&program, &log, |_| Ok(())).unwrap();
+        assert_eq!(result.score, -1);
+    }
+
+    #[test]
+    fn test_play() {
+        // draws a block, a paddle and a ball, then removes the block and
+        // outputs ten times the joystick input as score
+        let program = vec![
+            1, 0, 0, 0, 104, 1, 104, 0, 104, 2, 104, 0, 104, 1, 104, 3, 104, 2, 104, 0, 104, 4,
+            104, -1, 104, 0, 104, 0, 3, 100, 1002, 100, 10, 100, 104, 1, 104, 0, 104, 0, 104, -1,
+            104, 0, 4, 100, 99,
+        ];
+        let mut blocks = Vec::new();
+        let result = play(
+            &program,
+            |_| -1,
+            |game| {
+                blocks.push(game.screen.count(Tile::Block));
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(blocks, vec![1, 0]);
+        assert_eq!(
+            result,
+            GameResult {
+                score: -10,
+                blocks_broken: 1,
+                frames: 2
+            }
+        );
+        // the autopilot moves the paddle right towards the ball
+        let result = autopilot(&program, |_| Ok(())).unwrap();
+        assert_eq!((result.score, result.blocks_broken), (10, 1));
+        let result = replay(&program, &[], |_| Ok(())).unwrap();
+        assert_eq!((result.score, result.blocks_broken), (0, 1));
+    }
+
+    #[test]
+    fn test_draw_changes() {
+        let mut screen = Screen::new(3, 2);
+        let full = screen.draw_changes(None);
+        assert!(full.starts_with(terminal::CLEAR));
+        assert_eq!(full.matches("\r\n").count(), 2);
+
+        let previous = screen.clone();
+        assert_eq!(screen.draw_changes(Some(&previous)), "");
+        screen.set(2, 1, Tile::Ball);
+        assert_eq!(
+            screen.draw_changes(Some(&previous)),
+            format!("\x1b[2;3H{}", Screen::glyph(Tile::Ball))
+        );
+    }
+
+    #[test]
+    fn test_keys() {
+        let keys = terminal::parse_keys(b"\x1b[D\x1b[Cpq\x03\x1b");
+        assert_eq!(
+            keys,
+            vec![
+                Key::Left,
+                Key::Right,
+                Key::Char('p'),
+                Key::Char('q'),
+                Key::Interrupt,
+                Key::Escape
+            ]
+        );
+    }
 }
@@ -1,24 +1,27 @@
 use regex::Regex;
-use std::collections::hash_map::{Entry, HashMap};
-use std::collections::VecDeque;
+use std::fmt;
 use std::str::FromStr;
 mod common;
+use common::graph::{Cycle, Direction, Graph, NodeId};
+use common::InputError;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 struct ChemicalAmount {
-    amount: u64,
+    amount: u128,
     chemical: String,
 }
 
 impl FromStr for ChemicalAmount {
-    type Err = &'static str;
+    type Err = InputError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let re = Regex::new("(?P<amount>[0-9]*) (?P<chemical>[A-Z]*)").unwrap();
-        let caps = re.captures(s).ok_or("could not chemical amount")?;
+        let caps = re
+            .captures(s)
+            .ok_or_else(|| InputError::spanning(s, s, "could not parse chemical amount"))?;
         let amount = caps["amount"]
-            .parse::<u64>()
-            .map_err(|_| "could not parse amount")?;
+            .parse::<u128>()
+            .map_err(|_| InputError::spanning(s, &caps["amount"], "could not parse amount"))?;
         let chemical = caps["chemical"].to_string();
         Ok(ChemicalAmount { amount, chemical })
     }
@@ -31,161 +34,460 @@ struct Reaction {
 }
 
 impl FromStr for Reaction {
-    type Err = &'static str;
+    type Err = InputError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let re = Regex::new("(?P<lhs>[0-9]* [A-Z]*(, [0-9]* [A-Z]*)*) => (?P<rhs>[0-9]* [A-Z]*)")
             .unwrap();
-        let caps = re.captures(s).ok_or("could not parse reaction")?;
+        let caps = re
+            .captures(s)
+            .ok_or_else(|| InputError::spanning(s, s, "could not parse reaction"))?;
         let lhs = caps["lhs"]
             .split(", ")
-            .map(|s| s.parse::<ChemicalAmount>())
+            .map(|c| c.parse::<ChemicalAmount>().map_err(|e| e.within(s, c)))
             .collect::<Result<Vec<_>, _>>()?;
-        let rhs = caps["rhs"].parse::<ChemicalAmount>()?;
+        let rhs_str = &caps["rhs"];
+        let rhs = rhs_str
+            .parse::<ChemicalAmount>()
+            .map_err(|e| e.within(s, rhs_str))?;
         Ok(Reaction { lhs, rhs })
     }
 }
 
-struct ReactionGraph<'a> {
-    nodes: HashMap<String, &'a Reaction>,
+// edges go from the product of a reaction to its ingredients,
+// weighted with the amount of the ingredient that is needed
+struct ReactionGraph {
+    graph: Graph<u128>,
+    // amount produced by the reaction for a chemical, if there is one
+    produced: Vec<Option<u128>>,
 }
 
-impl<'a> ReactionGraph<'a> {
-    fn topological_sort(&self) -> Result<Vec<String>, &'static str> {
-        let mut incoming_count: HashMap<String, u64> = HashMap::new();
-        for r in self.nodes.values() {
-            incoming_count.entry(r.rhs.chemical.clone()).or_insert(0);
-            for lhs in r.lhs.iter() {
-                *incoming_count.entry(lhs.chemical.clone()).or_insert(0) += 1;
-            }
+impl ReactionGraph {
+    fn topological_sort(&self) -> Result<Vec<NodeId>, InputError> {
+        self.graph.topological_order().map_err(|Cycle(cycle)| {
+            let names: Vec<_> = cycle.iter().map(|&c| self.graph.name(c)).collect();
+            InputError::new(format!("reactions form a cycle: {}", names.join(" -> ")))
+        })
+    }
+
+    // DOT graph of the reactions, labelling each edge with the amount of the
+    // ingredient and highlighting everything the chemical is produced from
+    fn to_dot(&self, highlight: Option<&str>) -> String {
+        let highlight = highlight
+            .and_then(|chemical| self.graph.id(chemical))
+            .map(|id| self.graph.dfs(id, Direction::Forward))
+            .unwrap_or_default();
+        self.graph
+            .to_dot(|amount| Some(amount.to_string()), &highlight)
+    }
+}
+
+fn build_reaction_graph(reactions: &[Reaction]) -> Result<ReactionGraph, InputError> {
+    let mut graph = Graph::new();
+    let mut produced = Vec::new();
+    for r in reactions {
+        let product = graph.node(&r.rhs.chemical);
+        produced.resize(graph.len(), None);
+        if produced[product].is_some() {
+            return Err(InputError::new("duplicate chemical on right hand side"));
         }
-        let mut queue: VecDeque<String> = VecDeque::new();
-        for (s, &i) in incoming_count.iter() {
-            if i == 0 {
-                queue.push_back(s.clone());
-            }
+        produced[product] = Some(r.rhs.amount);
+        for lhs in r.lhs.iter() {
+            let ingredient = graph.node(&lhs.chemical);
+            graph.add_edge(product, ingredient, lhs.amount);
         }
-        let mut order = Vec::new();
-        while let Some(s) = queue.pop_front() {
-            for lhs in self.nodes.get(&s).iter().flat_map(|r| r.lhs.iter()) {
-                let c = incoming_count.get_mut(&lhs.chemical).unwrap();
-                *c -= 1;
-                if *c == 0 {
-                    queue.push_back(lhs.chemical.clone())
+    }
+    produced.resize(graph.len(), None);
+    Ok(ReactionGraph { graph, produced })
+}
+
+// comma separated amounts like "1 FUEL, 3 A"
+fn parse_amounts(s: &str) -> Result<Vec<ChemicalAmount>, InputError> {
+    s.split(',')
+        .map(|c| {
+            c.trim()
+                .parse::<ChemicalAmount>()
+                .map_err(|e| e.within(s, c))
+        })
+        .collect()
+}
+
+/// What happens to one chemical in a production plan.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+struct Usage {
+    /// amount used by reactions or asked for as a target
+    demand: u128,
+    /// part of the demand taken from the inventory
+    from_inventory: u128,
+    /// times the reaction producing the chemical fires
+    applications: u128,
+    produced: u128,
+    /// amount of raw material needed beyond the inventory
+    raw: u128,
+    /// overproduction and unused inventory
+    surplus: u128,
+}
+
+/// What prevents producing more of a chemical.
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Limit {
+    /// the raw material that runs out first
+    Budget(String),
+    /// amounts no longer fit into 64 bits
+    Overflow,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Budget(chemical) => write!(f, "{}", chemical),
+            Limit::Overflow => write!(f, "overflow"),
+        }
+    }
+}
+
+/// Reactions needed to produce target chemicals from raw materials, which
+/// are the chemicals no reaction produces.
+struct Plan<'a> {
+    graph: &'a ReactionGraph,
+    order: &'a [NodeId],
+    targets: Vec<NodeId>,
+    usage: Vec<Usage>,
+}
+
+impl ReactionGraph {
+    fn chemical_id(&self, chemical: &str) -> Result<NodeId, InputError> {
+        self.graph
+            .id(chemical)
+            .ok_or_else(|| InputError::new(format!("unknown chemical {}", chemical)))
+    }
+
+    fn overflow(&self, id: NodeId) -> InputError {
+        InputError::new(format!("amount of {} overflows", self.graph.name(id)))
+    }
+
+    // the inventory is used before running any reaction
+    fn plan<'a>(
+        &'a self,
+        order: &'a [NodeId],
+        targets: &[ChemicalAmount],
+        inventory: &[ChemicalAmount],
+    ) -> Result<Plan<'a>, InputError> {
+        let mut usage = vec![Usage::default(); self.graph.len()];
+        let mut stock = vec![0u128; self.graph.len()];
+        for c in inventory {
+            let id = self.chemical_id(&c.chemical)?;
+            stock[id] = stock[id]
+                .checked_add(c.amount)
+                .ok_or_else(|| self.overflow(id))?;
+        }
+        let mut target_ids = Vec::new();
+        for c in targets {
+            let id = self.chemical_id(&c.chemical)?;
+            usage[id].demand = usage[id]
+                .demand
+                .checked_add(c.amount)
+                .ok_or_else(|| self.overflow(id))?;
+            target_ids.push(id);
+        }
+        // the order has products before their ingredients, so the demand
+        // for a chemical is complete when it is reached
+        for &id in order {
+            let u = &mut usage[id];
+            u.from_inventory = u.demand.min(stock[id]);
+            let missing = u.demand - u.from_inventory;
+            u.surplus = stock[id] - u.from_inventory;
+            match self.produced[id] {
+                Some(produced) => {
+                    // integer version of ceil(missing / produced)
+                    u.applications = missing.div_ceil(produced);
+                    u.produced = u
+                        .applications
+                        .checked_mul(produced)
+                        .ok_or_else(|| self.overflow(id))?;
+                    u.surplus = (u.produced - missing)
+                        .checked_add(u.surplus)
+                        .ok_or_else(|| self.overflow(id))?;
+                    let applications = u.applications;
+                    for lhs in self.graph.successors(id) {
+                        let t = lhs.target;
+                        usage[t].demand = lhs
+                            .weight
+                            .checked_mul(applications)
+                            .and_then(|amount| amount.checked_add(usage[t].demand))
+                            .ok_or_else(|| self.overflow(t))?;
+                    }
                 }
+                None => u.raw = missing,
             }
-            order.push(s);
-        }
-        if incoming_count.values().any(|&c| c != 0) {
-            Err("graph is not acylic")
-        } else {
-            Ok(order)
         }
+        Ok(Plan {
+            graph: self,
+            order,
+            targets: target_ids,
+            usage,
+        })
     }
-}
 
-fn build_reaction_graph(reactions: &[Reaction]) -> Result<ReactionGraph, &'static str> {
-    let mut nodes: HashMap<String, &Reaction> = HashMap::new();
-    for r in reactions {
-        match nodes.entry(r.rhs.chemical.clone()) {
-            Entry::Occupied(_) => return Err("duplicate chemical on right hand side"),
-            Entry::Vacant(e) => {
-                e.insert(r);
+    /// Largest amount of the target that can be produced from the budget,
+    /// which is used like an inventory, together with what prevents
+    /// producing one more.
+    fn max_output(
+        &self,
+        order: &[NodeId],
+        target: &str,
+        budget: &[ChemicalAmount],
+    ) -> Result<(u128, Limit), InputError> {
+        let target_amount = |amount| ChemicalAmount {
+            amount,
+            chemical: target.to_string(),
+        };
+        // checks the chemicals, so that any later error is an overflow
+        self.plan(order, &[target_amount(0)], budget)?;
+        let limit = |amount| match self.plan(order, &[target_amount(amount)], budget) {
+            Ok(plan) => plan
+                .raw_materials()
+                .into_iter()
+                .next()
+                .map(|c| Limit::Budget(c.chemical)),
+            Err(_) => Some(Limit::Overflow),
+        };
+        // the lower amount can be produced while the upper one can not
+        let mut lower = 0;
+        let mut upper = 1;
+        let mut binding = loop {
+            if let Some(limit) = limit(upper) {
+                break limit;
+            }
+            if upper == u128::MAX {
+                return Ok((upper, Limit::Overflow));
+            }
+            lower = upper;
+            upper = upper.saturating_mul(2);
+        };
+        while upper - lower >= 2 {
+            let amount = lower + (upper - lower) / 2;
+            match limit(amount) {
+                Some(limit) => {
+                    upper = amount;
+                    binding = limit;
+                }
+                None => lower = amount,
             }
         }
+        Ok((lower, binding))
     }
-    Ok(ReactionGraph { nodes })
 }
 
-fn find_minimimum_amount(
+// the maximum output, or an error if amounts overflow before the budget
+// runs out, as the maximum found is then too small
+fn checked_max_output(
     graph: &ReactionGraph,
-    order: &[String],
-    target: ChemicalAmount,
-) -> Vec<ChemicalAmount> {
-    let mut amount: HashMap<String, u64> = HashMap::new();
-    amount.insert(target.chemical, target.amount);
-    let mut needed: Vec<ChemicalAmount> = Vec::new();
-    for cur_target in order.iter() {
-        let cur_amount = amount.get(cur_target).copied().unwrap_or(0);
-        if cur_amount > 0 {
-            if let Some(r) = graph.nodes.get(cur_target) {
-                assert!(r.rhs.amount > 0);
-                // can produce cur_amount of cur_target by
-                // by using reaction r num_application times
-                // use integer version of ceil(r.rhs.amount / cur_amount)
-                let num_applications = 1 + (cur_amount - 1) / r.rhs.amount;
-                for lhs in r.lhs.iter() {
-                    let lhs_needed = lhs.amount * num_applications;
-                    *amount.entry(lhs.chemical.clone()).or_insert(0) += lhs_needed;
-                }
+    order: &[NodeId],
+    target: &str,
+    budget: &[ChemicalAmount],
+) -> Result<(u128, Limit), InputError> {
+    match graph.max_output(order, target, budget)? {
+        (_, Limit::Overflow) => Err(InputError::new(format!(
+            "amounts overflow before the budget runs out for {}",
+            target
+        ))),
+        result => Ok(result),
+    }
+}
+
+impl Plan<'_> {
+    fn name(&self, id: NodeId) -> &str {
+        self.graph.graph.name(id)
+    }
+
+    /// Raw materials that are needed beyond the inventory.
+    fn raw_materials(&self) -> Vec<ChemicalAmount> {
+        self.order
+            .iter()
+            .filter(|&&id| self.usage[id].raw > 0)
+            .map(|&id| ChemicalAmount {
+                amount: self.usage[id].raw,
+                chemical: self.name(id).to_string(),
+            })
+            .collect()
+    }
+
+    fn table(&self) -> String {
+        let width = self.order.iter().map(|&id| self.name(id).len()).max();
+        let width = width.unwrap_or(0).max("chemical".len());
+        let mut table = format!(
+            "{:<w$} {:>12} {:>12} {:>12} {:>12} {:>12} {:>12}\n",
+            "chemical",
+            "demand",
+            "inventory",
+            "reactions",
+            "produced",
+            "raw",
+            "surplus",
+            w = width
+        );
+        for &id in self.order {
+            let u = &self.usage[id];
+            if u.demand == 0 && u.surplus == 0 {
+                continue;
+            }
+            table += &format!(
+                "{:<w$} {:>12} {:>12} {:>12} {:>12} {:>12} {:>12}\n",
+                self.name(id),
+                u.demand,
+                u.from_inventory,
+                u.applications,
+                u.produced,
+                u.raw,
+                u.surplus,
+                w = width
+            );
+        }
+        table
+    }
+
+    /// Dependency tree from the targets, with the amount of each ingredient
+    /// used by the reactions of its parent. Chemicals used in several places
+    /// are only expanded the first time.
+    fn tree(&self) -> String {
+        let mut tree = String::new();
+        let mut expanded = vec![false; self.usage.len()];
+        for &id in &self.targets {
+            self.tree_node(id, self.usage[id].demand, 0, &mut expanded, &mut tree);
+        }
+        tree
+    }
+
+    fn tree_node(
+        &self,
+        id: NodeId,
+        amount: u128,
+        depth: usize,
+        expanded: &mut [bool],
+        tree: &mut String,
+    ) {
+        let u = &self.usage[id];
+        let ingredients = self.graph.graph.successors(id);
+        let details = if expanded[id] {
+            if ingredients.is_empty() || u.applications == 0 {
+                String::new()
             } else {
-                // cannot produce cur_target
-                needed.push(ChemicalAmount {
-                    amount: cur_amount,
-                    chemical: cur_target.clone(),
-                });
+                " (see above)".to_string()
             }
+        } else if self.graph.produced[id].is_none() {
+            format!(" (raw {})", u.raw)
+        } else {
+            let plural = if u.applications == 1 { "" } else { "s" };
+            format!(
+                " ({} reaction{} producing {}, surplus {})",
+                u.applications, plural, u.produced, u.surplus
+            )
+        };
+        *tree += &format!(
+            "{}{} {}{}\n",
+            "  ".repeat(depth),
+            amount,
+            self.name(id),
+            details
+        );
+        if expanded[id] {
+            return;
+        }
+        expanded[id] = true;
+        // nothing is used when the inventory covers the demand
+        for lhs in ingredients.iter().filter(|_| u.applications > 0) {
+            let amount = lhs.weight * u.applications;
+            self.tree_node(lhs.target, amount, depth + 1, expanded, tree);
         }
     }
-    needed
 }
 
 fn minimum_amount_of_ore_for_fuel(
     graph: &ReactionGraph,
-    order: &[String],
-    target_fuel: u64,
-) -> Result<u64, &'static str> {
+    order: &[NodeId],
+    target_fuel: u128,
+) -> Result<u128, InputError> {
     let target = ChemicalAmount {
         amount: target_fuel,
         chemical: "FUEL".to_string(),
     };
-    let needed = find_minimimum_amount(&graph, &order, target);
+    let needed = graph.plan(order, &[target], &[])?.raw_materials();
     if needed.len() != 1 || needed[0].chemical != "ORE" {
-        return Err("can not produce FUEL with only ORE");
+        return Err(InputError::new("can not produce FUEL with only ORE"));
     }
     Ok(needed[0].amount)
 }
 
-fn part1(reactions: &[Reaction]) -> Result<u64, &'static str> {
-    let graph = build_reaction_graph(&reactions)?;
+fn part1(reactions: &[Reaction]) -> Result<u128, InputError> {
+    let graph = build_reaction_graph(reactions)?;
     let order = graph.topological_sort()?;
     let result = minimum_amount_of_ore_for_fuel(&graph, &order, 1)?;
     Ok(result)
 }
 
-fn part2(reactions: &[Reaction]) -> Result<u64, &'static str> {
-    let graph = build_reaction_graph(&reactions)?;
+fn part2(reactions: &[Reaction]) -> Result<u128, InputError> {
+    let graph = build_reaction_graph(reactions)?;
     let order = graph.topological_sort()?;
-    const CARGO_ORE: u64 = 1_000_000_000_000;
-    let mut lower: u64 = 1;
-    let mut upper: u64 = CARGO_ORE;
-    while upper - lower >= 2 {
-        let fuel = (lower + upper) / 2;
-        let ore = minimum_amount_of_ore_for_fuel(&graph, &order, fuel)?;
-        if ore <= CARGO_ORE {
-            lower = fuel;
-        } else {
-            upper = fuel;
-        }
-    }
-    Ok(lower)
+    const CARGO_ORE: u128 = 1_000_000_000_000;
+    let cargo = ChemicalAmount {
+        amount: CARGO_ORE,
+        chemical: "ORE".to_string(),
+    };
+    let (fuel, _) = checked_max_output(&graph, &order, "FUEL", &[cargo])?;
+    Ok(fuel)
 }
 
-fn main() -> Result<(), &'static str> {
-    let reactions: Vec<_> = common::get_lines()
-        .into_iter()
-        .map(|l| l.parse::<Reaction>())
-        .collect::<Result<Vec<_>, _>>()?;
+fn main() -> Result<(), InputError> {
+    let report = common::Report::new(2019, 14)?;
+    let reactions: Vec<Reaction> = common::get_input()?;
+    if let Some(filename) = common::option("dot") {
+        let graph = build_reaction_graph(&reactions)?;
+        let highlight = common::option("highlight");
+        common::write_file(&filename, graph.to_dot(highlight.as_deref()).as_bytes())?;
+    }
 
     let result1 = part1(&reactions)?;
-    println!("Part1: minimum amount of ORE for one FUEL is {}", result1);
+    report.part(
+        1,
+        result1,
+        format!("Part1: minimum amount of ORE for one FUEL is {}", result1),
+    );
 
     let result2 = part2(&reactions)?;
-    println!(
-        "Part2: maximum amount of fuel that can be produced is {}",
-        result2
+    report.part(
+        2,
+        result2,
+        format!(
+            "Part2: maximum amount of fuel that can be produced is {}",
+            result2
+        ),
     );
 
+    if report.format() == common::Format::Text && common::flag("plan") {
+        let graph = build_reaction_graph(&reactions)?;
+        let order = graph.topological_sort()?;
+        let targets = parse_amounts(&common::option("targets").unwrap_or("1 FUEL".to_string()))?;
+        let inventory = match common::option("inventory") {
+            Some(inventory) => parse_amounts(&inventory)?,
+            None => Vec::new(),
+        };
+        let plan = graph.plan(&order, &targets, &inventory)?;
+        print!("{}\n{}", plan.table(), plan.tree());
+    }
+    if report.format() == common::Format::Text {
+        if let Some(budget) = common::option("budget") {
+            let graph = build_reaction_graph(&reactions)?;
+            let order = graph.topological_sort()?;
+            let target = common::option("target").unwrap_or("FUEL".to_string());
+            let budget = parse_amounts(&budget)?;
+            let (amount, limit) = checked_max_output(&graph, &order, &target, &budget)?;
+            println!(
+                "At most {} {} can be produced, limited by {}",
+                amount, target, limit
+            );
+        }
+    }
     Ok(())
 }
 
@@ -193,7 +495,7 @@ fn main() -> Result<(), &'static str> {
 mod tests {
     use super::*;
 
-    fn test_ore_to_fuel(input: &[&str], expected_ore: u64) {
+    fn test_ore_to_fuel(input: &[&str], expected_ore: u128) {
         let reactions = input
             .iter()
             .map(|s| s.parse::<Reaction>().unwrap())
@@ -201,7 +503,7 @@ mod tests {
         let needed = part1(&reactions).unwrap();
         assert_eq!(needed, expected_ore);
     }
-    fn test_fuel_for_ore(input: &[&str], expected_fuel: u64) {
+    fn test_fuel_for_ore(input: &[&str], expected_fuel: u128) {
         let reactions = input
             .iter()
             .map(|s| s.parse::<Reaction>().unwrap())
@@ -296,4 +598,174 @@ mod tests {
         test_ore_to_fuel(&input, 2_210_736);
         test_fuel_for_ore(&input, 460_664);
     }
+
+    fn parse_graph(input: &[&str]) -> (ReactionGraph, Vec<NodeId>) {
+        let reactions = input
+            .iter()
+            .map(|s| s.parse::<Reaction>().unwrap())
+            .collect::<Vec<_>>();
+        let graph = build_reaction_graph(&reactions).unwrap();
+        let order = graph.topological_sort().unwrap();
+        (graph, order)
+    }
+
+    #[test]
+    fn test_plan() {
+        let input = [
+            "10 ORE => 10 A",
+            "1 ORE => 1 B",
+            "7 A, 1 B => 1 C",
+            "7 A, 1 C => 1 D",
+            "7 A, 1 D => 1 E",
+            "7 A, 1 E => 1 FUEL",
+        ];
+        let (graph, order) = parse_graph(&input);
+        let a = graph.graph.id("A").unwrap();
+        let plan = graph
+            .plan(&order, &parse_amounts("1 FUEL").unwrap(), &[])
+            .unwrap();
+        assert_eq!(
+            plan.usage[a],
+            Usage {
+                demand: 28,
+                from_inventory: 0,
+                applications: 3,
+                produced: 30,
+                raw: 0,
+                surplus: 2,
+            }
+        );
+        assert_eq!(plan.raw_materials(), parse_amounts("31 ORE").unwrap());
+        let tree = plan.tree();
+        let lines: Vec<_> = tree.lines().collect();
+        assert_eq!(
+            lines[..4],
+            [
+                "1 FUEL (1 reaction producing 1, surplus 0)",
+                "  7 A (3 reactions producing 30, surplus 2)",
+                "    30 ORE (raw 31)",
+                "  1 E (1 reaction producing 1, surplus 0)",
+            ]
+        );
+        assert_eq!(lines[4], "    7 A (see above)");
+        assert_eq!(plan.table().lines().count(), 8);
+
+        let plan = graph
+            .plan(&order, &parse_amounts("1 FUEL, 2 C").unwrap(), &[])
+            .unwrap();
+        assert_eq!(plan.usage[a].applications, 5);
+        assert_eq!(plan.raw_materials(), parse_amounts("53 ORE").unwrap());
+
+        let inventory = parse_amounts("2 A, 1 D, 4 B").unwrap();
+        let plan = graph
+            .plan(&order, &parse_amounts("1 FUEL").unwrap(), &inventory)
+            .unwrap();
+        let d = graph.graph.id("D").unwrap();
+        assert_eq!(
+            (plan.usage[d].from_inventory, plan.usage[d].applications),
+            (1, 0)
+        );
+        assert_eq!((plan.usage[a].produced, plan.usage[a].surplus), (20, 8));
+        let b = graph.graph.id("B").unwrap();
+        assert_eq!(plan.usage[b].surplus, 4);
+        assert_eq!(plan.raw_materials(), parse_amounts("20 ORE").unwrap());
+
+        let error = graph
+            .plan(&order, &parse_amounts("1 WATER").unwrap(), &[])
+            .err()
+            .unwrap();
+        assert_eq!(error.message(), "unknown chemical WATER");
+    }
+
+    #[test]
+    fn test_raw_materials() {
+        let (graph, order) = parse_graph(&["2 ORE, 3 WATER => 2 A", "3 A, 1 WATER => 1 FUEL"]);
+        let plan = graph
+            .plan(&order, &parse_amounts("2 FUEL").unwrap(), &[])
+            .unwrap();
+        assert_eq!(
+            plan.raw_materials(),
+            parse_amounts("6 ORE, 11 WATER").unwrap()
+        );
+        let error = minimum_amount_of_ore_for_fuel(&graph, &order, 1).unwrap_err();
+        assert_eq!(error.message(), "can not produce FUEL with only ORE");
+    }
+
+    #[test]
+    fn test_max_output() {
+        let (graph, order) = parse_graph(&["2 ORE, 3 WATER => 2 A", "3 A, 1 WATER => 1 FUEL"]);
+        let max = |budget| graph.max_output(&order, "FUEL", &parse_amounts(budget).unwrap());
+        assert_eq!(
+            max("100 ORE, 10 WATER").unwrap(),
+            (1, Limit::Budget("WATER".to_string()))
+        );
+        assert_eq!(
+            max("10 ORE, 1000 WATER").unwrap(),
+            (3, Limit::Budget("ORE".to_string()))
+        );
+        assert_eq!(
+            max("5 ORE").unwrap(),
+            (0, Limit::Budget("WATER".to_string()))
+        );
+        // intermediate chemicals can be part of the budget as well
+        assert_eq!(
+            max("3 A, 1 WATER").unwrap(),
+            (1, Limit::Budget("ORE".to_string()))
+        );
+        assert!(max("5 GOLD").is_err());
+
+        let (graph, order) = parse_graph(&["1000000000000 ORE => 1 FUEL"]);
+        let budget = parse_amounts(&format!("{} ORE", u64::MAX)).unwrap();
+        assert_eq!(
+            graph.max_output(&order, "FUEL", &budget).unwrap(),
+            (
+                u128::from(u64::MAX) / 1_000_000_000_000,
+                Limit::Budget("ORE".to_string())
+            )
+        );
+        // intermediate amounts beyond 64 bits
+        let (graph, order) =
+            parse_graph(&["1 ORE => 1000 A", "1000000000000000000000 A => 1 FUEL"]);
+        let budget = parse_amounts(&format!("{} ORE", u64::MAX)).unwrap();
+        assert_eq!(
+            graph.max_output(&order, "FUEL", &budget).unwrap(),
+            (
+                u128::from(u64::MAX) / 1_000_000_000_000_000_000,
+                Limit::Budget("ORE".to_string())
+            )
+        );
+
+        let reactions = [
+            "1000000000000000000000 ORE => 1 A",
+            "1000000000000000000000 A => 1 FUEL",
+        ];
+        let (graph, order) = parse_graph(&reactions);
+        let error = graph
+            .plan(&order, &parse_amounts("1 FUEL").unwrap(), &[])
+            .err()
+            .unwrap();
+        assert_eq!(error.message(), "amount of ORE overflows");
+        let budget = parse_amounts("1000 ORE").unwrap();
+        assert_eq!(
+            graph.max_output(&order, "FUEL", &budget).unwrap(),
+            (0, Limit::Overflow)
+        );
+        let error = checked_max_output(&graph, &order, "FUEL", &budget).unwrap_err();
+        assert_eq!(
+            error.message(),
+            "amounts overflow before the budget runs out for FUEL"
+        );
+        let reactions: Vec<Reaction> = reactions.iter().map(|r| r.parse().unwrap()).collect();
+        assert!(part2(&reactions).is_err());
+    }
+
+    #[test]
+    fn test_cycle() {
+        let reactions = ["1 ORE, 1 B => 1 A", "1 A => 1 B", "1 A => 1 FUEL"]
+            .iter()
+            .map(|s| s.parse::<Reaction>().unwrap())
+            .collect::<Vec<_>>();
+        let error = part1(&reactions).unwrap_err();
+        assert_eq!(error.message(), "reactions form a cycle: B -> A");
+    }
 }
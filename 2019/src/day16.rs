@@ -1,5 +1,6 @@
 use itertools::Itertools;
 mod common;
+use common::InputError;
 
 struct PatternIterator {
     repeat: usize,
@@ -32,34 +33,94 @@ impl PatternIterator {
     }
 }
 
-fn fft(n: &mut Vec<i32>, phases: usize, start: usize) {
+// reference implementation, quadratic in the length of the signal
+#[allow(dead_code)]
+fn fft(n: &mut [i32], phases: usize, start: usize) {
     for _ in 0..phases {
         for j in start..n.len() {
-            let pattern = PatternIterator::new(start + j + 1, start + j);
+            let pattern = PatternIterator::new(j + 1, j);
             let result = n[j..].iter().zip(pattern).map(|(x, y)| x * y).sum::<i32>();
             n[j] = result.abs() % 10;
         }
     }
 }
 
-fn fft2(n: &mut Vec<i32>, phases: usize, start: usize) {
+// pieces the digits are split into per thread, so that the expensive
+// first digits are spread over all threads
+const PIECES_PER_THREAD: usize = 16;
+
+// digit at start + i, from the prefix sums of the digits from start on
+fn fft_digit(prefix: &[i64], start: usize, i: usize) -> i32 {
+    let len = prefix.len() - 1;
+    let repeat = start + i + 1;
+    let block = |from: usize| prefix[(from + repeat).min(len)] - prefix[from.min(len)];
+    let mut sum = 0;
+    let mut from = i;
+    while from < len {
+        sum += block(from) - block(from + 2 * repeat);
+        from += 4 * repeat;
+    }
+    (sum.abs() % 10) as i32
+}
+
+// in the second half the pattern of a digit is 1 up to the end of the
+// signal, so every digit is the sum of the digits from it on
+fn suffix_fft(n: &mut [i32], phases: usize, start: usize) {
     for _ in 0..phases {
-        let mut sum = n.iter().skip(start).sum::<i32>();
-        #[allow(clippy::needless_range_loop)]
-        for j in start..n.len() {
-            let result = sum.abs() % 10;
-            sum -= n[j];
-            n[j] = result;
+        let mut sum = 0;
+        for x in n[start..].iter_mut().rev() {
+            sum = (sum + *x) % 10;
+            *x = sum;
         }
     }
 }
 
-fn parse_input(s: &str) -> Result<Vec<i32>, &'static str> {
-    s.chars()
-        .map(|c| {
+/// FFT of the digits from `start` on, which only depend on the digits after
+/// them. The pattern of a digit consists of blocks of equal values, so with
+/// prefix sums every block takes constant time, and a phase over n digits
+/// takes the harmonic sum of n / (i + 1), which is O(n log n). From the
+/// second half on, suffix sums take O(n).
+fn fast_fft(n: &mut [i32], phases: usize, start: usize) {
+    if start >= n.len() {
+        return;
+    }
+    if start >= n.len() / 2 {
+        suffix_fft(n, phases, start);
+        return;
+    }
+    let digits = n.len() - start;
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let piece = digits.div_ceil(threads * PIECES_PER_THREAD);
+    let mut prefix = vec![0i64; digits + 1];
+    for _ in 0..phases {
+        for (j, &x) in n[start..].iter().enumerate() {
+            prefix[j + 1] = prefix[j] + i64::from(x);
+        }
+        let prefix = &prefix;
+        let mut work: Vec<Vec<_>> = (0..threads).map(|_| Vec::new()).collect();
+        for (k, digits) in n[start..].chunks_mut(piece).enumerate() {
+            work[k % threads].push((k * piece, digits));
+        }
+        std::thread::scope(|scope| {
+            for pieces in work {
+                scope.spawn(move || {
+                    for (first, digits) in pieces {
+                        for (k, d) in digits.iter_mut().enumerate() {
+                            *d = fft_digit(prefix, start, first + k);
+                        }
+                    }
+                });
+            }
+        });
+    }
+}
+
+fn parse_input(s: &str) -> Result<Vec<i32>, InputError> {
+    s.char_indices()
+        .map(|(i, c)| {
             c.to_digit(10)
                 .map(|x| x as i32)
-                .ok_or("could not parse digit")
+                .ok_or_else(|| InputError::at(s, i, c.len_utf8(), "could not parse digit"))
         })
         .collect()
 }
@@ -72,33 +133,59 @@ fn sequence_to_number<'a>(n: impl Iterator<Item = &'a i32>) -> i32 {
     n.fold(0, |s, x| 10 * s + x)
 }
 
-fn part2(n: &[i32], phases: usize, digits: usize) -> String {
-    let offset = sequence_to_number(n.iter().take(7)) as usize;
-    let mut input: Vec<_> = n.iter().cycle().take(10000 * n.len()).cloned().collect();
-    if input.len() / 2 < offset {
-        // apply optimized version
-        fft2(&mut input, phases, offset);
-    } else {
-        println!("Warning: can not apply optimized version of FFT");
-        fft(&mut input, phases, offset);
-    }
+// the offset of the message is given by the first seven digits,
+// unless it is given explicitly
+fn message(
+    n: &[i32],
+    repeat: usize,
+    phases: usize,
+    offset: Option<usize>,
+    digits: usize,
+) -> String {
+    let offset = offset.unwrap_or(sequence_to_number(n.iter().take(7)) as usize);
+    let mut input: Vec<_> = n.iter().cycle().take(repeat * n.len()).cloned().collect();
+    fast_fft(&mut input, phases, offset);
     result_to_str(input.iter().skip(offset).take(digits))
 }
 
-fn main() -> Result<(), &'static str> {
-    let numbers: Vec<_> = common::get_lines()
-        .into_iter()
-        .map(|l| parse_input(&l))
+fn part1(n: &[i32], phases: usize, digits: usize) -> String {
+    let mut output = n.to_vec();
+    fast_fft(&mut output, phases, 0);
+    result_to_str(output.iter().take(digits))
+}
+
+fn part2(n: &[i32], phases: usize, digits: usize) -> String {
+    message(n, 10000, phases, None, digits)
+}
+
+fn main() -> Result<(), InputError> {
+    let report = common::Report::new(2019, 16)?;
+    let numbers: Vec<_> = common::get_lines()?
+        .iter()
+        .enumerate()
+        .map(|(i, l)| parse_input(l).map_err(|e| e.on_line(i + 1)))
         .collect::<Result<Vec<_>, _>>()?;
 
     for n in numbers {
-        let mut input1 = n.clone();
-        fft(&mut input1, 100, 0);
-        let output1 = result_to_str(n.iter().take(8));
-        println!("Part1: result after 100 phases of FFT: {}", output1);
+        let output1 = part1(&n, 100, 8);
+        report.part(
+            1,
+            &output1,
+            format!("Part1: result after 100 phases of FFT: {}", output1),
+        );
 
         let output2 = part2(&n, 100, 8);
-        println!("Part2: message is {}", output2);
+        report.part(2, &output2, format!("Part2: message is {}", output2));
+
+        if report.format() == common::Format::Text {
+            if let Some(offset) = common::option("offset") {
+                let offset = offset.parse().map_err(|_| {
+                    InputError::spanning(&offset, &offset, "could not parse offset")
+                })?;
+                let output = message(&n, 10000, 100, Some(offset), 8);
+                println!("Message at offset {} is {}", offset, output);
+            }
+        }
     }
 
     Ok(())
@@ -110,9 +197,16 @@ mod tests {
 
     fn test_part1(input: &str, phases: usize, digits: usize, expected: &str) {
         let mut n = parse_input(input).unwrap();
+        let mut fast = n.clone();
         fft(&mut n, phases, 0);
         let output = result_to_str(n.iter().take(digits));
         assert_eq!(output, expected);
+        fast_fft(&mut fast, phases, 0);
+        assert_eq!(fast, n);
+        assert_eq!(
+            part1(&parse_input(input).unwrap(), phases, digits),
+            expected
+        );
     }
 
     fn test_part2(input: &str, phases: usize, digits: usize, expected: &str) {
@@ -155,4 +249,41 @@ mod tests {
     fn test_example7() {
         test_part2("03081770884921959731165446850517", 100, 8, "53553731");
     }
+
+    #[test]
+    fn test_fast_fft() {
+        // digits of a linear congruential generator
+        let mut state = 12345u64;
+        let signal: Vec<i32> = (0..1000)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1);
+                (state >> 33) as i32 % 10
+            })
+            .collect();
+        for &start in &[0, 1, 7, 333, 500, 999, 1000] {
+            let mut slow = signal.clone();
+            let mut fast = signal.clone();
+            fft(&mut slow, 5, start);
+            fast_fft(&mut fast, 5, start);
+            assert_eq!(fast[start..], slow[start..]);
+        }
+    }
+
+    #[test]
+    fn test_message_offset() {
+        // offset in the first half of the repeated signal
+        let n = parse_input("00000123456789543210987654321012").unwrap();
+        let mut input: Vec<_> = n.iter().cycle().take(20 * n.len()).cloned().collect();
+        fft(&mut input, 100, 0);
+        assert_eq!(
+            message(&n, 20, 100, None, 8),
+            result_to_str(input.iter().skip(12).take(8))
+        );
+        assert_eq!(
+            message(&n, 20, 100, Some(300), 8),
+            result_to_str(input.iter().skip(300).take(8))
+        );
+    }
 }
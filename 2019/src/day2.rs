@@ -48,35 +48,35 @@ fn replace_input(program: &[i32], one: i32, two: i32) -> Vec<i32> {
 }
 
 #[allow(clippy::inconsistent_digit_grouping)]
-fn main() {
-    let input: Vec<Vec<i32>> = common::get_lines()
-        .into_iter()
-        .map(|l| {
-            l.split(',')
-                .map(|i| i.parse::<i32>().expect("could not parse number"))
-                .collect()
-        })
-        .collect();
+fn main() -> Result<(), common::InputError> {
+    let report = common::Report::new(2019, 2)?;
+    let input: Vec<Vec<i32>> = common::get_separated_input(',')?;
     for program in input {
         let replaced = replace_input(&program, 12, 2);
         let result1 = run_program(&replaced);
-        println!("Part1: Program output is: {}", result1);
+        report.part(1, result1, format!("Part1: Program output is: {}", result1));
 
         'part2: for noun in 0..=99 {
             for verb in 0..=99 {
                 let replaced = replace_input(&program, noun, verb);
                 let result2 = run_program(&replaced);
-                if result2 == 1969_07_20 { // date of moon landing
+                if result2 == 1969_07_20 {
+                    // date of moon landing
                     let answer = 100 * noun + verb;
-                    println!(
-                        "Part2: noun = {}, verb = {}, 100 * noun + verb = {}",
-                        noun, verb, answer
+                    report.part(
+                        2,
+                        answer,
+                        format!(
+                            "Part2: noun = {}, verb = {}, 100 * noun + verb = {}",
+                            noun, verb, answer
+                        ),
                     );
                     break 'part2;
                 }
             }
         }
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -1,8 +1,9 @@
-use std::collections::HashMap;
-use std::convert::TryFrom;
-use std::fmt;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::str::FromStr;
 mod common;
+use common::geometry::Point2D;
+use common::grid::SparseGrid;
+use common::InputError;
 
 enum Direction {
     U,
@@ -21,7 +22,7 @@ struct DirectedPath {
 }
 
 impl FromStr for Direction {
-    type Err = &'static str;
+    type Err = InputError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
@@ -29,116 +30,57 @@ impl FromStr for Direction {
             "D" => Ok(Direction::D),
             "L" => Ok(Direction::L),
             "R" => Ok(Direction::R),
-            _ => Err("unknown direction"),
+            _ => Err(InputError::spanning(s, s, "unknown direction")),
         }
     }
 }
 
 impl FromStr for DirectedVec {
-    type Err = &'static str;
+    type Err = InputError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if s.is_empty() {
-            Err("cannot parse empty directed vec")
+            Err(InputError::new("cannot parse empty directed vec"))
         } else {
-            let direction = s[0..1].parse::<Direction>()?;
-            let length = s[1..].parse::<i64>().map_err(|_| "cannot parse length")?;
+            let (dir_str, length_str) = s.split_at(s.chars().next().unwrap().len_utf8());
+            let direction = dir_str
+                .parse::<Direction>()
+                .map_err(|e| e.within(s, dir_str))?;
+            let length = length_str
+                .parse::<i64>()
+                .map_err(|_| InputError::spanning(s, length_str, "cannot parse length"))?;
             Ok(DirectedVec { direction, length })
         }
     }
 }
 
 impl FromStr for DirectedPath {
-    type Err = &'static str;
+    type Err = InputError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let seg = s
             .split(',')
-            .map(|d| d.parse::<DirectedVec>())
+            .map(|d| d.parse::<DirectedVec>().map_err(|e| e.within(s, d)))
             .collect::<Result<Vec<_>, Self::Err>>()?;
         Ok(DirectedPath { segment: seg })
     }
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Hash)]
-struct Point2D {
-    x: i64,
-    y: i64,
-}
-
-impl fmt::Display for Point2D {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "({}, {})", self.x, self.y)
-    }
-}
-
-impl Point2D {
-    fn zero() -> Point2D {
-        Point2D { x: 0, y: 0 }
-    }
-
-    fn manhattan_norm(&self) -> i64 {
-        self.x.abs() + self.y.abs()
-    }
-}
-impl std::ops::Sub for Point2D {
-    type Output = Point2D;
-
-    fn sub(self, other: Point2D) -> Point2D {
-        Point2D {
-            x: self.x - other.x,
-            y: self.y - other.y,
-        }
-    }
-}
-
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Hash)]
-struct Point3D {
-    x: i64,
-    y: i64,
-    z: i64,
-}
-
-impl TryFrom<Point3D> for Point2D {
-    type Error = &'static str;
-
-    fn try_from(p: Point3D) -> Result<Self, Self::Error> {
-        if p.z == 0 {
-            Err("3d point is a point in infinity")
-        } else if p.x % p.z != 0 || p.y % p.z != 0 {
-            Err("3d point is a non-integer 2d point")
-        } else {
-            Ok(Point2D {
-                x: p.x / p.z,
-                y: p.y / p.z,
-            })
-        }
-    }
-}
-
-impl From<Point2D> for Point3D {
-    fn from(p: Point2D) -> Point3D {
-        Point3D {
-            x: p.x,
-            y: p.y,
-            z: 1,
-        }
-    }
-}
-
-impl Point3D {
-    fn cross(self, other: Point3D) -> Point3D {
-        let x = self.y * other.z - self.z * other.y;
-        let y = self.z * other.x - self.x * other.z;
-        let z = self.x * other.y - self.y * other.x;
-        Point3D { x, y, z }
-    }
+/// Axis-aligned part of a wire between two turns, with the number of
+/// steps along the wire before its start.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+struct Segment {
+    start: Point2D,
+    end: Point2D,
+    steps: i64,
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
-struct Edge {
-    p: Point2D,
-    q: Point2D,
+/// Point where at least two wires cross, with the fewest steps along every
+/// wire to reach it, or `None` for wires that do not pass it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Crossing {
+    point: Point2D,
+    steps: Vec<Option<i64>>,
 }
 
 #[derive(Debug)]
@@ -172,187 +114,480 @@ impl From<&DirectedPath> for Path {
     }
 }
 
-struct EdgeIterator<'a> {
-    path: &'a Path,
-    index: usize,
+impl FromStr for Path {
+    type Err = InputError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let dp = s.parse::<DirectedPath>()?;
+        Ok(Path::from(&dp))
+    }
 }
 
-impl<'a> Iterator for EdgeIterator<'a> {
-    type Item = Edge;
+impl Segment {
+    // segments without length count as horizontal
+    fn is_horizontal(&self) -> bool {
+        self.start.y == self.end.y
+    }
 
-    fn next(&mut self) -> Option<Edge> {
-        if self.index + 1 >= self.path.path.len() {
-            None
+    // coordinate of the line the segment lies on
+    fn line(&self) -> i64 {
+        if self.is_horizontal() {
+            self.start.y
         } else {
-            let p = self.path.path[self.index];
-            let q = self.path.path[self.index + 1];
-            self.index += 1;
-            Some(Edge { p, q })
+            self.start.x
         }
     }
-}
-
-impl FromStr for Path {
-    type Err = &'static str;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let dp = s.parse::<DirectedPath>()?;
-        Ok(Path::from(&dp))
+    // smallest and largest coordinate along the line
+    fn range(&self) -> (i64, i64) {
+        let (a, b) = if self.is_horizontal() {
+            (self.start.x, self.end.x)
+        } else {
+            (self.start.y, self.end.y)
+        };
+        (a.min(b), a.max(b))
     }
-}
 
-impl Edge {
-    // tests if point r is on the edge, assuming it
-    // it is on the line extended by the edge
-    fn contains_point(&self, r: Point2D) -> bool {
-        let min_x = std::cmp::min(self.p.x, self.q.x);
-        let max_x = std::cmp::max(self.p.x, self.q.x);
-        let min_y = std::cmp::min(self.p.y, self.q.y);
-        let max_y = std::cmp::max(self.p.y, self.q.y);
-        (min_x <= r.x && r.x <= max_x) && (min_y <= r.y && r.y <= max_y)
-    }
-    fn intersect(&self, other: &Edge) -> Option<Point2D> {
-        let p1 = Point3D::from(self.p);
-        let q1 = Point3D::from(self.q);
-        let p2 = Point3D::from(other.p);
-        let q2 = Point3D::from(other.q);
-        // note: parallel edges currently yield no intersection,
-        // even if there is a unique intersection point
-        let r = Point2D::try_from(p1.cross(q1).cross(p2.cross(q2))).ok()?;
-        // test if point lies on both edges
-        if self.contains_point(r) && other.contains_point(r) {
-            Some(r)
+    // point on the line of the segment at the coordinate along the line
+    fn point_at(&self, c: i64) -> Point2D {
+        if self.is_horizontal() {
+            Point2D::new(c, self.start.y)
         } else {
-            None
+            Point2D::new(self.start.x, c)
         }
     }
-    fn manhattan_length(&self) -> i64 {
-        (self.p - self.q).manhattan_norm()
+
+    // steps along the wire to a point on the segment
+    fn steps_to(&self, p: Point2D) -> i64 {
+        self.steps + (p - self.start).l1_norm()
     }
 }
 
 impl Path {
-    fn edge_iter(&self) -> EdgeIterator {
-        EdgeIterator {
-            path: self,
-            index: 0,
+    // segments without length are left out, as their only point is
+    // also on the neighbouring segments
+    fn segments(&self) -> Vec<Segment> {
+        let mut steps = 0;
+        let mut segments: Vec<_> = self
+            .path
+            .windows(2)
+            .filter(|w| w[0] != w[1])
+            .map(|w| {
+                let segment = Segment {
+                    start: w[0],
+                    end: w[1],
+                    steps,
+                };
+                steps += (w[1] - w[0]).l1_norm();
+                segment
+            })
+            .collect();
+        if segments.is_empty() {
+            segments.push(Segment {
+                start: self.path[0],
+                end: self.path[0],
+                steps: 0,
+            });
         }
+        segments
+    }
+
+    /// Fewest steps along the wire to each of the points that it passes.
+    fn compute_distances(&self, points: &[Point2D]) -> HashMap<Point2D, i64> {
+        // the points become segments without length of a second wire
+        let points: Vec<_> = points
+            .iter()
+            .map(|&p| Segment {
+                start: p,
+                end: p,
+                steps: 0,
+            })
+            .collect();
+        let wires = [self.segments(), points];
+        let mut distances: HashMap<Point2D, i64> = HashMap::new();
+        shared_points(&wires, |p, (w, i), (v, j)| {
+            if w != v {
+                let segment = if w == 0 { &wires[0][i] } else { &wires[0][j] };
+                let steps = segment.steps_to(p);
+                let d = distances.entry(p).or_insert(steps);
+                *d = (*d).min(steps);
+            }
+        });
+        distances
     }
-    fn intersect(&self, other: &Path) -> Vec<Point2D> {
-        let mut intersections = Vec::new();
-        for e1 in self.edge_iter() {
-            for e2 in other.edge_iter() {
-                if let Some(p) = e1.intersect(&e2) {
-                    intersections.push(p);
-                }
+
+    /// Points that the wire passes more than once, sorted.
+    fn self_intersections(&self) -> Vec<Point2D> {
+        let mut points = HashSet::new();
+        shared_points(&[self.segments()], |p, _, _| {
+            points.insert(p);
+        });
+        let mut points: Vec<_> = points.into_iter().collect();
+        points.sort_unstable();
+        points
+    }
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+enum Event {
+    // the order makes horizontal segments active at both of their ends
+    Insert,
+    Query,
+    Remove,
+}
+
+// tests if the point is where a segment continues in the next one
+fn is_joint(wires: &[Vec<Segment>], p: Point2D, a: (usize, usize), b: (usize, usize)) -> bool {
+    let ((w, i), (v, j)) = (a.min(b), a.max(b));
+    w == v && i + 1 == j && wires[w][i].end == p
+}
+
+// reports every pair of a horizontal and a vertical segment with a common
+// point, sweeping a vertical line from left to right with the horizontal
+// segments it currently meets ordered by y
+fn perpendicular<F>(wires: &[Vec<Segment>], report: &mut F)
+where
+    F: FnMut(Point2D, (usize, usize), (usize, usize)),
+{
+    let mut events = Vec::new();
+    for (w, segments) in wires.iter().enumerate() {
+        for (i, s) in segments.iter().enumerate() {
+            let (lo, hi) = s.range();
+            if s.is_horizontal() {
+                events.push((lo, Event::Insert, w, i));
+                events.push((hi, Event::Remove, w, i));
+            } else {
+                events.push((s.line(), Event::Query, w, i));
             }
         }
-        intersections
-    }
-    // insert all points in inter into the path
-    // assumes that all of the points in inter are on some edge
-    fn with_intersections(&self, inter: &[Point2D]) -> Path {
-        let mut new_path = Vec::new();
-        new_path.push(self.path[0]);
-        for e in self.edge_iter() {
-            let origin = e.p;
-            let mut new_points = Vec::new();
-            for &p in inter {
-                if p != e.p && p != e.q && e.contains_point(p) {
-                    new_points.push(p);
-                }
+    }
+    events.sort_unstable();
+
+    let mut active = BTreeSet::new();
+    for (x, event, w, i) in events {
+        let s = &wires[w][i];
+        match event {
+            Event::Insert => {
+                active.insert((s.line(), w, i));
             }
-            new_points.sort_by_key(|&p| Edge { p: origin, q: p }.manhattan_length());
-            for p in new_points {
-                new_path.push(p);
+            Event::Remove => {
+                active.remove(&(s.line(), w, i));
+            }
+            Event::Query => {
+                let (lo, hi) = s.range();
+                for &(y, v, j) in active.range((lo, 0, 0)..=(hi, usize::MAX, usize::MAX)) {
+                    report(Point2D::new(x, y), (v, j), (w, i));
+                }
             }
-            new_path.push(e.q);
         }
-        Path { path: new_path }
-    }
-    fn compute_distances(&self) -> HashMap<Point2D, i64> {
-        let mut map = HashMap::new();
-        let mut start_dist = 0;
-        map.insert(self.path[0], start_dist);
-        for e in self.edge_iter() {
-            // note: do *not* take a shortcut if the start point
-            // was already visited
-            // let start_dist = *map.get(&e.p).unwrap();
-            let end_dist = start_dist + e.manhattan_length();
-            start_dist = end_dist;
-            map.entry(e.q).or_insert(end_dist);
+    }
+}
+
+// reports every lattice point shared by collinear segments, sweeping
+// along each line with the segments that are still open
+fn collinear<F>(wires: &[Vec<Segment>], report: &mut F)
+where
+    F: FnMut(Point2D, (usize, usize), (usize, usize)),
+{
+    let mut lines: HashMap<(bool, i64), Vec<_>> = HashMap::new();
+    for (w, segments) in wires.iter().enumerate() {
+        for (i, s) in segments.iter().enumerate() {
+            let (lo, hi) = s.range();
+            lines
+                .entry((s.is_horizontal(), s.line()))
+                .or_default()
+                .push((lo, hi, w, i));
         }
-        map
     }
+    for mut line in lines.into_values() {
+        line.sort_unstable();
+        let mut open: Vec<(i64, usize, usize)> = Vec::new();
+        for (lo, hi, w, i) in line {
+            let s = &wires[w][i];
+            // every segment still open overlaps this one from lo on
+            open.retain(|&(end, _, _)| end >= lo);
+            for &(end, v, j) in &open {
+                for c in lo..=hi.min(end) {
+                    report(s.point_at(c), (v, j), (w, i));
+                }
+            }
+            open.push((hi, w, i));
+        }
+    }
+}
+
+/// Reports every point shared by two segments, given by the index of their
+/// wire and their index in it, except where a segment continues in the
+/// next one. Perpendicular segments are found with a sweep line and
+/// collinear ones with a sweep along their common line, in O((n + k) log n)
+/// time for n segments and k shared points of segments.
+fn shared_points<F>(wires: &[Vec<Segment>], mut report: F)
+where
+    F: FnMut(Point2D, (usize, usize), (usize, usize)),
+{
+    let mut report_pair = |p, a, b| {
+        if !is_joint(wires, p, a, b) {
+            report(p, a, b);
+        }
+    };
+    perpendicular(wires, &mut report_pair);
+    collinear(wires, &mut report_pair);
 }
 
-fn non_trivial_intersections(p1: &Path, p2: &Path) -> Vec<Point2D> {
-    p1.intersect(&p2)
+/// Points where at least two of the wires cross, sorted, including every
+/// lattice point where wires overlap.
+fn crossings(paths: &[&Path]) -> Vec<Crossing> {
+    let wires: Vec<_> = paths.iter().map(|p| p.segments()).collect();
+    let mut points = HashSet::new();
+    shared_points(&wires, |p, (w, _), (v, _)| {
+        if w != v {
+            points.insert(p);
+        }
+    });
+    let mut points: Vec<_> = points.into_iter().collect();
+    points.sort_unstable();
+    let distances: Vec<_> = paths.iter().map(|p| p.compute_distances(&points)).collect();
+    points
         .into_iter()
-        .filter(|&p| p != Point2D::zero())
+        .map(|point| Crossing {
+            point,
+            steps: distances.iter().map(|d| d.get(&point).copied()).collect(),
+        })
         .collect()
 }
 
-fn part1(p1: &Path, p2: &Path) -> Option<i64> {
-    non_trivial_intersections(p1, p2)
-        .into_iter()
-        .map(|p| p.manhattan_norm())
-        .min()
+impl Crossing {
+    /// Total number of steps of the given wires to the crossing, if they all pass it.
+    fn delay(&self, wires: &[usize]) -> Option<i64> {
+        wires.iter().map(|&w| self.steps[w]).sum()
+    }
 }
 
-// adds all self-intersections and intersections of p1 and p2
-// to both paths and returns the new paths (p1', p2')
-fn extend_with_intersections(p1: &Path, p2: &Path) -> (Path, Path) {
-    let mut p1inter = p1.intersect(&p1);
-    let mut p2inter = p1.intersect(&p1);
-    let intersections = p1.intersect(&p2);
-    p1inter.extend(intersections.iter().cloned());
-    p2inter.extend(intersections.iter().cloned());
-    let p1new = p1.with_intersections(&p1inter);
-    let p2new = p2.with_intersections(&p2inter);
-    (p1new, p2new)
-}
-
-fn distance_to_intersections(p1: &Path, p2: &Path) -> Vec<(Point2D, i64, i64)> {
-    let inter = non_trivial_intersections(p1, p2);
-    let (p1new, p2new) = extend_with_intersections(p1, p2);
-    let dist1 = p1new.compute_distances();
-    let dist2 = p2new.compute_distances();
-    inter
-        .into_iter()
-        .map(|p| (p, *dist1.get(&p).unwrap(), *dist2.get(&p).unwrap()))
-        .collect()
+// crossings other than the origin where all of the given wires cross
+fn crossings_of<'a>(
+    crossings: &'a [Crossing],
+    wires: &'a [usize],
+) -> impl Iterator<Item = &'a Crossing> {
+    crossings
+        .iter()
+        .filter(move |c| c.point != Point2D::zero() && c.delay(wires).is_some())
 }
 
-fn part2(p1: &Path, p2: &Path) -> Option<i64> {
-    distance_to_intersections(&p1, &p2)
-        .into_iter()
-        .map(|(_, d1, d2)| d1 + d2)
+fn closest(crossings: &[Crossing], wires: &[usize]) -> Option<i64> {
+    crossings_of(crossings, wires)
+        .map(|c| c.point.l1_norm())
         .min()
 }
 
-fn main() {
-    let paths: Vec<_> = common::get_lines()
-        .into_iter()
-        .map(|l| l.parse::<Path>().expect("could not parse path"))
-        .collect();
-    assert_eq!(paths.len(), 2);
-    let p1 = &paths[0];
-    let p2 = &paths[1];
+fn fewest_steps(crossings: &[Crossing], wires: &[usize]) -> Option<i64> {
+    crossings_of(crossings, wires)
+        .filter_map(|c| c.delay(wires))
+        .min()
+}
+
+fn all_wires(crossings: &[Crossing]) -> Vec<usize> {
+    (0..crossings.first().map_or(0, |c| c.steps.len())).collect()
+}
+
+// closest crossing of all wires
+fn part1(crossings: &[Crossing]) -> Option<i64> {
+    closest(crossings, &all_wires(crossings))
+}
+
+// crossing of all wires with the fewest steps
+fn part2(crossings: &[Crossing]) -> Option<i64> {
+    fewest_steps(crossings, &all_wires(crossings))
+}
+
+fn format_optional(value: Option<i64>) -> String {
+    value.map_or("-".to_string(), |v| v.to_string())
+}
 
-    let r1 = part1(&p1, &p2);
+// summary of the crossings of every pair of wires, of all wires and of each
+// wire with itself, followed by the steps of every wire to each crossing
+fn format_crossings(paths: &[Path], crossings: &[Crossing]) -> String {
+    let mut lines = Vec::new();
+    let n = paths.len();
+    for a in 0..n {
+        for b in a + 1..n {
+            let wires = [a, b];
+            lines.push(format!(
+                "Wires {} and {}: {} crossings, closest at distance {}, fewest steps {}",
+                a + 1,
+                b + 1,
+                crossings_of(crossings, &wires).count(),
+                format_optional(closest(crossings, &wires)),
+                format_optional(fewest_steps(crossings, &wires))
+            ));
+        }
+    }
+    if n > 2 {
+        let wires: Vec<_> = (0..n).collect();
+        lines.push(format!(
+            "All {} wires: {} crossings",
+            n,
+            crossings_of(crossings, &wires).count()
+        ));
+    }
+    for (w, path) in paths.iter().enumerate() {
+        lines.push(format!(
+            "Wire {} crosses itself at {} points",
+            w + 1,
+            path.self_intersections().len()
+        ));
+    }
+    for c in crossings {
+        let steps: Vec<_> = c.steps.iter().map(|&s| format_optional(s)).collect();
+        let passing: Vec<_> = (0..n).filter(|&w| c.steps[w].is_some()).collect();
+        lines.push(format!(
+            "{}: steps {}, delay {}",
+            c.point,
+            steps.join(", "),
+            format_optional(c.delay(&passing))
+        ));
+    }
+    lines.join("\n")
+}
+
+// largest number of cells of an ascii rendering
+const ASCII_MAX_CELLS: i64 = 1_000_000;
+
+/// Drawing of the wires like in the puzzle, with `o` for the origin, `+`
+/// for turns and `X` for crossings, or `None` if it is too large.
+fn to_ascii(paths: &[Path], crossings: &[Crossing]) -> Option<String> {
+    let (min, max) = bounds(paths);
+    if (max.x - min.x + 3) * (max.y - min.y + 3) > ASCII_MAX_CELLS {
+        return None;
+    }
+    // y points up in the puzzle, so rows are drawn for -y
+    let flip = |p: Point2D| Point2D::new(p.x, -p.y);
+    let mut grid = SparseGrid::new('.');
+    grid.set(flip(min) + Point2D::new(-1, 1), '.');
+    grid.set(flip(max) + Point2D::new(1, -1), '.');
+    for path in paths {
+        for s in path.segments() {
+            let (lo, hi) = s.range();
+            let glyph = if s.is_horizontal() { '-' } else { '|' };
+            for c in lo..=hi {
+                grid.set(flip(s.point_at(c)), glyph);
+            }
+        }
+        for &p in &path.path[1..path.path.len() - 1] {
+            grid.set(flip(p), '+');
+        }
+    }
+    for c in crossings {
+        grid.set(flip(c.point), 'X');
+    }
+    grid.set(Point2D::zero(), 'o');
+    Some(grid.render(|&c| c))
+}
+
+fn bounds(paths: &[Path]) -> (Point2D, Point2D) {
+    let points = || paths.iter().flat_map(|p| p.path.iter());
+    let min_x = points().map(|p| p.x).min().unwrap_or(0);
+    let max_x = points().map(|p| p.x).max().unwrap_or(0);
+    let min_y = points().map(|p| p.y).min().unwrap_or(0);
+    let max_y = points().map(|p| p.y).max().unwrap_or(0);
+    (Point2D::new(min_x, min_y), Point2D::new(max_x, max_y))
+}
+
+const WIRE_COLOURS: [&str; 6] = [
+    "#d62728", "#1f77b4", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b",
+];
+
+// size of the larger side of the svg rendering in pixels
+const SVG_SIZE: i64 = 1000;
+
+/// Vector drawing of the wires with a circle around each crossing.
+fn to_svg(paths: &[Path], crossings: &[Crossing]) -> String {
+    let (min, max) = bounds(paths);
+    let margin = ((max.x - min.x).max(max.y - min.y) / 50).max(1);
+    let (left, top) = (min.x - margin, -max.y - margin);
+    let (width, height) = (max.x - min.x + 2 * margin, max.y - min.y + 2 * margin);
+    let larger = width.max(height);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         viewBox=\"{} {} {} {}\">\n",
+        width * SVG_SIZE / larger,
+        height * SVG_SIZE / larger,
+        left,
+        top,
+        width,
+        height
+    );
+    svg.push_str(&format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#ffffff\"/>\n",
+        left, top, width, height
+    ));
+    for (w, path) in paths.iter().enumerate() {
+        let points: Vec<_> = path
+            .path
+            .iter()
+            .map(|p| format!("{},{}", p.x, -p.y))
+            .collect();
+        svg.push_str(&format!(
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\" \
+             vector-effect=\"non-scaling-stroke\"/>\n",
+            points.join(" "),
+            WIRE_COLOURS[w % WIRE_COLOURS.len()]
+        ));
+    }
+    for c in crossings {
+        svg.push_str(&format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"#000000\" \
+             vector-effect=\"non-scaling-stroke\"/>\n",
+            c.point.x,
+            -c.point.y,
+            margin as f64 / 2.0
+        ));
+    }
+    svg.push_str(&format!(
+        "<circle cx=\"0\" cy=\"0\" r=\"{}\" fill=\"#000000\"/>\n",
+        margin as f64 / 2.0
+    ));
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn main() -> Result<(), common::InputError> {
+    let report = common::Report::new(2019, 3)?;
+    let paths: Vec<Path> = common::get_input()?;
+    if paths.is_empty() {
+        return Err(InputError::new("no wires given"));
+    }
+    let wires: Vec<&Path> = paths.iter().collect();
+    let crossings = crossings(&wires);
+
+    let r1 = part1(&crossings);
     if let Some(d) = r1 {
-        println!("Part1: Minimal distance {}", d);
+        report.part(1, d, format!("Part1: Minimal distance {}", d));
     } else {
-        println!("No non-trivial intersection found");
+        eprintln!("No non-trivial intersection found");
     }
 
-    let r2 = part2(&p1, &p2);
+    let r2 = part2(&crossings);
     if let Some(d) = r2 {
-        println!("Part2: Minimal distance {}", d);
+        report.part(2, d, format!("Part2: Minimal distance {}", d));
     } else {
-        println!("No non-trivial intersection found");
+        eprintln!("No non-trivial intersection found");
     }
+
+    if report.format() == common::Format::Text {
+        if common::flag("crossings") {
+            println!("{}", format_crossings(&paths, &crossings));
+        }
+        if common::flag("ascii") {
+            match to_ascii(&paths, &crossings) {
+                Some(ascii) => print!("{}", ascii),
+                None => eprintln!("Warning: wires are too large to draw as text"),
+            }
+        }
+    }
+    if let Some(filename) = common::option("svg") {
+        common::write_file(&filename, to_svg(&paths, &crossings).as_bytes())?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -381,10 +616,23 @@ mod tests {
         (p1, p2)
     }
 
+    fn intersect(p1: &Path, p2: &Path) -> Vec<Point2D> {
+        crossings(&[p1, p2]).into_iter().map(|c| c.point).collect()
+    }
+
+    // non-trivial crossings with the steps of both wires
+    fn distance_to_intersections(p1: &Path, p2: &Path) -> Vec<(Point2D, i64, i64)> {
+        crossings(&[p1, p2])
+            .into_iter()
+            .filter(|c| c.point != Point2D::zero())
+            .map(|c| (c.point, c.steps[0].unwrap(), c.steps[1].unwrap()))
+            .collect()
+    }
+
     #[test]
     fn test_intersection() {
         let (p1, p2) = example0();
-        let mut inter = p1.intersect(&p2);
+        let mut inter = intersect(&p1, &p2);
         let mut expected = vec![
             Point2D { x: 0, y: 0 },
             Point2D { x: 3, y: 3 },
@@ -395,57 +643,93 @@ mod tests {
         assert_eq!(inter, expected);
     }
 
+    #[test]
+    fn test_parse_error() {
+        let err = "R8,U5,X5,D3".parse::<Path>().unwrap_err();
+        assert_eq!(err.message(), "unknown direction");
+        assert_eq!(err.column(), Some(7));
+
+        let err = "R8,U5,L5x,D3".parse::<Path>().unwrap_err().on_line(3);
+        assert_eq!(
+            err.to_string(),
+            "cannot parse length\n\
+             \x20--> <input>:3:8\n\
+             \x20 |\n\
+             3 | R8,U5,L5x,D3\n\
+             \x20 |        ^^"
+        );
+    }
+
     #[test]
     fn test_intersection_parallel() {
         let p1 = "R5".parse::<Path>().unwrap();
         let p2 = "L5".parse::<Path>().unwrap();
-        let inter = p1.intersect(&p2);
-        assert_eq!(inter, vec![]);
+        let inter = intersect(&p1, &p2);
+        assert_eq!(inter, vec![Point2D::zero()]);
+
+        let p2 = "R3".parse::<Path>().unwrap();
+        let inter = intersect(&p1, &p2);
+        assert_eq!(
+            inter,
+            (0..=3).map(|x| Point2D::new(x, 0)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_overlap() {
+        let p1 = "R8".parse::<Path>().unwrap();
+        let p2 = "U2,R2,D2,R4".parse::<Path>().unwrap();
+        let r = distance_to_intersections(&p1, &p2);
+        let expected = (2..=6)
+            .map(|x| (Point2D::new(x, 0), x, 4 + x))
+            .collect::<Vec<_>>();
+        assert_eq!(r, expected);
+        assert_eq!(part1(&crossings(&[&p1, &p2])), Some(2));
+        assert_eq!(part2(&crossings(&[&p1, &p2])), Some(8));
+
+        // doubling back along the wire keeps the steps of the first visit
+        let p2 = "U1,R5,D1,L3".parse::<Path>().unwrap();
+        let r = distance_to_intersections(&p1, &p2);
+        assert_eq!(r[0], (Point2D::new(2, 0), 2, 10));
+        assert_eq!(r[3], (Point2D::new(5, 0), 5, 7));
+    }
+
+    #[test]
+    fn test_many_segments() {
+        let n = 25_000;
+        let p1 = vec!["R1,U1"; n].join(",").parse::<Path>().unwrap();
+        let p2 = vec!["U1,R1"; n].join(",").parse::<Path>().unwrap();
+        let inter = distance_to_intersections(&p1, &p2);
+        assert_eq!(inter.len(), n);
+        assert_eq!(part1(&crossings(&[&p1, &p2])), Some(2));
+        assert_eq!(part2(&crossings(&[&p1, &p2])), Some(4));
     }
 
     #[test]
     fn test_part1_ex1() {
         let (p1, p2) = example1();
-        let d = part1(&p1, &p2);
+        let d = part1(&crossings(&[&p1, &p2]));
         assert_eq!(d, Some(159));
     }
 
     #[test]
     fn test_part1_ex2() {
         let (p1, p2) = example2();
-        let d = part1(&p1, &p2);
+        let d = part1(&crossings(&[&p1, &p2]));
         assert_eq!(d, Some(135));
     }
 
     #[test]
-    fn test_extend_intersections() {
-        let (p1, p2) = example0();
-        let (p1new, p2new) = extend_with_intersections(&p1, &p2);
-        let p1expected = Path {
-            path: vec![
-                Point2D { x: 0, y: 0 },
-                Point2D { x: 8, y: 0 },
-                Point2D { x: 8, y: 5 },
-                Point2D { x: 6, y: 5 },
-                Point2D { x: 3, y: 5 },
-                Point2D { x: 3, y: 3 },
-                Point2D { x: 3, y: 2 },
-            ],
-        };
-        let p2expected = Path {
-            path: vec![
-                Point2D { x: 0, y: 0 },
-                Point2D { x: 0, y: 7 },
-                Point2D { x: 6, y: 7 },
-                Point2D { x: 6, y: 5 },
-                Point2D { x: 6, y: 3 },
-                Point2D { x: 3, y: 3 },
-                Point2D { x: 2, y: 3 },
-            ],
-        };
-        assert_eq!(p1new, p1expected);
-        assert_eq!(p2new, p2expected);
+    fn test_segments() {
+        let (p1, _) = example0();
+        let segments = p1.segments();
+        let steps: Vec<_> = segments.iter().map(|s| s.steps).collect();
+        assert_eq!(steps, [0, 8, 13, 18]);
+        assert!(segments[0].is_horizontal() && !segments[1].is_horizontal());
+        assert_eq!(segments[2].range(), (3, 8));
+        assert_eq!(segments[2].steps_to(Point2D::new(6, 5)), 15);
     }
+
     #[test]
     fn test_part2_ex0() {
         let (p1, p2) = example0();
@@ -461,14 +745,99 @@ mod tests {
     #[test]
     fn test_part2_ex1() {
         let (p1, p2) = example1();
-        let d = part2(&p1, &p2);
+        let d = part2(&crossings(&[&p1, &p2]));
         assert_eq!(d, Some(610));
     }
 
     #[test]
     fn test_part2_ex2() {
         let (p1, p2) = example2();
-        let d = part2(&p1, &p2);
+        let d = part2(&crossings(&[&p1, &p2]));
         assert_eq!(d, Some(410));
     }
+
+    #[test]
+    fn test_three_wires() {
+        let (p1, p2) = example0();
+        let p3 = "R4,U6".parse::<Path>().unwrap();
+        let paths = [p1, p2, p3];
+        let crossings = crossings(&paths.iter().collect::<Vec<_>>());
+        let points: Vec<_> = crossings.iter().map(|c| c.point).collect();
+        assert_eq!(
+            points,
+            [
+                (0, 0),
+                (1, 0),
+                (2, 0),
+                (3, 0),
+                (3, 3),
+                (4, 0),
+                (4, 3),
+                (4, 5),
+                (6, 5)
+            ]
+            .iter()
+            .map(|&(x, y)| Point2D::new(x, y))
+            .collect::<Vec<_>>()
+        );
+        // only the origin is on all three wires
+        assert_eq!(part1(&crossings), None);
+        assert_eq!(closest(&crossings, &[0, 2]), Some(1));
+        assert_eq!(closest(&crossings, &[1, 2]), Some(7));
+        assert_eq!(fewest_steps(&crossings, &[1, 2]), Some(19 + 7));
+        assert_eq!(crossings[6].steps, [None, Some(19), Some(7)]);
+        assert_eq!(crossings[6].delay(&[1, 2]), Some(26));
+        assert_eq!(crossings[7].steps, [Some(17), None, Some(9)]);
+        assert_eq!(crossings[7].delay(&[0, 1, 2]), None);
+
+        let summary = format_crossings(&paths, &crossings);
+        assert!(
+            summary.contains("Wires 2 and 3: 1 crossings, closest at distance 7, fewest steps 26")
+        );
+        assert!(summary.contains("All 3 wires: 0 crossings"));
+        assert!(summary.contains("(3, 0): steps 3, -, 3, delay 6"));
+    }
+
+    #[test]
+    fn test_self_intersections() {
+        let p = "R4,U2,L2,D4,L2,U2".parse::<Path>().unwrap();
+        assert_eq!(
+            p.self_intersections(),
+            [Point2D::zero(), Point2D::new(2, 0)]
+        );
+        // doubling back passes the points before the turn twice
+        let p = "R5,L3,U0".parse::<Path>().unwrap();
+        assert_eq!(
+            p.self_intersections(),
+            (2..=4).map(|x| Point2D::new(x, 0)).collect::<Vec<_>>()
+        );
+        let (p1, _) = example0();
+        assert_eq!(p1.self_intersections(), []);
+        let distances = p1.compute_distances(&[Point2D::new(3, 3), Point2D::new(1, 1)]);
+        assert_eq!(distances.get(&Point2D::new(3, 3)), Some(&20));
+        assert_eq!(distances.get(&Point2D::new(1, 1)), None);
+    }
+
+    #[test]
+    fn test_render() {
+        let (p1, p2) = example0();
+        let paths = [p1, p2];
+        let crossings = crossings(&paths.iter().collect::<Vec<_>>());
+        let expected = "\
+            ...........\n\
+            .+-----+...\n\
+            .|.....|...\n\
+            .|..+--X-+.\n\
+            .|..|..|.|.\n\
+            .|.-X--+.|.\n\
+            .|..|....|.\n\
+            .|.......|.\n\
+            .o-------+.\n\
+            ...........\n";
+        assert_eq!(to_ascii(&paths, &crossings).as_deref(), Some(expected));
+
+        let svg = to_svg(&paths, &crossings);
+        assert!(svg.contains("<polyline points=\"0,0 8,0 8,-5 3,-5 3,-2\""));
+        assert!(svg.contains("<circle cx=\"6\" cy=\"-5\""));
+    }
 }
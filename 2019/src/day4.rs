@@ -1,22 +1,19 @@
 mod common;
 use itertools::Itertools;
 
-fn get_digits(n: i64) -> [i8; 6] {
-    assert!(n >= 0);
-    assert!(n <= 999_999);
+fn get_digits(n: u128) -> Vec<i8> {
     let mut x = n;
-    let mut d = [0i8; 6];
-    const EXP: [i64; 6] = [100_000, 10_000, 1000, 100, 10, 1];
-    for i in 0..6usize {
-        let m = EXP[i];
-        d[i] = (x / m) as i8;
-        x %= m;
+    let mut d = vec![(x % 10) as i8];
+    while x >= 10 {
+        x /= 10;
+        d.push((x % 10) as i8);
     }
+    d.reverse();
     d
 }
 
 #[allow(dead_code)]
-fn check_criteria_part1_loop(n: i64) -> bool {
+fn check_criteria_part1_loop(n: u128) -> bool {
     let digits = get_digits(n);
     let mut last: i8 = -1;
     let mut has_double = false;
@@ -32,13 +29,14 @@ fn check_criteria_part1_loop(n: i64) -> bool {
     has_double
 }
 
-fn check_criteria_part1_windows(n: i64) -> bool {
+#[allow(dead_code)]
+fn check_criteria_part1_windows(n: u128) -> bool {
     let digits = get_digits(n);
     digits.windows(2).all(|w| w[0] <= w[1]) && digits.windows(2).any(|w| w[0] == w[1])
 }
 
 #[allow(dead_code)]
-fn check_criteria_part2_iter(n: i64) -> bool {
+fn check_criteria_part2_iter(n: u128) -> bool {
     let digits = get_digits(n);
     let has_double = digits
         .iter()
@@ -53,7 +51,7 @@ fn check_criteria_part2_iter(n: i64) -> bool {
 }
 
 #[allow(dead_code)]
-fn check_criteria_part2_loop(n: i64) -> bool {
+fn check_criteria_part2_loop(n: u128) -> bool {
     let digits = get_digits(n);
     let mut last: i8 = -1;
     let mut digit_count = 1;
@@ -78,39 +76,211 @@ fn check_criteria_part2_loop(n: i64) -> bool {
     has_double
 }
 
-fn count_passwords<F>(start: i64, end: i64, criterion: F) -> usize
+#[allow(dead_code)]
+fn count_passwords<F>(start: u128, end: u128, criterion: F) -> usize
 where
-    F: Fn(i64) -> bool,
+    F: Fn(u128) -> bool,
 {
     (start..=end).filter(|&n| criterion(n)).count()
 }
 
-fn main() {
-    let input: Vec<Vec<_>> = common::get_lines()
-        .into_iter()
-        .map(|l| {
-            l.split('-')
-                .map(|i| i.parse::<i64>().expect("could not parse number"))
-                .collect()
+// number of digits of the largest u128
+const MAX_DIGITS: usize = 39;
+
+// last digit, length of the run of equal digits it ends, and whether an
+// earlier run had a matching length
+type State = (usize, usize, bool);
+
+/// Counts the passwords, which are positive numbers with non-decreasing
+/// digits that have a run of equal digits whose length matches, by dynamic
+/// programming over the digits instead of checking every number.
+struct PasswordCounter {
+    // whether a run of the length matches
+    matching: Vec<bool>,
+    // number of ways to append the number of digits to a state that give a
+    // password, indexed by the number of digits and the state
+    completions: Vec<Vec<Vec<[u128; 2]>>>,
+}
+
+impl PasswordCounter {
+    fn new<F: Fn(usize) -> bool>(run: F) -> PasswordCounter {
+        let matching: Vec<bool> = (0..=MAX_DIGITS).map(run).collect();
+        let mut completions = vec![vec![vec![[0; 2]; MAX_DIGITS + 1]; 10]; MAX_DIGITS];
+        for remaining in 0..MAX_DIGITS {
+            for last in 0..10 {
+                for run in 1..=MAX_DIGITS - remaining {
+                    for found in [false, true] {
+                        completions[remaining][last][run][found as usize] = if remaining == 0 {
+                            (found || matching[run]) as u128
+                        } else {
+                            (last..10)
+                                .map(|d| {
+                                    let (last, run, found) =
+                                        advance(&matching, Some((last, run, found)), d);
+                                    completions[remaining - 1][last][run][found as usize]
+                                })
+                                .sum()
+                        };
+                    }
+                }
+            }
+        }
+        PasswordCounter {
+            matching,
+            completions,
+        }
+    }
+
+    fn completions(&self, remaining: usize, (last, run, found): State) -> u128 {
+        self.completions[remaining][last][run][found as usize]
+    }
+
+    // passwords with the number of digits
+    fn with_digits(&self, digits: usize) -> u128 {
+        (1..10)
+            .map(|d| self.completions(digits - 1, (d, 1, false)))
+            .sum()
+    }
+
+    /// Number of passwords from 1 to n.
+    fn count_up_to(&self, n: u128) -> u128 {
+        if n == 0 {
+            return 0;
+        }
+        let digits: Vec<usize> = get_digits(n).into_iter().map(|d| d as usize).collect();
+        let mut count: u128 = (1..digits.len()).map(|l| self.with_digits(l)).sum();
+        let mut state = None;
+        for (i, &digit) in digits.iter().enumerate() {
+            let lowest = state.map_or(1, |(last, _, _)| last);
+            let remaining = digits.len() - i - 1;
+            for d in lowest..digit {
+                count += self.completions(remaining, advance(&self.matching, state, d));
+            }
+            if digit < lowest {
+                return count;
+            }
+            state = Some(advance(&self.matching, state, digit));
+        }
+        count + self.completions(0, state.unwrap())
+    }
+
+    /// Number of passwords in the range, including both ends.
+    fn count(&self, start: u128, end: u128) -> u128 {
+        if start > end {
+            0
+        } else {
+            self.count_up_to(end) - self.count_up_to(start.saturating_sub(1))
+        }
+    }
+
+    /// Password at the index, counted from 0, in increasing order of all passwords.
+    fn select(&self, index: u128) -> Option<u128> {
+        let mut index = index;
+        let mut digits = 1;
+        while index >= self.with_digits(digits) {
+            index -= self.with_digits(digits);
+            digits += 1;
+            if digits > MAX_DIGITS {
+                return None;
+            }
+        }
+        let mut n: u128 = 0;
+        let mut state = None;
+        for i in 0..digits {
+            let lowest = state.map_or(1, |(last, _, _)| last);
+            let remaining = digits - i - 1;
+            for d in lowest..10 {
+                let next = advance(&self.matching, state, d);
+                let count = self.completions(remaining, next);
+                if index < count {
+                    // the largest passwords with 39 digits do not fit
+                    n = n.checked_mul(10)?.checked_add(d as u128)?;
+                    state = Some(next);
+                    break;
+                }
+                index -= count;
+            }
+        }
+        Some(n)
+    }
+
+    /// Password at the index, counted from 0, in increasing order of the
+    /// passwords in the range.
+    fn nth(&self, start: u128, end: u128, index: u128) -> Option<u128> {
+        let before = self.count_up_to(start.saturating_sub(1));
+        self.select(before.checked_add(index)?)
+            .filter(|&n| n <= end)
+    }
+}
+
+// state after appending the digit, which may not be smaller than the last one
+fn advance(matching: &[bool], state: Option<State>, d: usize) -> State {
+    match state {
+        None => (d, 1, false),
+        Some((last, run, found)) if d == last => (last, run + 1, found),
+        Some((_, run, found)) => (d, 1, found || matching[run]),
+    }
+}
+
+fn main() -> Result<(), common::InputError> {
+    let report = common::Report::new(2019, 4)?;
+    let input: Vec<Vec<u128>> = common::get_separated_input('-')?;
+    let index = common::option("nth")
+        .map(|s| {
+            s.parse::<u128>()
+                .map_err(|_| common::InputError::spanning(&s, &s, "could not parse index"))
         })
-        .collect();
+        .transpose()?;
+    let runs = common::option("runs")
+        .map(|s| common::parse_separated::<usize>(&s, ','))
+        .transpose()?;
+    let pair = PasswordCounter::new(|l| l >= 2);
+    let exact_pair = PasswordCounter::new(|l| l == 2);
     for range in input {
         assert!(range.len() == 2);
         let start = range[0];
         let end = range[1];
 
-        let result1 = count_passwords(start, end, check_criteria_part1_windows);
-        println!(
-            "Part1: Number of matching passwords in range {}-{}: {}",
-            start, end, result1
+        let result1 = pair.count(start, end);
+        report.part(
+            1,
+            result1,
+            format!(
+                "Part1: Number of matching passwords in range {}-{}: {}",
+                start, end, result1
+            ),
         );
 
-        let result2 = count_passwords(start, end, check_criteria_part2_loop);
-        println!(
-            "Part2: Number of matching passwords in range {}-{}: {}",
-            start, end, result2
+        let result2 = exact_pair.count(start, end);
+        report.part(
+            2,
+            result2,
+            format!(
+                "Part2: Number of matching passwords in range {}-{}: {}",
+                start, end, result2
+            ),
         );
+
+        if report.format() == common::Format::Text {
+            if let Some(index) = index {
+                for (part, counter) in [(1, &pair), (2, &exact_pair)] {
+                    match counter.nth(start, end, index) {
+                        Some(n) => println!("Password {} of part {}: {}", index, part, n),
+                        None => println!("Part {} has no password {}", part, index),
+                    }
+                }
+            }
+            if let Some(runs) = &runs {
+                let counter = PasswordCounter::new(|l| runs.contains(&l));
+                println!(
+                    "Passwords with a run of length {:?}: {}",
+                    runs,
+                    counter.count(start, end)
+                );
+            }
+        }
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -150,4 +320,76 @@ mod tests {
         assert!(!check_criteria_part2_loop(123_444));
         assert!(check_criteria_part2_loop(111_122));
     }
+
+    #[test]
+    fn test_count() {
+        let pair = PasswordCounter::new(|l| l >= 2);
+        let exact_pair = PasswordCounter::new(|l| l == 2);
+        for &(start, end) in &[
+            (1, 999),
+            (100, 12_345),
+            (111_111, 111_111),
+            (240_920, 789_857),
+        ] {
+            assert_eq!(
+                pair.count(start, end),
+                count_passwords(start, end, check_criteria_part1_windows) as u128
+            );
+            assert_eq!(
+                exact_pair.count(start, end),
+                count_passwords(start, end, check_criteria_part2_loop) as u128
+            );
+        }
+        assert_eq!(pair.count(10, 9), 0);
+        assert_eq!(pair.count(0, 11), 1);
+
+        let triple = PasswordCounter::new(|l| l == 3);
+        let criterion = |n| {
+            get_digits(n)
+                .iter()
+                .group_by(|&d| d)
+                .into_iter()
+                .any(|(_, e)| e.count() == 3)
+                && check_criteria_part1_windows(n)
+        };
+        assert_eq!(
+            triple.count(1, 200_000),
+            count_passwords(1, 200_000, criterion) as u128
+        );
+    }
+
+    #[test]
+    fn test_large_range() {
+        let pair = PasswordCounter::new(|l| l >= 2);
+        // numbers with non-decreasing digits without zeros are given by how
+        // often each digit occurs, and have a pair with more than 9 digits
+        assert_eq!(pair.count(10u128.pow(9), 10u128.pow(10) - 1), 43_758);
+        assert!(pair.count(1, u128::MAX) > pair.count(1, 10u128.pow(38)));
+    }
+
+    #[test]
+    fn test_nth() {
+        let pair = PasswordCounter::new(|l| l >= 2);
+        assert_eq!(pair.nth(1, 1000, 0), Some(11));
+        assert_eq!(pair.nth(1, 1000, 1), Some(22));
+        assert_eq!(pair.nth(12, 1000, 0), Some(22));
+        assert_eq!(pair.nth(100, 1000, 0), Some(111));
+        assert_eq!(pair.nth(100, 1000, 1), Some(112));
+        assert_eq!(pair.nth(100, 110, 0), None);
+
+        let exact_pair = PasswordCounter::new(|l| l == 2);
+        let (start, end) = (240_920, 789_857);
+        let all: Vec<u128> = (start..=end)
+            .filter(|&n| check_criteria_part2_loop(n))
+            .collect();
+        for (i, &n) in all.iter().enumerate().step_by(37) {
+            assert_eq!(exact_pair.nth(start, end, i as u128), Some(n));
+        }
+        assert_eq!(exact_pair.nth(start, end, all.len() as u128), None);
+
+        let big = 10u128.pow(30);
+        let n = pair.nth(big, u128::MAX, 0).unwrap();
+        assert_eq!(n, "1".repeat(31).parse::<u128>().unwrap());
+        assert_eq!(pair.count(big, n), 1);
+    }
 }
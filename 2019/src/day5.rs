@@ -112,8 +112,8 @@ impl ProgramIO {
     fn next_output(&self) -> Option<i32> {
         self.or.recv().expect("could not send input")
     }
-    fn output_iter(&self) -> OutputIterator {
-        OutputIterator { io: &self }
+    fn output_iter(&self) -> OutputIterator<'_> {
+        OutputIterator { io: self }
     }
     fn collect_outputs(&self) -> Vec<i32> {
         self.output_iter().collect()
@@ -223,24 +223,31 @@ impl ProgramRunner {
     }
 }
 
-fn main() {
-    let input: Vec<Vec<i32>> = common::get_lines()
-        .into_iter()
-        .map(|l| {
-            l.split(',')
-                .map(|i| i.parse::<i32>().expect("could not parse number"))
-                .collect()
-        })
-        .collect();
+fn main() -> Result<(), common::InputError> {
+    let report = common::Report::new(2019, 5)?;
+    let input: Vec<Vec<i32>> = common::get_separated_input(',')?;
     for program in input {
         let p = ProgramRunner::new(&program);
 
         let output1 = p.run_with(&[1]);
-        println!("Part1: Program output is: {:?}", output1);
+        // the answer is the last output, preceded by test results
+        let answer1 = output1.last().map_or_else(String::new, i32::to_string);
+        report.part(
+            1,
+            answer1,
+            format!("Part1: Program output is: {:?}", output1),
+        );
 
         let output2 = p.run_with(&[5]);
-        println!("Part2: Program output is: {:?}", output2);
+        // the answer is the last output, preceded by test results
+        let answer2 = output2.last().map_or_else(String::new, i32::to_string);
+        report.part(
+            2,
+            answer2,
+            format!("Part2: Program output is: {:?}", output2),
+        );
     }
+    Ok(())
 }
 
 #[cfg(test)]
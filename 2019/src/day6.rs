@@ -1,6 +1,6 @@
 mod common;
-use std::collections::HashMap;
-use std::collections::VecDeque;
+use common::graph::{Cycle, Graph, NodeId};
+use common::InputError;
 use std::str::FromStr;
 
 struct Edge {
@@ -9,12 +9,12 @@ struct Edge {
 }
 
 impl FromStr for Edge {
-    type Err = &'static str;
+    type Err = InputError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let m: Vec<_> = s.split(')').collect();
         if m.len() != 2 {
-            Err("could not parse edge")
+            Err(InputError::spanning(s, s, "could not parse edge"))
         } else {
             Ok(Edge {
                 source: m[0].to_string(),
@@ -24,71 +24,286 @@ impl FromStr for Edge {
     }
 }
 
+// edges go from the center of mass to the object in orbit around it,
+// which form a tree rooted at the only object that orbits nothing
 struct OrbitMap {
-    edges: HashMap<String, Vec<String>>,
+    graph: Graph,
+    depth: Vec<usize>,
+    // ancestors[k][v] is the object 2^k levels above v, or the root
+    ancestors: Vec<Vec<NodeId>>,
+    subtree_size: Vec<usize>,
 }
 
 impl OrbitMap {
-    fn from_edges(edges: &[Edge]) -> OrbitMap {
-        let mut m = OrbitMap {
-            edges: HashMap::new(),
+    fn from_edges(edges: &[Edge]) -> Result<OrbitMap, InputError> {
+        let mut graph = Graph::new();
+        for (i, e) in edges.iter().enumerate() {
+            let source = graph.node(&e.source);
+            let target = graph.node(&e.target);
+            if let Some(parent) = graph.predecessors(target).first() {
+                let message = if parent.target == source {
+                    format!("{} orbits {} more than once", e.target, e.source)
+                } else {
+                    format!(
+                        "{} orbits both {} and {}",
+                        e.target,
+                        graph.name(parent.target),
+                        e.source
+                    )
+                };
+                return Err(InputError::new(message).on_line(i + 1));
+            }
+            graph.add_edge(source, target, ());
+        }
+        let order = graph.topological_order().map_err(|Cycle(cycle)| {
+            let names: Vec<_> = cycle.iter().map(|&c| graph.name(c)).collect();
+            InputError::new(format!("orbits form a cycle: {}", names.join(" -> ")))
+        })?;
+        let roots: Vec<_> = graph
+            .ids()
+            .filter(|&v| graph.predecessors(v).is_empty())
+            .collect();
+        let root = match graph.id("COM").filter(|c| roots.contains(c)) {
+            Some(com) => com,
+            None => *roots
+                .first()
+                .ok_or_else(|| InputError::new("orbit map is empty"))?,
         };
-        for e in edges {
-            let u = &e.source;
-            let v = &e.target;
-            m.edges
-                .entry(u.clone())
-                .or_insert_with(|| vec![])
-                .push(v.clone());
-            m.edges
-                .entry(v.clone())
-                .or_insert_with(|| vec![])
-                .push(u.clone());
+        if roots.len() > 1 {
+            let others: Vec<_> = roots
+                .iter()
+                .filter(|&&r| r != root)
+                .map(|&r| graph.name(r))
+                .collect();
+            return Err(InputError::new(format!(
+                "objects not connected to {}: {}",
+                graph.name(root),
+                others.join(", ")
+            )));
+        }
+
+        // the topological order has every object after the one it orbits
+        let n = graph.len();
+        let mut depth = vec![0; n];
+        let mut parent = vec![root; n];
+        for &v in &order {
+            for e in graph.successors(v) {
+                depth[e.target] = depth[v] + 1;
+                parent[e.target] = v;
+            }
         }
-        m
-    }
-
-    fn distances<'a>(&'a self, source: &'a str) -> HashMap<&'a str, i32> {
-        let mut distances = HashMap::new();
-        let mut queue = VecDeque::new();
-        distances.insert(source, 0);
-        queue.push_back(source);
-        while let Some(u) = queue.pop_front() {
-            let dist = *distances.get(u).unwrap();
-            for v in self.edges.get(u).iter().flat_map(|e| e.iter()) {
-                distances.entry(v).or_insert_with(|| {
-                    queue.push_back(v);
-                    dist + 1
-                });
+        let mut subtree_size = vec![1; n];
+        for &v in order.iter().rev() {
+            if v != root {
+                subtree_size[parent[v]] += subtree_size[v];
             }
         }
-        distances
+        let max_depth = depth.iter().copied().max().unwrap_or(0);
+        let mut ancestors = vec![parent];
+        while 1 << ancestors.len() <= max_depth {
+            let last = ancestors.last().unwrap();
+            let next = last.iter().map(|&a| last[a]).collect();
+            ancestors.push(next);
+        }
+        Ok(OrbitMap {
+            graph,
+            depth,
+            ancestors,
+            subtree_size,
+        })
+    }
+
+    /// Number of objects that the object orbits directly or indirectly.
+    fn depth(&self, name: &str) -> Option<usize> {
+        Some(self.depth[self.graph.id(name)?])
+    }
+
+    /// Number of objects in orbit around the object directly or
+    /// indirectly, including itself.
+    fn subtree_size(&self, name: &str) -> Option<usize> {
+        Some(self.subtree_size[self.graph.id(name)?])
+    }
+
+    // object the number of levels closer to the root, in O(log n)
+    fn ancestor_id(&self, v: NodeId, levels: usize) -> Option<NodeId> {
+        if levels > self.depth[v] {
+            return None;
+        }
+        let mut v = v;
+        for (k, ancestors) in self.ancestors.iter().enumerate() {
+            if levels >> k & 1 == 1 {
+                v = ancestors[v];
+            }
+        }
+        Some(v)
+    }
+
+    /// Object that the object orbits indirectly over the number of levels.
+    fn ancestor(&self, name: &str, levels: usize) -> Option<&str> {
+        let v = self.ancestor_id(self.graph.id(name)?, levels)?;
+        Some(self.graph.name(v))
+    }
+
+    // lowest common ancestor with binary lifting, in O(log n)
+    fn lca_id(&self, a: NodeId, b: NodeId) -> NodeId {
+        let (a, b) = if self.depth[a] >= self.depth[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        let mut a = self.ancestor_id(a, self.depth[a] - self.depth[b]).unwrap();
+        let mut b = b;
+        if a == b {
+            return a;
+        }
+        for ancestors in self.ancestors.iter().rev() {
+            if ancestors[a] != ancestors[b] {
+                a = ancestors[a];
+                b = ancestors[b];
+            }
+        }
+        self.ancestors[0][a]
+    }
+
+    /// Closest object that both objects orbit directly or indirectly, or
+    /// one of the objects if it is orbited by the other.
+    fn lca(&self, a: &str, b: &str) -> Option<&str> {
+        let v = self.lca_id(self.graph.id(a)?, self.graph.id(b)?);
+        Some(self.graph.name(v))
+    }
+
+    fn distance_ids(&self, a: NodeId, b: NodeId) -> usize {
+        self.depth[a] + self.depth[b] - 2 * self.depth[self.lca_id(a, b)]
+    }
+
+    /// Number of orbits between the objects, moving in both directions.
+    fn distance(&self, a: &str, b: &str) -> Option<usize> {
+        Some(self.distance_ids(self.graph.id(a)?, self.graph.id(b)?))
+    }
+
+    fn checksum(&self) -> usize {
+        self.depth.iter().sum()
+    }
+
+    // number of orbital transfers to move from the object that source
+    // orbits to the object that target orbits
+    fn transfers(&self, source: &str, target: &str) -> Option<usize> {
+        let source = self.ancestor_id(self.graph.id(source)?, 1)?;
+        let target = self.ancestor_id(self.graph.id(target)?, 1)?;
+        Some(self.distance_ids(source, target))
     }
 
-    fn checksum(&self) -> i32 {
-        let orbits = self.distances("COM");
-        orbits.values().sum()
+    fn batch_transfers<S: AsRef<str>>(&self, pairs: &[(S, S)]) -> Vec<Option<usize>> {
+        pairs
+            .iter()
+            .map(|(source, target)| self.transfers(source.as_ref(), target.as_ref()))
+            .collect()
     }
 
-    fn transfers(&self, source: &str, target: &str) -> Option<i32> {
-        let distances = self.distances(source);
-        let distance = distances.get(target)?;
-        Some(distance - 2)
+    // DOT graph of the map, highlighting the orbits between source and target
+    fn to_dot(&self, path: Option<(&str, &str)>) -> String {
+        let highlight = path
+            .and_then(|(source, target)| {
+                let source = self.graph.id(source)?;
+                let target = self.graph.id(target)?;
+                let lca = self.lca_id(source, target);
+                let mut path: Vec<_> = (0..=self.depth[source] - self.depth[lca])
+                    .filter_map(|l| self.ancestor_id(source, l))
+                    .collect();
+                path.extend(
+                    (0..self.depth[target] - self.depth[lca])
+                        .rev()
+                        .filter_map(|l| self.ancestor_id(target, l)),
+                );
+                Some(path)
+            })
+            .unwrap_or_default();
+        self.graph.to_dot(|_| None, &highlight)
     }
 }
 
-fn main() {
-    let edges: Vec<_> = common::get_lines()
-        .into_iter()
-        .map(|l| l.parse::<Edge>().expect("could not parse orbit"))
-        .collect();
-    let map = OrbitMap::from_edges(&edges);
+// pairs of objects separated by whitespace, one per line
+fn parse_queries(filename: &str) -> Result<Vec<(String, String)>, InputError> {
+    let content = common::read_file(filename)?;
+    content
+        .lines()
+        .enumerate()
+        .map(
+            |(i, line)| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [source, target] => Ok((source.to_string(), target.to_string())),
+                _ => Err(InputError::spanning(line, line, "expected two objects")
+                    .on_line(i + 1)
+                    .in_file(filename)),
+            },
+        )
+        .collect()
+}
+
+fn main() -> Result<(), InputError> {
+    let report = common::Report::new(2019, 6)?;
+    let map = common::parse_content_with(|content| {
+        let edges = content
+            .lines()
+            .map(|l| l.parse::<Edge>().map_err(|e| e.within(content, l)))
+            .collect::<Result<Vec<_>, _>>()?;
+        OrbitMap::from_edges(&edges)
+    })?;
+    if let Some(filename) = common::option("dot") {
+        let highlight = common::option("highlight");
+        let path = match &highlight {
+            Some(highlight) => Some(highlight.split_once(',').ok_or_else(|| {
+                InputError::spanning(highlight, highlight, "expected two objects as <from>,<to>")
+            })?),
+            None => None,
+        };
+        common::write_file(&filename, map.to_dot(path).as_bytes())?;
+    }
 
     let result1 = map.checksum();
-    println!("Part1: orbit map checksum: {}", result1);
+    report.part(
+        1,
+        result1,
+        format!("Part1: orbit map checksum: {}", result1),
+    );
+
+    let result2 = map
+        .transfers("YOU", "SAN")
+        .ok_or_else(|| InputError::new("YOU and SAN do not both orbit an object"))?;
+    report.part(2, result2, format!("Part2: orbital transfers: {}", result2));
 
-    let result2 = map.transfers("YOU", "SAN").unwrap();
-    println!("Part1: orbit map checksum: {}", result2);
+    if let Some(filename) = common::option("queries") {
+        let queries = parse_queries(&filename)?;
+        if report.format() == common::Format::Text {
+            for ((source, target), transfers) in queries.iter().zip(map.batch_transfers(&queries)) {
+                match (transfers, map.lca(source, target)) {
+                    (Some(t), Some(center)) => println!(
+                        "{} -> {}: {} transfers around {}",
+                        source, target, t, center
+                    ),
+                    _ => println!("{} -> {}: no transfers", source, target),
+                }
+            }
+        }
+    }
+    if let Some(object) = common::option("object") {
+        let depth = map
+            .depth(&object)
+            .ok_or_else(|| InputError::spanning(&object, &object, "unknown object"))?;
+        if report.format() == common::Format::Text {
+            let path: Vec<_> = (1..=depth)
+                .filter_map(|l| map.ancestor(&object, l))
+                .collect();
+            println!("{} orbits {} objects: {}", object, depth, path.join(", "));
+            println!(
+                "{} objects orbit {}, {} orbital transfers from YOU",
+                map.subtree_size(&object).unwrap() - 1,
+                object,
+                map.distance("YOU", &object)
+                    .map_or("no".to_string(), |d| d.to_string())
+            );
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -101,11 +316,10 @@ mod tests {
             "COM)B", "B)C", "C)D", "D)E", "E)F", "B)G", "G)H", "D)I", "E)J", "J)K", "K)L",
         ];
         let edges: Vec<_> = input.iter().map(|e| e.parse::<Edge>().unwrap()).collect();
-        let map = OrbitMap::from_edges(&edges);
-        let orbits = map.distances("COM");
-        assert_eq!(orbits.get("D"), Some(&3));
-        assert_eq!(orbits.get("L"), Some(&7));
-        assert_eq!(orbits.get("COM"), Some(&0));
+        let map = OrbitMap::from_edges(&edges).unwrap();
+        assert_eq!(map.depth("D"), Some(3));
+        assert_eq!(map.depth("L"), Some(7));
+        assert_eq!(map.depth("COM"), Some(0));
         let checksum = map.checksum();
         assert_eq!(checksum, 42)
     }
@@ -117,12 +331,92 @@ mod tests {
             "I)SAN",
         ];
         let edges: Vec<_> = input.iter().map(|e| e.parse::<Edge>().unwrap()).collect();
-        let map = OrbitMap::from_edges(&edges);
-        let distances = map.distances("YOU");
-        assert_eq!(distances.get("YOU"), Some(&0));
-        assert_eq!(distances.get("K"), Some(&1));
-        assert_eq!(distances.get("SAN"), Some(&6));
+        let map = OrbitMap::from_edges(&edges).unwrap();
+        assert_eq!(map.distance("YOU", "YOU"), Some(0));
+        assert_eq!(map.distance("YOU", "K"), Some(1));
+        assert_eq!(map.distance("YOU", "SAN"), Some(6));
         let transfers = map.transfers("YOU", "SAN");
         assert_eq!(transfers, Some(4))
     }
+
+    #[test]
+    fn test_to_dot() {
+        let input = ["COM)B", "B)YOU", "COM)SAN"];
+        let edges: Vec<_> = input.iter().map(|e| e.parse::<Edge>().unwrap()).collect();
+        let map = OrbitMap::from_edges(&edges).unwrap();
+        let dot = map.to_dot(Some(("YOU", "SAN")));
+        assert!(dot.starts_with("digraph {\n"));
+        assert!(dot.contains("    2 [label=\"YOU\", color=red, penwidth=2];\n"));
+        assert!(dot.contains("    0 -> 1 [color=red, penwidth=2];\n"));
+        assert!(dot.contains("    0 -> 3 [color=red, penwidth=2];\n"));
+        assert_eq!(map.to_dot(None).matches("red").count(), 0);
+    }
+
+    fn parse_map(input: &[&str]) -> Result<OrbitMap, InputError> {
+        let edges: Vec<_> = input.iter().map(|e| e.parse::<Edge>().unwrap()).collect();
+        OrbitMap::from_edges(&edges)
+    }
+
+    #[test]
+    fn test_tree_queries() {
+        let map = parse_map(&[
+            "COM)B", "B)C", "C)D", "D)E", "E)F", "B)G", "G)H", "D)I", "E)J", "J)K", "K)L", "K)YOU",
+            "I)SAN",
+        ])
+        .unwrap();
+        assert_eq!(map.lca("YOU", "SAN"), Some("D"));
+        assert_eq!(map.lca("H", "L"), Some("B"));
+        assert_eq!(map.lca("L", "E"), Some("E"));
+        assert_eq!(map.lca("COM", "COM"), Some("COM"));
+        assert_eq!(map.lca("X", "COM"), None);
+        assert_eq!(map.ancestor("L", 0), Some("L"));
+        assert_eq!(map.ancestor("L", 3), Some("E"));
+        assert_eq!(map.ancestor("L", 7), Some("COM"));
+        assert_eq!(map.ancestor("L", 8), None);
+        assert_eq!(map.subtree_size("COM"), Some(14));
+        assert_eq!(map.subtree_size("E"), Some(6));
+        assert_eq!(map.subtree_size("YOU"), Some(1));
+        assert_eq!(
+            map.batch_transfers(&[("YOU", "SAN"), ("L", "F"), ("YOU", "COM"), ("H", "X")]),
+            [Some(4), Some(2), None, None]
+        );
+        assert_eq!(map.to_dot(Some(("YOU", "SAN"))).matches("red").count(), 13);
+    }
+
+    #[test]
+    fn test_deep_chain() {
+        let names: Vec<String> = (0..1000).map(|i| format!("O{}", i)).collect();
+        let edges: Vec<String> = names
+            .windows(2)
+            .map(|w| format!("{}){}", w[0], w[1]))
+            .collect();
+        let edges: Vec<&str> = edges.iter().map(|e| e.as_str()).collect();
+        let map = parse_map(&edges).unwrap();
+        assert_eq!(map.lca("O999", "O500"), Some("O500"));
+        assert_eq!(map.ancestor("O999", 998), Some("O1"));
+        assert_eq!(map.distance("O3", "O997"), Some(994));
+        assert_eq!(map.checksum(), 999 * 1000 / 2);
+    }
+
+    #[test]
+    fn test_validation() {
+        let error = parse_map(&["COM)A", "COM)B", "B)A"]).err().unwrap();
+        assert_eq!(error.message(), "A orbits both COM and B");
+        assert_eq!(error.line(), Some(3));
+        let error = parse_map(&["COM)A", "COM)A"]).err().unwrap();
+        assert_eq!(error.message(), "A orbits COM more than once");
+
+        let error = parse_map(&["COM)A", "A)B", "C)D", "D)E", "E)C"])
+            .err()
+            .unwrap();
+        assert!(error.message().starts_with("orbits form a cycle: "));
+        for name in &["C", "D", "E"] {
+            assert!(error.message().contains(name));
+        }
+
+        let error = parse_map(&["X)Y", "COM)A", "Z)W"]).err().unwrap();
+        assert_eq!(error.message(), "objects not connected to COM: X, Z");
+        let error = parse_map(&[]).err().unwrap();
+        assert_eq!(error.message(), "orbit map is empty");
+    }
 }
@@ -1,286 +1,298 @@
 mod common;
-use std::sync::mpsc::channel;
-use std::sync::mpsc::Receiver;
-use std::sync::mpsc::Sender;
-
-#[derive(PartialEq, Eq, Copy, Clone)]
-enum Op {
-    Add,
-    Mul,
-}
-#[derive(PartialEq, Eq, Copy, Clone)]
-enum Cnd {
-    True,
-    False,
-}
-#[derive(PartialEq, Eq, Copy, Clone)]
-enum Cmp {
-    LessThan,
-    Equal,
-}
-#[derive(PartialEq, Eq, Copy, Clone)]
-enum OpCode {
-    Arith(Op),
-    Input,
-    Output,
-    JumpIf(Cnd),
-    Compare(Cmp),
-    Halt,
-}
-struct ParamOpCode {
-    opcode: OpCode,
-    params: [u8; 3],
-}
+use common::intcode::Machine;
+use common::InputError;
+use std::convert::TryFrom;
+use std::ops::RangeInclusive;
 
-fn parse_opcode(code: i32) -> ParamOpCode {
-    let param1 = ((code / 100) % 10) as u8;
-    let param2 = ((code / 1000) % 10) as u8;
-    let param3 = ((code / 10000) % 10) as u8;
-    let opcode = match code % 100 {
-        1 => OpCode::Arith(Op::Add),
-        2 => OpCode::Arith(Op::Mul),
-        3 => OpCode::Input,
-        4 => OpCode::Output,
-        5 => OpCode::JumpIf(Cnd::True),
-        6 => OpCode::JumpIf(Cnd::False),
-        7 => OpCode::Compare(Cmp::LessThan),
-        8 => OpCode::Compare(Cmp::Equal),
-        99 => OpCode::Halt,
-        _ => panic!("unknown opcode"),
-    };
-    ParamOpCode {
-        opcode,
-        params: [param1, param2, param3],
-    }
+/// Where the outputs of each amplifier go.
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Wiring {
+    /// Each amplifier feeds the next one.
+    Serial,
+    /// Like serial, with the last amplifier also feeding the first one.
+    Feedback,
+    /// The amplifiers that each amplifier feeds.
+    Custom(Vec<Vec<usize>>),
 }
 
-struct ProgramState {
-    mem: Vec<i32>,
-    pointer: usize,
+/// Circuit of amplifiers running the same program, each of which gets its
+/// phase setting as first input. The first amplifier gets the signal 0 and
+/// the output of the circuit is the last signal of the last amplifier.
+#[derive(Debug, Clone)]
+struct Circuit {
+    program: Vec<i64>,
+    amplifiers: usize,
+    phases: Vec<i64>,
+    wiring: Wiring,
 }
 
-impl ProgramState {
-    fn fetch_opcode(&self) -> i32 {
-        self.mem[self.pointer]
-    }
+/// Phase setting with the highest signal found by a search.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Setting {
+    phases: Vec<i64>,
+    signal: i64,
+}
 
-    fn increase_pointer(&mut self, position: usize) {
-        self.pointer += position;
+impl Circuit {
+    /// Circuit of five amplifiers in series with the phases 0 to 4.
+    fn new(program: &[i64]) -> Circuit {
+        Circuit {
+            program: program.to_vec(),
+            amplifiers: 5,
+            phases: (0..5).collect(),
+            wiring: Wiring::Serial,
+        }
     }
 
-    fn set_pointer(&mut self, position: usize) {
-        self.pointer = position;
+    fn with_amplifiers(self, amplifiers: usize) -> Circuit {
+        Circuit { amplifiers, ..self }
     }
 
-    fn fetch_value(&self, position: usize, p: &ParamOpCode) -> i32 {
-        if p.params[position] == 0 {
-            self.mem[self.mem[self.pointer + position + 1] as usize]
-        } else {
-            self.mem[self.pointer + position + 1]
+    /// Phases that can be given to the amplifiers, each to at most one.
+    fn with_phases(self, phases: RangeInclusive<i64>) -> Circuit {
+        Circuit {
+            phases: phases.collect(),
+            ..self
         }
     }
 
-    fn write_value(&mut self, position: usize, value: i32) {
-        let target = self.mem[self.pointer + position + 1] as usize;
-        self.mem[target] = value;
+    fn with_wiring(self, wiring: Wiring) -> Circuit {
+        Circuit { wiring, ..self }
     }
-}
 
-struct Program {
-    program: Vec<i32>,
-    ir: Receiver<Option<i32>>,
-    os: Sender<Option<i32>>,
-}
-
-impl Program {
-    fn new(program: &[i32], ir: Receiver<Option<i32>>, os: Sender<Option<i32>>) -> Program {
-        Program {
-            program: program.to_vec(),
-            ir,
-            os,
+    // amplifiers that each amplifier feeds
+    fn targets(&self) -> Vec<Vec<usize>> {
+        let n = self.amplifiers;
+        match &self.wiring {
+            Wiring::Serial => (0..n).map(|i| (i + 1..n).take(1).collect()).collect(),
+            Wiring::Feedback => (0..n).map(|i| vec![(i + 1) % n]).collect(),
+            Wiring::Custom(targets) => targets.clone(),
         }
     }
-    fn run(&self) {
-        let mut state = ProgramState {
-            mem: self.program.clone(),
-            pointer: 0,
-        };
+
+    /// Output of the circuit with the phase settings, running the
+    /// amplifiers in turn until all have halted or wait for input, or
+    /// `None` if the last amplifier produces no output or any amplifier
+    /// faults, like with a phase its program does not support.
+    fn run(&self, phase_settings: &[i64]) -> Option<i64> {
+        assert_eq!(phase_settings.len(), self.amplifiers);
+        let targets = self.targets();
+        let mut machines: Vec<_> = phase_settings
+            .iter()
+            .map(|&phase| {
+                let mut machine = Machine::new(&self.program);
+                machine.push_input(phase);
+                machine
+            })
+            .collect();
+        machines[0].push_input(0);
+        let last = self.amplifiers - 1;
+        let mut signal = None;
         loop {
-            let p = parse_opcode(state.fetch_opcode());
-            match p.opcode {
-                OpCode::Arith(op) => {
-                    let x = state.fetch_value(0, &p);
-                    let y = state.fetch_value(1, &p);
-                    let z = match op {
-                        Op::Add => x + y,
-                        Op::Mul => x * y,
-                    };
-                    state.write_value(2, z);
-                    state.increase_pointer(4);
-                }
-                OpCode::Input => {
-                    // panic if there is no more input
-                    let i = self.ir.recv().unwrap().unwrap();
-                    state.write_value(0, i);
-                    state.increase_pointer(2);
-                }
-                OpCode::Output => {
-                    let o = state.fetch_value(0, &p);
-                    // ignore send errors
-                    let _ = self.os.send(Some(o));
-                    state.increase_pointer(2);
-                }
-                OpCode::JumpIf(condition) => {
-                    let x = state.fetch_value(0, &p);
-                    let matched = match condition {
-                        Cnd::True => x != 0,
-                        Cnd::False => x == 0,
-                    };
-                    if matched {
-                        let y = state.fetch_value(1, &p);
-                        state.set_pointer(y as usize);
-                    } else {
-                        state.increase_pointer(3);
+            let steps: u64 = machines.iter().map(|m| m.steps()).sum();
+            for i in 0..machines.len() {
+                for o in machines[i].try_run_to_input().ok()? {
+                    for &t in &targets[i] {
+                        machines[t].push_input(o);
                     }
-                }
-                OpCode::Compare(comparison) => {
-                    let x = state.fetch_value(0, &p);
-                    let y = state.fetch_value(1, &p);
-                    let result = match comparison {
-                        Cmp::LessThan => x < y,
-                        Cmp::Equal => x == y,
-                    };
-                    if result {
-                        state.write_value(2, 1);
-                    } else {
-                        state.write_value(2, 0);
+                    if i == last {
+                        signal = Some(o);
                     }
-                    state.increase_pointer(4);
-                }
-                OpCode::Halt => {
-                    // ignore send errors
-                    let _ = self.os.send(None);
-                    break;
                 }
             }
+            if machines.iter().map(|m| m.steps()).sum::<u64>() == steps {
+                return signal;
+            }
         }
     }
-}
 
-fn run_amplifiers(program: &[i32], phase_settings: &[i32; 5], feedback: bool) -> i32 {
-    let (input, a_in) = channel::<Option<i32>>();
-    let (a_out, b_in) = channel::<Option<i32>>();
-    let (b_out, c_in) = channel::<Option<i32>>();
-    let (c_out, d_in) = channel::<Option<i32>>();
-    let (d_out, e_in) = channel::<Option<i32>>();
-    let (e_out, output) = channel::<Option<i32>>();
-
-    input.send(Some(phase_settings[0])).unwrap();
-    a_out.send(Some(phase_settings[1])).unwrap();
-    b_out.send(Some(phase_settings[2])).unwrap();
-    c_out.send(Some(phase_settings[3])).unwrap();
-    d_out.send(Some(phase_settings[4])).unwrap();
-
-    let amp_a = Program::new(&program, a_in, a_out);
-    let amp_b = Program::new(&program, b_in, b_out);
-    let amp_c = Program::new(&program, c_in, c_out);
-    let amp_d = Program::new(&program, d_in, d_out);
-    let amp_e = Program::new(&program, e_in, e_out);
-
-    let a_thread = std::thread::spawn(move || {
-        amp_a.run();
-    });
-    let b_thread = std::thread::spawn(move || {
-        amp_b.run();
-    });
-    let c_thread = std::thread::spawn(move || {
-        amp_c.run();
-    });
-    let d_thread = std::thread::spawn(move || {
-        amp_d.run();
-    });
-    let e_thread = std::thread::spawn(move || {
-        amp_e.run();
-    });
-
-    input.send(Some(0)).unwrap();
-    let final_output = if feedback {
-        let mut last_output = None;
-        while let Some(out) = output.recv().unwrap() {
-            last_output = Some(out);
-            // ignore error in case first thread has already terminated
-            let _ = input.send(Some(out));
+    fn distinct_phases(&self) -> Vec<i64> {
+        let mut phases = self.phases.clone();
+        phases.sort_unstable();
+        phases.dedup();
+        phases
+    }
+
+    // number of assignments of different phases to the amplifiers,
+    // limited to what fits into 64 bits
+    fn setting_count(&self) -> u64 {
+        let phases = self.distinct_phases().len();
+        if self.amplifiers > phases {
+            return 0;
         }
-        last_output
-    } else {
-        output.recv().unwrap()
-    };
-    let result = final_output.expect("final amplifier did not produce any output");
+        let count = permutations(phases, self.amplifiers).unwrap_or(u128::MAX);
+        u64::try_from(count).unwrap_or(u64::MAX)
+    }
 
-    a_thread.join().expect("could not join thread");
-    b_thread.join().expect("could not join thread");
-    c_thread.join().expect("could not join thread");
-    d_thread.join().expect("could not join thread");
-    e_thread.join().expect("could not join thread");
+    // assignment with the index in lexicographic order, decoding the
+    // index digit by digit with the number of completions of each prefix
+    fn nth_setting(&self, index: u64) -> Vec<i64> {
+        let mut phases = self.distinct_phases();
+        let mut index = u128::from(index);
+        (1..=self.amplifiers)
+            .map(|placed| {
+                match permutations(phases.len() - 1, self.amplifiers - placed) {
+                    Some(completions) => {
+                        let digit = (index / completions) as usize;
+                        index %= completions;
+                        phases.remove(digit)
+                    }
+                    // more completions than any index
+                    None => phases.remove(0),
+                }
+            })
+            .collect()
+    }
 
-    result
+    /// Phase setting with the highest signal, trying the settings on all
+    /// available threads, each of which generates its own range of them.
+    /// Of equally good settings the first in lexicographic order is taken.
+    fn best_setting(&self) -> Option<Setting> {
+        let count = self.setting_count();
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get()) as u64;
+        let chunk = count.div_ceil(threads).max(1);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|t| {
+                    let start = (t * chunk).min(count);
+                    let end = start.saturating_add(chunk).min(count);
+                    scope.spawn(move || {
+                        (start..end)
+                            .filter_map(|index| {
+                                let phases = self.nth_setting(index);
+                                self.run(&phases).map(|signal| Setting { phases, signal })
+                            })
+                            .fold(None, better)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("could not join thread"))
+                .fold(None, |best, s| s.into_iter().fold(best, better))
+        })
+    }
 }
 
-fn next_phase(phase: &mut [i32; 5]) -> bool {
-    // Find non-increasing suffix
-    let mut i: usize = phase.len() - 1;
-    while i > 0 && phase[i - 1] >= phase[i] {
-        i -= 1;
-    }
-    if i == 0 {
-        return false;
+// ordered selections of k out of n elements, if the number fits into 128 bits
+fn permutations(n: usize, k: usize) -> Option<u128> {
+    (n - k + 1..=n).try_fold(1u128, |p, f| p.checked_mul(f as u128))
+}
+
+// keeps the setting with the higher signal, or the earlier one
+fn better(best: Option<Setting>, s: Setting) -> Option<Setting> {
+    match best {
+        Some(b) if b.signal > s.signal || (b.signal == s.signal && b.phases <= s.phases) => Some(b),
+        _ => Some(s),
     }
+}
 
-    // Find successor to pivot
-    let mut j: usize = phase.len() - 1;
-    while phase[j] <= phase[i - 1] {
-        j -= 1;
+fn find_best_phase_setting(program: &[i64], feedback: bool) -> Option<Setting> {
+    let circuit = Circuit::new(program);
+    if feedback {
+        circuit.with_phases(5..=9).with_wiring(Wiring::Feedback)
+    } else {
+        circuit
     }
-    phase.swap(i - 1, j);
+    .best_setting()
+}
 
-    // Reverse suffix
-    phase[i..].reverse();
-    true
+fn parse_phases(s: &str) -> Result<RangeInclusive<i64>, InputError> {
+    let (lo, hi) = s
+        .split_once('-')
+        .ok_or_else(|| InputError::spanning(s, s, "expected a range like 0-4"))?;
+    let lo = lo
+        .parse::<i64>()
+        .map_err(|e| InputError::from(e).within(s, lo))?;
+    let hi = hi
+        .parse::<i64>()
+        .map_err(|e| InputError::from(e).within(s, hi))?;
+    Ok(lo..=hi)
 }
 
-fn find_best_phase_setting(program: &[i32], feedback: bool) -> i32 {
-    let mut phase: [i32; 5] = if feedback {
-        [5, 6, 7, 8, 9]
-    } else {
-        [0, 1, 2, 3, 4]
-    };
-    let mut max = -1;
-    loop {
-        let output = run_amplifiers(&program, &phase, feedback);
-        max = std::cmp::max(max, output);
-        if !next_phase(&mut phase) {
-            break;
+// serial, feedback or connections like 0>1,1>2,2>0
+fn parse_wiring(s: &str, amplifiers: usize) -> Result<Wiring, InputError> {
+    match s {
+        "serial" => Ok(Wiring::Serial),
+        "feedback" => Ok(Wiring::Feedback),
+        _ => {
+            let mut targets = vec![Vec::new(); amplifiers];
+            for c in s.split(',') {
+                let (from, to) = c
+                    .split_once('>')
+                    .ok_or_else(|| InputError::spanning(s, c, "expected a connection like 0>1"))?;
+                let amplifier = |a: &str| {
+                    a.parse::<usize>()
+                        .ok()
+                        .filter(|&a| a < amplifiers)
+                        .ok_or_else(|| InputError::spanning(s, a, "unknown amplifier"))
+                };
+                targets[amplifier(from)?].push(amplifier(to)?);
+            }
+            Ok(Wiring::Custom(targets))
         }
     }
-    max
 }
 
-fn main() {
-    let input: Vec<Vec<i32>> = common::get_lines()
-        .into_iter()
-        .map(|l| {
-            l.split(',')
-                .map(|i| i.parse::<i32>().expect("could not parse number"))
-                .collect()
-        })
-        .collect();
+fn format_setting(setting: &Setting) -> String {
+    let phases: Vec<_> = setting.phases.iter().map(|p| p.to_string()).collect();
+    format!("{} with phase setting {}", setting.signal, phases.join(","))
+}
+
+fn main() -> Result<(), InputError> {
+    let report = common::Report::new(2019, 7)?;
+    let input: Vec<Vec<i64>> = common::get_separated_input(',')?;
     for program in input {
-        let result1 = find_best_phase_setting(&program, false);
-        println!("Part1: Highest signal is {}", result1);
+        let best1 = find_best_phase_setting(&program, false)
+            .ok_or_else(|| InputError::new("amplifiers produce no signal"))?;
+        report.part(
+            1,
+            best1.signal,
+            format!("Part1: Highest signal is {}", format_setting(&best1)),
+        );
 
-        let result2 = find_best_phase_setting(&program, true);
-        println!("Part2: Highest signal is {}", result2);
+        let best2 = find_best_phase_setting(&program, true)
+            .ok_or_else(|| InputError::new("amplifiers produce no signal"))?;
+        report.part(
+            2,
+            best2.signal,
+            format!("Part2: Highest signal is {}", format_setting(&best2)),
+        );
+
+        let amplifiers = common::option("amplifiers");
+        let phases = common::option("phases");
+        let wiring = common::option("wiring");
+        if amplifiers.is_some() || phases.is_some() || wiring.is_some() {
+            let mut circuit = Circuit::new(&program);
+            if let Some(a) = amplifiers {
+                let n = a.parse().ok().filter(|&n| n > 0).ok_or_else(|| {
+                    InputError::spanning(&a, &a, "expected a number of amplifiers")
+                })?;
+                circuit = circuit.with_amplifiers(n).with_phases(0..=n as i64 - 1);
+            }
+            if let Some(p) = phases {
+                circuit = circuit.with_phases(parse_phases(&p)?);
+            }
+            if let Some(w) = wiring {
+                let wiring = parse_wiring(&w, circuit.amplifiers)?;
+                circuit = circuit.with_wiring(wiring);
+            }
+            if report.format() == common::Format::Text {
+                match circuit.best_setting() {
+                    Some(best) => println!(
+                        "Circuit of {} amplifiers: highest signal is {}",
+                        circuit.amplifiers,
+                        format_setting(&best)
+                    ),
+                    None => println!(
+                        "Circuit of {} amplifiers produces no signal",
+                        circuit.amplifiers
+                    ),
+                }
+            }
+        }
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -293,10 +305,11 @@ mod tests {
             3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
         ];
         let phases = [4, 3, 2, 1, 0];
-        let result = run_amplifiers(&program, &phases, false);
-        assert_eq!(result, 43210);
-        let optimal = find_best_phase_setting(&program, false);
-        assert_eq!(optimal, 43210);
+        let result = Circuit::new(&program).run(&phases);
+        assert_eq!(result, Some(43210));
+        let optimal = find_best_phase_setting(&program, false).unwrap();
+        assert_eq!(optimal.signal, 43210);
+        assert_eq!(optimal.phases, phases);
     }
 
     #[test]
@@ -306,10 +319,11 @@ mod tests {
             99, 0, 0,
         ];
         let phases = [0, 1, 2, 3, 4];
-        let result = run_amplifiers(&program, &phases, false);
-        assert_eq!(result, 54321);
-        let optimal = find_best_phase_setting(&program, false);
-        assert_eq!(optimal, 54321);
+        let result = Circuit::new(&program).run(&phases);
+        assert_eq!(result, Some(54321));
+        let optimal = find_best_phase_setting(&program, false).unwrap();
+        assert_eq!(optimal.signal, 54321);
+        assert_eq!(optimal.phases, phases);
     }
 
     #[test]
@@ -319,10 +333,11 @@ mod tests {
             33, 31, 31, 1, 32, 31, 31, 4, 31, 99, 0, 0, 0,
         ];
         let phases = [1, 0, 4, 3, 2];
-        let result = run_amplifiers(&program, &phases, false);
-        assert_eq!(result, 65210);
-        let optimal = find_best_phase_setting(&program, false);
-        assert_eq!(optimal, 65210);
+        let result = Circuit::new(&program).run(&phases);
+        assert_eq!(result, Some(65210));
+        let optimal = find_best_phase_setting(&program, false).unwrap();
+        assert_eq!(optimal.signal, 65210);
+        assert_eq!(optimal.phases, phases);
     }
 
     #[test]
@@ -332,10 +347,13 @@ mod tests {
             28, 1005, 28, 6, 99, 0, 0, 5,
         ];
         let phases = [9, 8, 7, 6, 5];
-        let result = run_amplifiers(&program, &phases, true);
-        assert_eq!(result, 139_629_729);
-        let optimal = find_best_phase_setting(&program, true);
-        assert_eq!(optimal, 139_629_729);
+        let result = Circuit::new(&program)
+            .with_wiring(Wiring::Feedback)
+            .run(&phases);
+        assert_eq!(result, Some(139_629_729));
+        let optimal = find_best_phase_setting(&program, true).unwrap();
+        assert_eq!(optimal.signal, 139_629_729);
+        assert_eq!(optimal.phases, phases);
     }
 
     #[test]
@@ -346,9 +364,70 @@ mod tests {
             53, 1001, 56, -1, 56, 1005, 56, 6, 99, 0, 0, 0, 0, 10,
         ];
         let phases = [9, 7, 8, 5, 6];
-        let result = run_amplifiers(&program, &phases, true);
-        assert_eq!(result, 18216);
-        let optimal = find_best_phase_setting(&program, true);
-        assert_eq!(optimal, 18216);
+        let result = Circuit::new(&program)
+            .with_wiring(Wiring::Feedback)
+            .run(&phases);
+        assert_eq!(result, Some(18216));
+        let optimal = find_best_phase_setting(&program, true).unwrap();
+        assert_eq!(optimal.signal, 18216);
+        assert_eq!(optimal.phases, phases);
+    }
+
+    #[test]
+    fn test_circuits() {
+        // adds the phase to the signal
+        let program = [3, 11, 3, 12, 1, 11, 12, 12, 4, 12, 99, 0, 0];
+        let circuit = Circuit::new(&program).with_amplifiers(3);
+        assert_eq!(circuit.run(&[1, 2, 3]), Some(6));
+        let best = circuit.with_phases(0..=9).best_setting().unwrap();
+        assert_eq!(best.phases, [7, 8, 9]);
+        assert_eq!(best.signal, 24);
+
+        // the first amplifier feeds both others, so the last one only
+        // reads the signal of the first one
+        let wiring = parse_wiring("0>1,0>2,1>2", 3).unwrap();
+        let circuit = Circuit::new(&program)
+            .with_amplifiers(3)
+            .with_phases(parse_phases("0-4").unwrap())
+            .with_wiring(wiring);
+        assert_eq!(circuit.run(&[1, 2, 3]), Some(4));
+        let best = circuit.best_setting().unwrap();
+        assert_eq!(best.phases, [3, 0, 4]);
+        assert_eq!(best.signal, 7);
+        assert_eq!(circuit.setting_count(), 5 * 4 * 3);
+        let settings: Vec<_> = (0..60).map(|i| circuit.nth_setting(i)).collect();
+        assert!(settings.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(settings[0], [0, 1, 2]);
+        assert_eq!(settings[59], [4, 3, 2]);
+
+        // far too many settings to list them all
+        let circuit = Circuit::new(&program)
+            .with_amplifiers(30)
+            .with_phases(0..=29);
+        assert_eq!(circuit.setting_count(), u64::MAX);
+        let mut expected: Vec<i64> = (0..30).collect();
+        assert_eq!(circuit.nth_setting(0), expected);
+        expected.swap(28, 29);
+        assert_eq!(circuit.nth_setting(1), expected);
+
+        // nothing reaches the last amplifier
+        let circuit = Circuit::new(&program)
+            .with_amplifiers(2)
+            .with_wiring(Wiring::Custom(vec![vec![], vec![]]));
+        assert_eq!(circuit.best_setting(), None);
+
+        // faults with a phase of 5 or more, on the unknown opcode 0
+        let program = [
+            3, 20, 3, 21, 1, 20, 21, 21, 7, 20, 22, 23, 1005, 23, 16, 0, 4, 21, 99, 0, 0, 0, 5, 0,
+        ];
+        let circuit = Circuit::new(&program).with_amplifiers(3).with_phases(0..=9);
+        assert_eq!(circuit.run(&[1, 2, 3]), Some(6));
+        assert_eq!(circuit.run(&[1, 2, 5]), None);
+        let best = circuit.best_setting().unwrap();
+        assert_eq!(best.phases, [2, 3, 4]);
+        assert_eq!(best.signal, 9);
+
+        assert!(parse_wiring("0>3", 3).is_err());
+        assert!(parse_phases("4").is_err());
     }
 }
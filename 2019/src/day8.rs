@@ -1,30 +1,33 @@
 mod common;
 use ansi_term::Colour::{Black, Red, White};
-use generic_array::typenum::{U25, U6};
-use generic_array::{ArrayLength, GenericArray};
+use common::geometry::Point2D;
+use common::grid::Grid;
+use common::image::{Palette, Style, BLACK, WHITE};
+use common::ocr::{self, OcrError};
+use common::InputError;
 use std::fmt;
-use std::str::FromStr;
+
+/// Aspect ratio of the images of the puzzle, used to infer their size.
+const ASPECT_RATIO: (usize, usize) = (25, 6);
 
 #[derive(Debug)]
-struct Layer<Width: ArrayLength<u8>, Height: ArrayLength<GenericArray<u8, Width>>> {
-    pixels: GenericArray<GenericArray<u8, Width>, Height>,
+struct Layer {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
 }
 
 #[derive(Debug)]
-struct Image<Width: ArrayLength<u8>, Height: ArrayLength<GenericArray<u8, Width>>> {
-    layers: Vec<Layer<Width, Height>>,
+struct Image {
+    width: usize,
+    height: usize,
+    layers: Vec<Layer>,
 }
 
-impl<Width: ArrayLength<u8>, Height: ArrayLength<GenericArray<u8, Width>>> fmt::Display
-    for Layer<Width, Height>
-{
+impl fmt::Display for Layer {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        let width = Width::to_usize();
-        let height = Height::to_usize();
-        for y in 0..height {
-            let row = &self.pixels[y];
-            for x in 0..width {
-                let p = row[x];
+        for row in self.rows() {
+            for (x, &p) in row.iter().enumerate() {
                 let out = match p {
                     0 => White.on(Black).paint(" "),
                     1 => Black.on(White).paint(" "),
@@ -38,122 +41,254 @@ impl<Width: ArrayLength<u8>, Height: ArrayLength<GenericArray<u8, Width>>> fmt::
     }
 }
 
-impl<Width: ArrayLength<u8>, Height: ArrayLength<GenericArray<u8, Width>>> FromStr
-    for Layer<Width, Height>
-{
-    type Err = &'static str;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let width = Width::to_usize();
-        let height = Height::to_usize();
+impl Layer {
+    fn parse(s: &str, width: usize, height: usize) -> Result<Layer, InputError> {
         if s.len() != width * height {
-            Err("size of string is not matching width and height")
+            Err(InputError::spanning(
+                s,
+                s,
+                "size of string is not matching width and height",
+            ))
+        } else if let Some(i) = s.bytes().position(|c| !c.is_ascii_digit()) {
+            Err(InputError::at(s, i, 1, "could not parse pixel"))
         } else {
-            let mut v = Vec::with_capacity(height);
-            for row in s.as_bytes().chunks(width) {
-                let p: GenericArray<u8, Width> =
-                    GenericArray::from_exact_iter(row.iter().map(|c| c - b'0')).unwrap();
-                v.push(p);
-            }
             Ok(Layer {
-                pixels: GenericArray::clone_from_slice(v.as_slice()),
+                width,
+                height,
+                pixels: s.bytes().map(|c| c - b'0').collect(),
             })
         }
     }
-}
 
-impl<Width: ArrayLength<u8>, Height: ArrayLength<GenericArray<u8, Width>>> Layer<Width, Height> {
-    fn transparent() -> Layer<Width, Height> {
-        let twos = vec![vec![2; Width::to_usize()]; Height::to_usize()];
+    fn transparent(width: usize, height: usize) -> Layer {
         Layer {
-            pixels: GenericArray::from_exact_iter(
-                twos.iter()
-                    .map(|r| GenericArray::clone_from_slice(r.as_slice())),
-            )
-            .unwrap(),
+            width,
+            height,
+            pixels: vec![2; width * height],
+        }
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        self.pixels.chunks(self.width.max(1))
+    }
+
+    // plain text rendering with '#' for white and '.' for other pixels
+    fn to_grid(&self) -> String {
+        let mut s = String::new();
+        for row in self.rows() {
+            s.extend(row.iter().map(|&p| if p == 1 { '#' } else { '.' }));
+            s.push('\n');
         }
+        s
+    }
+
+    fn to_cells(&self) -> Grid<u8> {
+        let mut grid = Grid::new(self.width, self.height, 2);
+        for (y, row) in self.rows().enumerate() {
+            for (x, &p) in row.iter().enumerate() {
+                grid[Point2D::new(x as i64, y as i64)] = p;
+            }
+        }
+        grid
+    }
+
+    // letters formed by the white pixels
+    fn read_letters(&self) -> Result<String, OcrError> {
+        ocr::read(&self.to_cells(), |&p| p == 1)
     }
 
-    fn count_digits(&self, d: u8) -> usize {
-        self.pixels
-            .iter()
-            .flat_map(|r| r.iter())
-            .filter(|&&p| p == d)
-            .count()
+    /// Number of pixels of each digit.
+    fn digit_counts(&self) -> [usize; 10] {
+        let mut counts = [0; 10];
+        for &p in &self.pixels {
+            counts[p as usize] += 1;
+        }
+        counts
     }
 
-    fn add(&mut self, other: &Layer<Width, Height>) {
-        let width = Width::to_usize();
-        let height = Height::to_usize();
-        for y in 0..height {
-            for x in 0..width {
-                let p = self.pixels[y][x];
-                let q = other.pixels[y][x];
-                let r = if p == 2 { q } else { p };
-                self.pixels[y][x] = r;
+    // transparent pixels of this layer show the pixels of the layer below
+    fn add(&mut self, other: &Layer) {
+        for (p, &q) in self.pixels.iter_mut().zip(&other.pixels) {
+            if *p == 2 {
+                *p = q;
             }
         }
     }
 }
 
-impl<Width: ArrayLength<u8>, Height: ArrayLength<GenericArray<u8, Width>>> FromStr
-    for Image<Width, Height>
-{
-    type Err = &'static str;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let width = Width::to_usize();
-        let height = Height::to_usize();
-        let layers: Vec<_> = s
-            .as_bytes()
-            .chunks(width * height)
-            .map(|l| {
-                std::str::from_utf8(l)
-                    .unwrap()
-                    .parse::<Layer<Width, Height>>()
-                    .expect("could not parse layer")
-            })
-            .collect();
-        Ok(Image { layers })
+/// Width and height of an image with `len` pixels in total. A missing
+/// dimension follows from the other one by the aspect ratio of the puzzle,
+/// and if both are missing the smallest size with that aspect ratio whose
+/// layers fit the input exactly is used.
+fn image_size(
+    len: usize,
+    width: Option<usize>,
+    height: Option<usize>,
+) -> Result<(usize, usize), String> {
+    let (w, h) = ASPECT_RATIO;
+    let size = match (width, height) {
+        (Some(width), Some(height)) => (width, height),
+        (Some(width), None) if (width * h).is_multiple_of(w) => (width, width * h / w),
+        (None, Some(height)) if (height * w).is_multiple_of(h) => (height * w / h, height),
+        (Some(width), None) => {
+            return Err(format!(
+                "width {} has no height with aspect ratio {}:{}",
+                width, w, h
+            ))
+        }
+        (None, Some(height)) => {
+            return Err(format!(
+                "height {} has no width with aspect ratio {}:{}",
+                height, w, h
+            ))
+        }
+        (None, None) => (1..)
+            .map(|k| (k * w, k * h))
+            .take_while(|&(width, height)| width * height <= len)
+            .find(|&(width, height)| len.is_multiple_of(width * height))
+            .ok_or_else(|| {
+                format!(
+                    "{} pixels do not form layers with aspect ratio {}:{}",
+                    len, w, h
+                )
+            })?,
+    };
+    if size.0 == 0 || size.1 == 0 {
+        Err("width and height must be positive".to_string())
+    } else {
+        Ok(size)
     }
 }
 
-impl<Width: ArrayLength<u8>, Height: ArrayLength<GenericArray<u8, Width>>> Image<Width, Height> {
+impl Image {
+    fn parse(s: &str, width: usize, height: usize) -> Result<Image, InputError> {
+        if !s.is_ascii() {
+            return Err(InputError::spanning(
+                s,
+                s,
+                "image contains non-ascii characters",
+            ));
+        }
+        let layers = (0..s.len())
+            .step_by(width * height)
+            .map(|start| {
+                let l = &s[start..std::cmp::min(start + width * height, s.len())];
+                Layer::parse(l, width, height).map_err(|e| e.within(s, l))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Image {
+            width,
+            height,
+            layers,
+        })
+    }
+
+    /// Parses an image, taking missing dimensions from its length.
+    fn parse_sized(
+        s: &str,
+        width: Option<usize>,
+        height: Option<usize>,
+    ) -> Result<Image, InputError> {
+        let (width, height) =
+            image_size(s.len(), width, height).map_err(|e| InputError::spanning(s, s, e))?;
+        Image::parse(s, width, height)
+    }
+
+    /// Digit counts of every layer.
+    fn statistics(&self) -> Vec<[usize; 10]> {
+        self.layers.iter().map(|l| l.digit_counts()).collect()
+    }
+
     fn checksum(&self) -> usize {
-        let min_zero_layer = self
-            .layers
-            .iter()
-            .map(|l| (l, l.count_digits(0)))
-            .min_by_key(|&(_, zeros)| zeros)
-            .unwrap()
-            .0;
-        let ones = min_zero_layer.count_digits(1);
-        let twos = min_zero_layer.count_digits(2);
-        ones * twos
-    }
-
-    fn decode(&self) -> Layer<Width, Height> {
-        let mut layer = Layer::transparent();
+        let counts = self
+            .statistics()
+            .into_iter()
+            .min_by_key(|counts| counts[0])
+            .unwrap();
+        counts[1] * counts[2]
+    }
+
+    fn decode(&self) -> Layer {
+        let mut layer = Layer::transparent(self.width, self.height);
         for l in &self.layers {
-            layer.add(&l);
+            layer.add(l);
         }
         layer
     }
 }
 
-fn main() {
-    let input: Vec<_> = common::get_lines()
-        .into_iter()
-        .map(|l| l.parse::<Image<U25, U6>>().expect("could not parse image"))
-        .collect();
-    
+// table of the digit counts with one row per layer
+fn format_statistics(statistics: &[[usize; 10]]) -> String {
+    let mut s = String::from("layer");
+    for d in 0..10 {
+        s.push_str(&format!(" {:>5}", d));
+    }
+    for (i, counts) in statistics.iter().enumerate() {
+        s.push_str(&format!("\n{:>5}", i));
+        for c in counts {
+            s.push_str(&format!(" {:>5}", c));
+        }
+    }
+    s
+}
+
+// positive number given by an option
+fn dimension(name: &str) -> Result<Option<usize>, InputError> {
+    common::option(name)
+        .map(|s| match s.parse() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(InputError::spanning(
+                &s,
+                &s,
+                format!("{} must be a positive number", name),
+            )),
+        })
+        .transpose()
+}
+
+fn main() -> Result<(), InputError> {
+    let report = common::Report::new(2019, 8)?;
+    let width = dimension("width")?;
+    let height = dimension("height")?;
+    let input = common::parse_content_with(|content| {
+        content
+            .lines()
+            .map(|line| {
+                Image::parse_sized(line, width, height).map_err(|e| e.within(content, line))
+            })
+            .collect::<Result<Vec<_>, _>>()
+    })?;
+
     for image in input {
         let result1 = image.checksum();
-        println!("Part1: image checksum: {}", result1);
+        report.part(1, result1, format!("Part1: image checksum: {}", result1));
+        if common::flag("stats") && report.format() == common::Format::Text {
+            println!("{}", format_statistics(&image.statistics()));
+        }
 
         let decoded = image.decode();
-        println!("Part2 decoded image:\n{}", decoded);
+        let letters = decoded
+            .read_letters()
+            .map_err(|e| eprintln!("Warning: {}", e))
+            .ok();
+        report.grid_part(
+            2,
+            &decoded.to_grid(),
+            letters.as_deref(),
+            format!(
+                "Part2 decoded image: {}\n{}",
+                letters.as_deref().unwrap_or("?"),
+                decoded
+            ),
+        );
+        if let Some(filename) = common::option("image") {
+            // black, white and transparent pixels
+            let style = Style::from_args(Palette(vec![BLACK, WHITE, [0x80, 0x80, 0x80]]))?;
+            let raster = style.render(&decoded.to_cells(), |&p| p as usize)?;
+            raster.write(&filename)?;
+        }
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -162,29 +297,86 @@ mod tests {
 
     #[test]
     fn test_image_parsing() {
-        use generic_array::typenum::{U2, U3};
         let input = "123456789012";
-        let image = input
-            .parse::<Image<U3, U2>>()
-            .expect("could not parse image");
-        assert_eq!(image.layers[0].pixels[0].as_slice(), [1, 2, 3]);
-        assert_eq!(image.layers[0].pixels[1].as_slice(), [4, 5, 6]);
-        assert_eq!(image.layers[1].pixels[0].as_slice(), [7, 8, 9]);
-        assert_eq!(image.layers[1].pixels[1].as_slice(), [0, 1, 2]);
+        let image = Image::parse(input, 3, 2).expect("could not parse image");
+        let rows = |l: &Layer| l.rows().map(|r| r.to_vec()).collect::<Vec<_>>();
+        assert_eq!(rows(&image.layers[0]), [[1, 2, 3], [4, 5, 6]]);
+        assert_eq!(rows(&image.layers[1]), [[7, 8, 9], [0, 1, 2]]);
 
         let checksum = image.checksum();
         assert_eq!(checksum, 1);
+        assert_eq!(image.statistics()[1], [1, 1, 1, 0, 0, 0, 0, 1, 1, 1]);
+
+        assert!(Image::parse("1234567", 3, 2).is_err());
+    }
+
+    #[test]
+    fn test_image_size() {
+        assert_eq!(image_size(15000, None, None), Ok((25, 6)));
+        assert_eq!(image_size(1200, None, None), Ok((25, 6)));
+        assert_eq!(image_size(600, None, None), Ok((25, 6)));
+        assert_eq!(image_size(1800, None, None), Ok((25, 6)));
+        assert_eq!(image_size(15000, Some(50), None), Ok((50, 12)));
+        assert_eq!(image_size(15000, None, Some(12)), Ok((50, 12)));
+        assert_eq!(image_size(12, Some(3), Some(2)), Ok((3, 2)));
+        assert!(image_size(15000, Some(10), None).is_err());
+        assert!(image_size(100, None, None).is_err());
+
+        let image = Image::parse_sized(&"0".repeat(600), None, Some(12)).unwrap();
+        assert_eq!((image.width, image.height, image.layers.len()), (50, 12, 1));
     }
 
     #[test]
     fn test_image_decode() {
-        use generic_array::typenum::U2;
         let input = "0222112222120000";
-        let image = input
-            .parse::<Image<U2, U2>>()
-            .expect("could not parse image");
+        let image = Image::parse(input, 2, 2).expect("could not parse image");
         let decoded = image.decode();
-        assert_eq!(decoded.pixels[0].as_slice(), [0, 1]);
-        assert_eq!(decoded.pixels[1].as_slice(), [1, 0]);
+        assert_eq!(decoded.pixels, [0, 1, 1, 0]);
+    }
+
+    #[test]
+    fn test_image_export() {
+        let image = Image::parse("0222112222120000", 2, 2).unwrap();
+        let style = Style {
+            scale: 1,
+            palette: Palette(vec![BLACK, WHITE]),
+        };
+        let raster = style
+            .render(&image.decode().to_cells(), |&p| p as usize)
+            .unwrap();
+        assert_eq!(raster.to_pgm(), b"P5\n2 2\n255\n\x00\xff\xff\x00".to_vec());
+        assert!(raster
+            .to_svg()
+            .contains("<rect x=\"1\" y=\"0\" width=\"1\" height=\"1\" fill=\"#ffffff\"/>"));
+
+        // digits beyond the palette are parsed, but can not be rendered
+        let image = Image::parse("0322112222120000", 2, 2).unwrap();
+        let error = style
+            .render(&image.decode().to_cells(), |&p| p as usize)
+            .unwrap_err();
+        assert_eq!(error.message(), "palette has no colour 3");
+    }
+
+    #[test]
+    fn test_read_letters() {
+        let pixels = "1110011110\
+                      1001010000\
+                      1001011100\
+                      1110010000\
+                      1010010000\
+                      1001011110";
+        let image = Image::parse(pixels, 10, 6).unwrap();
+        assert_eq!(image.decode().read_letters(), Ok("RE".to_string()));
+
+        let pixels = pixels.replacen('0', "1", 1);
+        let image = Image::parse(&pixels, 10, 6).unwrap();
+        match image.decode().read_letters() {
+            Err(OcrError::Unknown(text, glyphs)) => {
+                assert_eq!(text, "?E");
+                assert_eq!(glyphs[0].position, Point2D::new(0, 0));
+                assert_eq!(glyphs[0].pixels.lines().next(), Some("####"));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
     }
 }
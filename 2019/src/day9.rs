@@ -144,8 +144,8 @@ impl ProgramIO {
     fn next_output(&self) -> Option<i64> {
         self.or.recv().expect("could not receive output")
     }
-    fn output_iter(&self) -> OutputIterator {
-        OutputIterator { io: &self }
+    fn output_iter(&self) -> OutputIterator<'_> {
+        OutputIterator { io: self }
     }
     fn collect_outputs(&self) -> Vec<i64> {
         self.output_iter().collect()
@@ -168,7 +168,7 @@ impl Program {
     }
 
     fn run(&self) {
-        let mut state = ProgramState::from_program(&self);
+        let mut state = ProgramState::from_program(self);
         loop {
             match state.fetch_opcode() {
                 OpCode::Arith(op) => {
@@ -252,24 +252,31 @@ impl ProgramRunner {
     }
 }
 
-fn main() {
-    let input: Vec<Vec<i64>> = common::get_lines()
-        .into_iter()
-        .map(|l| {
-            l.split(',')
-                .map(|i| i.parse::<i64>().expect("could not parse number"))
-                .collect()
-        })
-        .collect();
+fn main() -> Result<(), common::InputError> {
+    let report = common::Report::new(2019, 9)?;
+    let input: Vec<Vec<i64>> = common::get_separated_input(',')?;
     for program in input {
         let p = ProgramRunner::new(&program);
 
         let output1 = p.run_with(&[1]);
-        println!("Part1: Program output is: {:?}", output1);
+        // the answer is the last output, preceded by test results
+        let answer1 = output1.last().map_or_else(String::new, i64::to_string);
+        report.part(
+            1,
+            answer1,
+            format!("Part1: Program output is: {:?}", output1),
+        );
 
         let output2 = p.run_with(&[2]);
-        println!("Part2: Program output is: {:?}", output2);
+        // the answer is the last output, preceded by test results
+        let answer2 = output2.last().map_or_else(String::new, i64::to_string);
+        report.part(
+            2,
+            answer2,
+            format!("Part2: Program output is: {:?}", output2),
+        );
     }
+    Ok(())
 }
 
 #[cfg(test)]
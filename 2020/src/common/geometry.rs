@@ -0,0 +1,331 @@
+#![allow(dead_code)]
+use super::InputError;
+use std::convert::TryFrom;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+/// Point or vector in the plane with integer coordinates.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Hash)]
+pub struct Point2D {
+    pub x: i64,
+    pub y: i64,
+}
+
+/// Point or vector in space with integer coordinates.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Hash)]
+pub struct Point3D {
+    pub x: i64,
+    pub y: i64,
+    pub z: i64,
+}
+
+// implements the operations common to points of all dimensions
+macro_rules! impl_point {
+    ($P:ident, $($c:ident),+) => {
+        impl $P {
+            pub const fn new($($c: i64),+) -> $P {
+                $P { $($c),+ }
+            }
+
+            pub const fn zero() -> $P {
+                $P { $($c: 0),+ }
+            }
+
+            /// Sum of the absolute values of the coordinates, i.e. the manhattan distance to zero.
+            pub fn l1_norm(self) -> i64 {
+                0 $(+ self.$c.abs())+
+            }
+
+            /// Maximum of the absolute values of the coordinates.
+            pub fn max_norm(self) -> i64 {
+                0 $(.max(self.$c.abs()))+
+            }
+
+            pub fn dot(self, other: $P) -> i64 {
+                0 $(+ self.$c * other.$c)+
+            }
+
+            pub fn signum(self) -> $P {
+                $P { $($c: self.$c.signum()),+ }
+            }
+
+            pub fn abs(self) -> $P {
+                $P { $($c: self.$c.abs()),+ }
+            }
+        }
+
+        impl Add for $P {
+            type Output = $P;
+
+            fn add(self, other: $P) -> $P {
+                $P { $($c: self.$c + other.$c),+ }
+            }
+        }
+
+        impl AddAssign for $P {
+            fn add_assign(&mut self, other: $P) {
+                $(self.$c += other.$c;)+
+            }
+        }
+
+        impl Sub for $P {
+            type Output = $P;
+
+            fn sub(self, other: $P) -> $P {
+                $P { $($c: self.$c - other.$c),+ }
+            }
+        }
+
+        impl SubAssign for $P {
+            fn sub_assign(&mut self, other: $P) {
+                $(self.$c -= other.$c;)+
+            }
+        }
+
+        impl Neg for $P {
+            type Output = $P;
+
+            fn neg(self) -> $P {
+                $P { $($c: -self.$c),+ }
+            }
+        }
+
+        impl Mul<i64> for $P {
+            type Output = $P;
+
+            fn mul(self, factor: i64) -> $P {
+                $P { $($c: self.$c * factor),+ }
+            }
+        }
+
+        impl MulAssign<i64> for $P {
+            fn mul_assign(&mut self, factor: i64) {
+                $(self.$c *= factor;)+
+            }
+        }
+
+        impl Div<i64> for $P {
+            type Output = $P;
+
+            fn div(self, divisor: i64) -> $P {
+                $P { $($c: self.$c / divisor),+ }
+            }
+        }
+
+        impl DivAssign<i64> for $P {
+            fn div_assign(&mut self, divisor: i64) {
+                $(self.$c /= divisor;)+
+            }
+        }
+
+        impl Sum for $P {
+            fn sum<I: Iterator<Item = $P>>(iter: I) -> $P {
+                iter.fold($P::zero(), |a, b| a + b)
+            }
+        }
+
+        impl fmt::Display for $P {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let coordinates = [$(self.$c),+];
+                write!(f, "(")?;
+                for (i, c) in coordinates.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", c)?;
+                }
+                write!(f, ")")
+            }
+        }
+
+        /// Parses the coordinates separated by commas, optionally enclosed in
+        /// parentheses or angle brackets and prefixed by their name, so both
+        /// `(1, -2)` and `<x=1, y=-2>` are accepted.
+        impl FromStr for $P {
+            type Err = InputError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let trimmed = s.trim();
+                let inner = trimmed
+                    .strip_prefix('(')
+                    .and_then(|t| t.strip_suffix(')'))
+                    .or_else(|| trimmed.strip_prefix('<').and_then(|t| t.strip_suffix('>')))
+                    .unwrap_or(trimmed);
+                let names = [$(stringify!($c)),+];
+                let parts: Vec<&str> = inner.split(',').collect();
+                if parts.len() != names.len() {
+                    return Err(InputError::spanning(
+                        s,
+                        trimmed,
+                        format!("expected {} coordinates", names.len()),
+                    ));
+                }
+                let values = parts
+                    .iter()
+                    .zip(names.iter())
+                    .map(|(part, name)| {
+                        let part = part.trim();
+                        let number = match part.split_once('=') {
+                            Some((prefix, number)) if prefix.trim() == *name => number.trim(),
+                            Some((prefix, _)) => {
+                                return Err(InputError::spanning(
+                                    s,
+                                    prefix,
+                                    format!("expected coordinate {}", name),
+                                ))
+                            }
+                            None => part,
+                        };
+                        number.parse::<i64>().map_err(|_| {
+                            let message = format!("could not parse {} coordinate", name);
+                            InputError::spanning(s, number, message)
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                // one value for each coordinate, as the number of parts is checked
+                let mut values = values.into_iter();
+                Ok($P { $($c: values.next().unwrap()),+ })
+            }
+        }
+    };
+}
+
+impl_point!(Point2D, x, y);
+impl_point!(Point3D, x, y, z);
+
+impl Point2D {
+    /// z-coordinate of the cross product of the vectors extended to space,
+    /// positive if `other` is turned counterclockwise from `self`.
+    pub fn cross(self, other: Point2D) -> i64 {
+        self.x * other.y - self.y * other.x
+    }
+}
+
+impl Point3D {
+    pub fn cross(self, other: Point3D) -> Point3D {
+        Point3D {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        }
+    }
+}
+
+/// Homogeneous coordinates of a point in the plane.
+impl From<Point2D> for Point3D {
+    fn from(p: Point2D) -> Point3D {
+        Point3D {
+            x: p.x,
+            y: p.y,
+            z: 1,
+        }
+    }
+}
+
+/// Point in the plane for homogeneous coordinates, if it is finite and integer.
+impl TryFrom<Point3D> for Point2D {
+    type Error = &'static str;
+
+    fn try_from(p: Point3D) -> Result<Self, Self::Error> {
+        if p.z == 0 {
+            Err("3d point is a point in infinity")
+        } else if p.x % p.z != 0 || p.y % p.z != 0 {
+            Err("3d point is a non-integer 2d point")
+        } else {
+            Ok(Point2D {
+                x: p.x / p.z,
+                y: p.y / p.z,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_display() {
+        let p: Point2D = "(1, -2)".parse().unwrap();
+        assert_eq!(p, Point2D::new(1, -2));
+        assert_eq!(p.to_string(), "(1, -2)");
+        assert_eq!(p.to_string().parse::<Point2D>().unwrap(), p);
+        assert_eq!("<x=1, y=-2>".parse::<Point2D>().unwrap(), p);
+        assert_eq!(" 1,-2 ".parse::<Point2D>().unwrap(), p);
+
+        let p: Point3D = "<x=-1, y=0, z=2>".parse().unwrap();
+        assert_eq!(p, Point3D::new(-1, 0, 2));
+        assert_eq!(p.to_string(), "(-1, 0, 2)");
+        assert_eq!(p.to_string().parse::<Point3D>().unwrap(), p);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = "(1, 2, 3)".parse::<Point2D>().unwrap_err();
+        assert_eq!(error.message(), "expected 2 coordinates");
+        let error = "1".parse::<Point2D>().unwrap_err();
+        assert_eq!(error.message(), "expected 2 coordinates");
+        let error = "(1, 2)".parse::<Point3D>().unwrap_err();
+        assert_eq!(error.message(), "expected 3 coordinates");
+        let error = "<x=1, y=2, z=3, w=4>".parse::<Point3D>().unwrap_err();
+        assert_eq!(error.message(), "expected 3 coordinates");
+        let error = "<x=1, z=2>".parse::<Point2D>().unwrap_err();
+        assert_eq!(error.message(), "expected coordinate y");
+        let error = "(1, a)".parse::<Point2D>().unwrap_err();
+        assert_eq!(error.message(), "could not parse y coordinate");
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = Point2D::new(3, -4);
+        let b = Point2D::new(-1, 2);
+        assert_eq!(a + b, Point2D::new(2, -2));
+        assert_eq!(a - b, Point2D::new(4, -6));
+        assert_eq!(-a, Point2D::new(-3, 4));
+        assert_eq!(a * 2, Point2D::new(6, -8));
+        assert_eq!(a / 2, Point2D::new(1, -2));
+        let mut c = a;
+        c += b;
+        c -= Point2D::new(1, 1);
+        c *= 3;
+        c /= 2;
+        assert_eq!(c, Point2D::new(1, -4));
+        assert_eq!(
+            vec![a, b, c].into_iter().sum::<Point2D>(),
+            Point2D::new(3, -6)
+        );
+
+        assert_eq!(a.l1_norm(), 7);
+        assert_eq!(a.max_norm(), 4);
+        assert_eq!(a.dot(b), -11);
+        assert_eq!(a.signum(), Point2D::new(1, -1));
+        assert_eq!(a.abs(), Point2D::new(3, 4));
+        assert_eq!(Point2D::new(1, 0).cross(Point2D::new(0, 1)), 1);
+        assert_eq!(Point2D::new(0, 1).cross(Point2D::new(1, 0)), -1);
+
+        let x = Point3D::new(1, 0, 0);
+        let y = Point3D::new(0, 1, 0);
+        assert_eq!(x.cross(y), Point3D::new(0, 0, 1));
+        assert_eq!(y.cross(x), Point3D::new(0, 0, -1));
+        assert_eq!(Point3D::new(1, -2, 3).l1_norm(), 6);
+        assert_eq!(Point3D::zero(), Point3D::default());
+    }
+
+    #[test]
+    fn test_conversions() {
+        let p = Point2D::new(2, -3);
+        assert_eq!(Point3D::from(p), Point3D::new(2, -3, 1));
+        assert_eq!(Point2D::try_from(Point3D::from(p)), Ok(p));
+        assert_eq!(Point2D::try_from(Point3D::new(4, -6, 2)), Ok(p));
+        assert_eq!(
+            Point2D::try_from(Point3D::new(4, -6, 0)),
+            Err("3d point is a point in infinity")
+        );
+        assert_eq!(
+            Point2D::try_from(Point3D::new(4, -5, 2)),
+            Err("3d point is a non-integer 2d point")
+        );
+    }
+}
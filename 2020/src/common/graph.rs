@@ -0,0 +1,463 @@
+#![allow(dead_code)]
+use std::collections::hash_map::{Entry, HashMap};
+use std::collections::VecDeque;
+
+pub type NodeId = usize;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Edge<W> {
+    pub target: NodeId,
+    pub weight: W,
+}
+
+#[derive(Debug, Clone)]
+struct Node<W> {
+    name: String,
+    successors: Vec<Edge<W>>,
+    predecessors: Vec<Edge<W>>,
+}
+
+/// Direction in which edges are followed during a search.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Direction {
+    Forward,
+    Backward,
+    Both,
+}
+
+// attributes of highlighted nodes and edges in DOT output
+const DOT_HIGHLIGHT: &str = "color=red, penwidth=2";
+
+// quoted DOT identifier
+fn dot_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Nodes of a cycle, in the direction of the edges.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Cycle(pub Vec<NodeId>);
+
+/// Directed graph with named nodes and weighted edges.
+///
+/// Names are interned, so nodes are referred to by their `NodeId`, which
+/// are given out consecutively starting from 0 and can index vectors.
+#[derive(Debug, Clone)]
+pub struct Graph<W = ()> {
+    ids: HashMap<String, NodeId>,
+    nodes: Vec<Node<W>>,
+}
+
+impl<W> Default for Graph<W> {
+    fn default() -> Self {
+        Graph {
+            ids: HashMap::new(),
+            nodes: Vec::new(),
+        }
+    }
+}
+
+impl<W: Clone> Graph<W> {
+    pub fn new() -> Graph<W> {
+        Graph::default()
+    }
+
+    /// Id of the node with the name, adding the node if there is none yet.
+    pub fn node(&mut self, name: &str) -> NodeId {
+        match self.ids.entry(name.to_string()) {
+            Entry::Occupied(e) => *e.get(),
+            Entry::Vacant(e) => {
+                let id = self.nodes.len();
+                self.nodes.push(Node {
+                    name: name.to_string(),
+                    successors: Vec::new(),
+                    predecessors: Vec::new(),
+                });
+                e.insert(id);
+                id
+            }
+        }
+    }
+
+    pub fn id(&self, name: &str) -> Option<NodeId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: NodeId) -> &str {
+        &self.nodes[id].name
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn ids(&self) -> std::ops::Range<NodeId> {
+        0..self.nodes.len()
+    }
+
+    pub fn add_edge(&mut self, source: NodeId, target: NodeId, weight: W) {
+        self.nodes[source].successors.push(Edge {
+            target,
+            weight: weight.clone(),
+        });
+        self.nodes[target].predecessors.push(Edge {
+            target: source,
+            weight,
+        });
+    }
+
+    /// Adds an edge between the named nodes, adding the nodes if necessary.
+    pub fn add_named_edge(&mut self, source: &str, target: &str, weight: W) {
+        let source = self.node(source);
+        let target = self.node(target);
+        self.add_edge(source, target, weight);
+    }
+
+    pub fn successors(&self, id: NodeId) -> &[Edge<W>] {
+        &self.nodes[id].successors
+    }
+
+    /// Incoming edges, with `target` being the source of the edge.
+    pub fn predecessors(&self, id: NodeId) -> &[Edge<W>] {
+        &self.nodes[id].predecessors
+    }
+
+    /// All edges as triples of source, target and weight.
+    pub fn edges(&self) -> impl Iterator<Item = (NodeId, NodeId, &W)> {
+        self.nodes.iter().enumerate().flat_map(|(source, node)| {
+            node.successors
+                .iter()
+                .map(move |e| (source, e.target, &e.weight))
+        })
+    }
+
+    pub fn neighbours(
+        &self,
+        id: NodeId,
+        direction: Direction,
+    ) -> impl Iterator<Item = NodeId> + '_ {
+        let node = &self.nodes[id];
+        let successors = match direction {
+            Direction::Forward | Direction::Both => &node.successors[..],
+            Direction::Backward => &[],
+        };
+        let predecessors = match direction {
+            Direction::Backward | Direction::Both => &node.predecessors[..],
+            Direction::Forward => &[],
+        };
+        successors.iter().chain(predecessors).map(|e| e.target)
+    }
+
+    // breadth-first search returning the distance and parent of reached nodes
+    fn search(&self, source: NodeId, direction: Direction) -> Vec<Option<(usize, NodeId)>> {
+        let mut reached = vec![None; self.len()];
+        let mut queue = VecDeque::new();
+        reached[source] = Some((0, source));
+        queue.push_back(source);
+        while let Some(u) = queue.pop_front() {
+            let (dist, _) = reached[u].unwrap();
+            for v in self.neighbours(u, direction) {
+                if reached[v].is_none() {
+                    reached[v] = Some((dist + 1, u));
+                    queue.push_back(v);
+                }
+            }
+        }
+        reached
+    }
+
+    /// Number of edges on a shortest path from the source to each node,
+    /// `None` for nodes that can not be reached.
+    pub fn bfs(&self, source: NodeId, direction: Direction) -> Vec<Option<usize>> {
+        self.search(source, direction)
+            .into_iter()
+            .map(|r| r.map(|(dist, _)| dist))
+            .collect()
+    }
+
+    /// Nodes on a shortest path from source to target, including both.
+    pub fn shortest_path(
+        &self,
+        source: NodeId,
+        target: NodeId,
+        direction: Direction,
+    ) -> Option<Vec<NodeId>> {
+        let reached = self.search(source, direction);
+        reached[target]?;
+        let mut path = vec![target];
+        let mut current = target;
+        while current != source {
+            current = reached[current].unwrap().1;
+            path.push(current);
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Nodes reachable from the source, including itself, in depth-first preorder.
+    pub fn dfs(&self, source: NodeId, direction: Direction) -> Vec<NodeId> {
+        let mut visited = vec![false; self.len()];
+        let mut order = Vec::new();
+        let mut stack = vec![source];
+        while let Some(u) = stack.pop() {
+            if visited[u] {
+                continue;
+            }
+            visited[u] = true;
+            order.push(u);
+            let neighbours: Vec<_> = self.neighbours(u, direction).collect();
+            stack.extend(neighbours.into_iter().rev().filter(|&v| !visited[v]));
+        }
+        order
+    }
+
+    /// Which nodes are reachable from the source, including itself.
+    pub fn reachable(&self, source: NodeId, direction: Direction) -> Vec<bool> {
+        self.search(source, direction)
+            .iter()
+            .map(|r| r.is_some())
+            .collect()
+    }
+
+    /// Order of all nodes in which every edge goes from an earlier to a later
+    /// node, or a cycle if there is no such order.
+    pub fn topological_order(&self) -> Result<Vec<NodeId>, Cycle> {
+        let mut incoming: Vec<usize> = self.nodes.iter().map(|n| n.predecessors.len()).collect();
+        let mut queue: VecDeque<NodeId> = self.ids().filter(|&u| incoming[u] == 0).collect();
+        let mut order = Vec::with_capacity(self.len());
+        while let Some(u) = queue.pop_front() {
+            for e in self.successors(u) {
+                incoming[e.target] -= 1;
+                if incoming[e.target] == 0 {
+                    queue.push_back(e.target);
+                }
+            }
+            order.push(u);
+        }
+        if order.len() == self.len() {
+            return Ok(order);
+        }
+        // every remaining node has a remaining predecessor, so walking
+        // backwards from any of them has to run into a cycle
+        let mut position = vec![None; self.len()];
+        let mut walk = Vec::new();
+        let mut u = self.ids().find(|&u| incoming[u] > 0).unwrap();
+        while position[u].is_none() {
+            position[u] = Some(walk.len());
+            walk.push(u);
+            u = self
+                .predecessors(u)
+                .iter()
+                .map(|e| e.target)
+                .find(|&v| incoming[v] > 0)
+                .unwrap();
+        }
+        let mut cycle = walk.split_off(position[u].unwrap());
+        cycle.reverse();
+        Err(Cycle(cycle))
+    }
+
+    /// Computes a value for the source from the values of its successors,
+    /// which are computed the same way, visiting every node only once.
+    /// `f` gets the node and the weight and value of each outgoing edge.
+    pub fn fold_successors<T, F>(&self, source: NodeId, mut f: F) -> Result<T, Cycle>
+    where
+        T: Clone,
+        F: FnMut(NodeId, &[(&W, T)]) -> T,
+    {
+        let mut values: Vec<Option<T>> = vec![None; self.len()];
+        let mut on_stack = vec![false; self.len()];
+        // nodes being visited with the index of their next edge
+        let mut stack = vec![(source, 0)];
+        on_stack[source] = true;
+        while let Some(&(u, i)) = stack.last() {
+            if let Some(e) = self.successors(u).get(i) {
+                stack.last_mut().unwrap().1 += 1;
+                let v = e.target;
+                if on_stack[v] {
+                    let start = stack.iter().position(|&(w, _)| w == v).unwrap();
+                    return Err(Cycle(stack[start..].iter().map(|&(w, _)| w).collect()));
+                } else if values[v].is_none() {
+                    on_stack[v] = true;
+                    stack.push((v, 0));
+                }
+            } else {
+                let inputs: Vec<_> = self
+                    .successors(u)
+                    .iter()
+                    .map(|e| (&e.weight, values[e.target].clone().unwrap()))
+                    .collect();
+                values[u] = Some(f(u, &inputs));
+                on_stack[u] = false;
+                stack.pop();
+            }
+        }
+        Ok(values[source].take().unwrap())
+    }
+
+    /// Graphviz DOT representation of the graph, where `label` gives the
+    /// label of an edge from its weight. The highlighted nodes and the edges
+    /// between them are drawn in red.
+    pub fn to_dot<F: Fn(&W) -> Option<String>>(&self, label: F, highlight: &[NodeId]) -> String {
+        let mut highlighted = vec![false; self.len()];
+        for &u in highlight {
+            highlighted[u] = true;
+        }
+        let mut dot = String::from("digraph {\n");
+        for u in self.ids() {
+            let mut attributes = vec![format!("label={}", dot_string(self.name(u)))];
+            if highlighted[u] {
+                attributes.push(DOT_HIGHLIGHT.to_string());
+            }
+            dot.push_str(&format!("    {} [{}];\n", u, attributes.join(", ")));
+        }
+        for (u, v, w) in self.edges() {
+            let mut attributes = Vec::new();
+            if let Some(l) = label(w) {
+                attributes.push(format!("label={}", dot_string(&l)));
+            }
+            if highlighted[u] && highlighted[v] {
+                attributes.push(DOT_HIGHLIGHT.to_string());
+            }
+            if attributes.is_empty() {
+                dot.push_str(&format!("    {} -> {};\n", u, v));
+            } else {
+                dot.push_str(&format!(
+                    "    {} -> {} [{}];\n",
+                    u,
+                    v,
+                    attributes.join(", ")
+                ));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a -> b -> d and a -> c -> d, with e unconnected
+    fn diamond() -> Graph<u32> {
+        let mut graph = Graph::new();
+        graph.add_named_edge("a", "b", 1);
+        graph.add_named_edge("a", "c", 2);
+        graph.add_named_edge("b", "d", 3);
+        graph.add_named_edge("c", "d", 4);
+        graph.node("e");
+        graph
+    }
+
+    #[test]
+    fn test_bfs() {
+        let graph = diamond();
+        assert_eq!(
+            graph.bfs(0, Direction::Forward),
+            vec![Some(0), Some(1), Some(1), Some(2), None]
+        );
+        assert_eq!(
+            graph.bfs(3, Direction::Backward),
+            vec![Some(2), Some(1), Some(1), Some(0), None]
+        );
+        assert_eq!(
+            graph.bfs(1, Direction::Both),
+            vec![Some(1), Some(0), Some(2), Some(1), None]
+        );
+        assert_eq!(
+            graph.reachable(1, Direction::Forward),
+            vec![false, true, false, true, false]
+        );
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        let graph = diamond();
+        assert_eq!(
+            graph.shortest_path(0, 3, Direction::Forward),
+            Some(vec![0, 1, 3])
+        );
+        assert_eq!(graph.shortest_path(0, 0, Direction::Forward), Some(vec![0]));
+        assert_eq!(graph.shortest_path(3, 0, Direction::Forward), None);
+        assert_eq!(
+            graph.shortest_path(3, 0, Direction::Backward),
+            Some(vec![3, 1, 0])
+        );
+        assert_eq!(graph.shortest_path(0, 4, Direction::Both), None);
+    }
+
+    #[test]
+    fn test_dfs() {
+        let graph = diamond();
+        assert_eq!(graph.dfs(0, Direction::Forward), vec![0, 1, 3, 2]);
+        assert_eq!(graph.dfs(3, Direction::Backward), vec![3, 1, 0, 2]);
+        assert_eq!(graph.dfs(3, Direction::Both), vec![3, 1, 0, 2]);
+        assert_eq!(graph.dfs(4, Direction::Both), vec![4]);
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let mut graph = diamond();
+        let order = graph.topological_order().unwrap();
+        let mut position = vec![0; graph.len()];
+        for (i, &u) in order.iter().enumerate() {
+            position[u] = i;
+        }
+        assert_eq!(order.len(), graph.len());
+        assert!(graph.edges().all(|(u, v, _)| position[u] < position[v]));
+
+        graph.add_named_edge("d", "a", 5);
+        assert_eq!(graph.topological_order(), Err(Cycle(vec![1, 3, 0])));
+    }
+
+    #[test]
+    fn test_fold_successors() {
+        let mut graph = diamond();
+        let paths = graph.fold_successors(0, |_, inputs| {
+            if inputs.is_empty() {
+                1
+            } else {
+                inputs.iter().map(|&(_, paths)| paths).sum()
+            }
+        });
+        assert_eq!(paths, Ok(2));
+        let longest = graph.fold_successors(0, |_, inputs| {
+            inputs.iter().map(|&(&w, l)| w + l).max().unwrap_or(0)
+        });
+        assert_eq!(longest, Ok(6));
+        let mut visited = Vec::new();
+        graph.fold_successors(0, |u, _| visited.push(u)).unwrap();
+        assert_eq!(visited, vec![3, 1, 2, 0]);
+
+        graph.add_named_edge("d", "a", 5);
+        assert_eq!(
+            graph.fold_successors(0, |_, _| 0),
+            Err(Cycle(vec![0, 1, 3]))
+        );
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let mut graph = Graph::new();
+        graph.add_named_edge("say \"hi\"", "a\\b", 3);
+        graph.add_named_edge("a\\b", "c", 0);
+        let label = |&w: &u32| if w > 0 { Some(w.to_string()) } else { None };
+        assert_eq!(
+            graph.to_dot(label, &[0, 1]),
+            concat!(
+                "digraph {\n",
+                "    0 [label=\"say \\\"hi\\\"\", color=red, penwidth=2];\n",
+                "    1 [label=\"a\\\\b\", color=red, penwidth=2];\n",
+                "    2 [label=\"c\"];\n",
+                "    0 -> 1 [label=\"3\", color=red, penwidth=2];\n",
+                "    1 -> 2;\n",
+                "}\n"
+            )
+        );
+        assert_eq!(graph.to_dot(|_| None, &[]).matches("red").count(), 0);
+    }
+}
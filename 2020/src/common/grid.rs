@@ -0,0 +1,388 @@
+#![allow(dead_code)]
+use super::geometry::Point2D;
+use super::InputError;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::ops::{Index, IndexMut};
+
+/// Offsets to the four orthogonal neighbours, clockwise from up with y pointing down.
+pub const NEIGHBOURS4: [Point2D; 4] = [
+    Point2D::new(0, -1),
+    Point2D::new(1, 0),
+    Point2D::new(0, 1),
+    Point2D::new(-1, 0),
+];
+
+/// Offsets to the eight orthogonal and diagonal neighbours, clockwise from up.
+pub const NEIGHBOURS8: [Point2D; 8] = [
+    Point2D::new(0, -1),
+    Point2D::new(1, -1),
+    Point2D::new(1, 0),
+    Point2D::new(1, 1),
+    Point2D::new(0, 1),
+    Point2D::new(-1, 1),
+    Point2D::new(-1, 0),
+    Point2D::new(-1, -1),
+];
+
+// renders the rectangle from min to max (inclusive) row by row
+fn render_rect<G: Display, F: Fn(Point2D) -> G>(min: Point2D, max: Point2D, glyph: F) -> String {
+    let mut s = String::new();
+    for y in min.y..=max.y {
+        for x in min.x..=max.x {
+            s.push_str(&glyph(Point2D { x, y }).to_string());
+        }
+        s.push('\n');
+    }
+    s
+}
+
+/// Dense grid with fixed width and height, with (0, 0) in the top left corner.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Grid<T> {
+        Grid {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    /// Parses a map with one character per cell and one line per row,
+    /// where `cell` returns `None` for characters that are not allowed.
+    pub fn parse<F: Fn(char) -> Option<T>>(s: &str, cell: F) -> Result<Grid<T>, InputError> {
+        let mut cells = Vec::with_capacity(s.len());
+        let mut width = None;
+        let mut height = 0;
+        for line in s.lines() {
+            let mut row_width = 0;
+            for (i, c) in line.char_indices() {
+                let t = cell(c).ok_or_else(|| {
+                    InputError::at(line, i, c.len_utf8(), format!("unknown cell '{}'", c))
+                        .within(s, line)
+                })?;
+                cells.push(t);
+                row_width += 1;
+            }
+            match width {
+                None if row_width == 0 => {
+                    return Err(InputError::spanning(s, line, "first row empty"))
+                }
+                None => width = Some(row_width),
+                Some(w) if w != row_width => {
+                    return Err(InputError::spanning(
+                        s,
+                        line,
+                        format!("row has width {} instead of {}", row_width, w),
+                    ))
+                }
+                Some(_) => (),
+            }
+            height += 1;
+        }
+        let width = width.ok_or_else(|| InputError::new("no rows"))?;
+        Ok(Grid {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, p: Point2D) -> bool {
+        p.x >= 0 && p.y >= 0 && (p.x as usize) < self.width && (p.y as usize) < self.height
+    }
+
+    fn index_of(&self, p: Point2D) -> Option<usize> {
+        if self.contains(p) {
+            Some(p.y as usize * self.width + p.x as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn get(&self, p: Point2D) -> Option<&T> {
+        self.index_of(p).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, p: Point2D) -> Option<&mut T> {
+        self.index_of(p).map(move |i| &mut self.cells[i])
+    }
+
+    /// Sets the cell, panicking if it is outside of the grid.
+    pub fn set(&mut self, p: Point2D, value: T) {
+        self[p] = value;
+    }
+
+    /// Moves the point into the grid as if the grid was tiled infinitely.
+    ///
+    /// Panics if the grid is empty, as there is no cell to wrap to.
+    pub fn wrap(&self, p: Point2D) -> Point2D {
+        assert!(
+            !self.cells.is_empty(),
+            "cannot wrap {} into an empty grid",
+            p
+        );
+        Point2D {
+            x: p.x.rem_euclid(self.width as i64),
+            y: p.y.rem_euclid(self.height as i64),
+        }
+    }
+
+    /// Cell at the point, with the grid repeating in all directions.
+    pub fn get_wrapping(&self, p: Point2D) -> &T {
+        &self[self.wrap(p)]
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Point2D> {
+        let (width, height) = (self.width as i64, self.height as i64);
+        (0..height).flat_map(move |y| (0..width).map(move |x| Point2D { x, y }))
+    }
+
+    /// Iterates over all cells with their position, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Point2D, &T)> {
+        self.points().zip(self.cells.iter())
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// Position of the first cell in row order matching the predicate.
+    pub fn position<F: Fn(&T) -> bool>(&self, predicate: F) -> Option<Point2D> {
+        self.iter().find(|(_, t)| predicate(t)).map(|(p, _)| p)
+    }
+
+    /// Orthogonal neighbours of the point that are inside of the grid.
+    pub fn neighbours4(&self, p: Point2D) -> impl Iterator<Item = Point2D> + '_ {
+        NEIGHBOURS4
+            .iter()
+            .map(move |&d| p + d)
+            .filter(move |&q| self.contains(q))
+    }
+
+    /// Orthogonal and diagonal neighbours of the point that are inside of the grid.
+    pub fn neighbours8(&self, p: Point2D) -> impl Iterator<Item = Point2D> + '_ {
+        NEIGHBOURS8
+            .iter()
+            .map(move |&d| p + d)
+            .filter(move |&q| self.contains(q))
+    }
+
+    /// Renders the grid row by row with the glyph of each cell.
+    pub fn render<G: Display, F: Fn(&T) -> G>(&self, glyph: F) -> String {
+        if self.cells.is_empty() {
+            return String::new();
+        }
+        let max = Point2D {
+            x: self.width as i64 - 1,
+            y: self.height as i64 - 1,
+        };
+        render_rect(Point2D::zero(), max, |p| glyph(&self[p]))
+    }
+}
+
+impl<T> Index<Point2D> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, p: Point2D) -> &T {
+        self.get(p)
+            .unwrap_or_else(|| panic!("{} is outside of the grid", p))
+    }
+}
+
+impl<T> IndexMut<Point2D> for Grid<T> {
+    fn index_mut(&mut self, p: Point2D) -> &mut T {
+        self.get_mut(p)
+            .unwrap_or_else(|| panic!("{} is outside of the grid", p))
+    }
+}
+
+/// Sparse grid that grows with the cells that are set, which may have
+/// negative coordinates. Cells that were never set have the default value.
+#[derive(Debug, Clone)]
+pub struct SparseGrid<T> {
+    cells: HashMap<Point2D, T>,
+    default: T,
+    bounds: Option<(Point2D, Point2D)>,
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new(default: T) -> SparseGrid<T> {
+        SparseGrid {
+            cells: HashMap::new(),
+            default,
+            bounds: None,
+        }
+    }
+
+    pub fn get(&self, p: Point2D) -> &T {
+        self.cells.get(&p).unwrap_or(&self.default)
+    }
+
+    pub fn set(&mut self, p: Point2D, value: T) {
+        self.bounds = Some(match self.bounds {
+            None => (p, p),
+            Some((min, max)) => (
+                Point2D::new(min.x.min(p.x), min.y.min(p.y)),
+                Point2D::new(max.x.max(p.x), max.y.max(p.y)),
+            ),
+        });
+        self.cells.insert(p, value);
+    }
+
+    pub fn is_set(&self, p: Point2D) -> bool {
+        self.cells.contains_key(&p)
+    }
+
+    /// Number of cells that have been set.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Smallest and largest coordinates of the cells that have been set.
+    pub fn bounds(&self) -> Option<(Point2D, Point2D)> {
+        self.bounds
+    }
+
+    /// Iterates over the cells that have been set, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (Point2D, &T)> {
+        self.cells.iter().map(|(&p, t)| (p, t))
+    }
+
+    pub fn neighbours4(p: Point2D) -> impl Iterator<Item = Point2D> {
+        NEIGHBOURS4.iter().map(move |&d| p + d)
+    }
+
+    pub fn neighbours8(p: Point2D) -> impl Iterator<Item = Point2D> {
+        NEIGHBOURS8.iter().map(move |&d| p + d)
+    }
+
+    /// Renders the bounding box of the cells that have been set row by row.
+    pub fn render<G: Display, F: Fn(&T) -> G>(&self, glyph: F) -> String {
+        match self.bounds {
+            None => String::new(),
+            Some((min, max)) => render_rect(min, max, |p| glyph(self.get(p))),
+        }
+    }
+}
+
+impl<T: Clone> SparseGrid<T> {
+    /// Dense grid of the bounding box, with the smallest coordinates moved to (0, 0).
+    pub fn to_dense(&self) -> Grid<T> {
+        match self.bounds {
+            None => Grid::new(0, 0, self.default.clone()),
+            Some((min, max)) => {
+                let size = max - min + Point2D::new(1, 1);
+                let mut grid = Grid::new(size.x as usize, size.y as usize, self.default.clone());
+                for (&p, t) in &self.cells {
+                    grid[p - min] = t.clone();
+                }
+                grid
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(points: impl Iterator<Item = Point2D>) -> Vec<Point2D> {
+        let mut points: Vec<_> = points.collect();
+        points.sort();
+        points
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = Grid::new(3, 2, 0);
+        assert_eq!(
+            sorted(grid.neighbours4(Point2D::new(0, 0))),
+            vec![Point2D::new(0, 1), Point2D::new(1, 0)]
+        );
+        assert_eq!(grid.neighbours4(Point2D::new(1, 1)).count(), 3);
+        assert_eq!(
+            sorted(grid.neighbours8(Point2D::new(0, 0))),
+            vec![Point2D::new(0, 1), Point2D::new(1, 0), Point2D::new(1, 1)]
+        );
+        assert_eq!(grid.neighbours8(Point2D::new(1, 0)).count(), 5);
+        assert_eq!(grid.neighbours4(Point2D::new(-1, 0)).count(), 1);
+
+        assert_eq!(
+            SparseGrid::<u8>::neighbours4(Point2D::new(0, 0)).collect::<Vec<_>>(),
+            NEIGHBOURS4.to_vec()
+        );
+        assert_eq!(
+            SparseGrid::<u8>::neighbours8(Point2D::new(-5, -5)).count(),
+            8
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        let grid = Grid::parse("ab\ncd\nef\n", Some).unwrap();
+        assert_eq!(grid.wrap(Point2D::new(1, 2)), Point2D::new(1, 2));
+        assert_eq!(grid.wrap(Point2D::new(5, 7)), Point2D::new(1, 1));
+        assert_eq!(grid.wrap(Point2D::new(-1, -1)), Point2D::new(1, 2));
+        assert_eq!(grid.wrap(Point2D::new(-4, -6)), Point2D::new(0, 0));
+        assert_eq!(*grid.get_wrapping(Point2D::new(2, -2)), 'c');
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot wrap (1, 2) into an empty grid")]
+    fn test_wrap_empty() {
+        Grid::new(0, 0, 0).wrap(Point2D::new(1, 2));
+    }
+
+    #[test]
+    fn test_sparse() {
+        let mut grid = SparseGrid::new('.');
+        assert!(grid.is_empty());
+        assert_eq!(grid.bounds(), None);
+        assert_eq!(grid.render(|&c| c), "");
+        assert_eq!(grid.to_dense(), Grid::new(0, 0, '.'));
+
+        grid.set(Point2D::new(-2, 1), 'a');
+        grid.set(Point2D::new(1, -1), 'b');
+        grid.set(Point2D::new(0, 0), 'c');
+        assert_eq!(grid.len(), 3);
+        assert!(grid.is_set(Point2D::new(-2, 1)));
+        assert!(!grid.is_set(Point2D::new(-1, 1)));
+        assert_eq!(*grid.get(Point2D::new(-1, 1)), '.');
+        assert_eq!(
+            grid.bounds(),
+            Some((Point2D::new(-2, -1), Point2D::new(1, 1)))
+        );
+        assert_eq!(grid.render(|&c| c), "...b\n..c.\na...\n");
+
+        let dense = grid.to_dense();
+        assert_eq!((dense.width(), dense.height()), (4, 3));
+        assert_eq!(dense[Point2D::new(0, 2)], 'a');
+        assert_eq!(dense[Point2D::new(3, 0)], 'b');
+        assert_eq!(dense.render(|&c| c), grid.render(|&c| c));
+    }
+}
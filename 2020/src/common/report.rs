@@ -0,0 +1,114 @@
+#![allow(dead_code)]
+use super::InputError;
+use std::cell::Cell;
+use std::fmt::Display;
+use std::fs;
+use std::time::Instant;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+/// Prints the answers of a solution, either as the free-form text of the
+/// solution or, with `--format json`, as one JSON object per line.
+///
+/// The runtime of a part is the time since the previous part was reported,
+/// or since the report was created for the first part.
+pub struct Report {
+    year: u32,
+    day: u32,
+    format: Format,
+    input_hash: String,
+    lap: Cell<Instant>,
+}
+
+// 64-bit FNV-1a, stable across platforms and compiler versions
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+pub fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl Report {
+    pub fn new(year: u32, day: u32) -> Result<Report, InputError> {
+        let format = match super::option("format").as_deref() {
+            None | Some("text") => Format::Text,
+            Some("json") => Format::Json,
+            Some(other) => panic!("unknown output format: {}", other),
+        };
+        let filename = super::get_filename();
+        let content = fs::read(&filename).map_err(|err| {
+            InputError::new(format!("could not read file: {}", err)).in_file(&filename)
+        })?;
+        Ok(Report {
+            year,
+            day,
+            format,
+            input_hash: format!("{:016x}", fnv1a(&content)),
+            lap: Cell::new(Instant::now()),
+        })
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    fn emit(&self, part: u32, answer: &str, extra: &[(&str, String)], text: &dyn Display) {
+        let now = Instant::now();
+        let runtime = now.duration_since(self.lap.replace(now));
+        match self.format {
+            Format::Text => println!("{}", text),
+            Format::Json => {
+                let mut fields = vec![
+                    ("year", self.year.to_string()),
+                    ("day", self.day.to_string()),
+                    ("part", part.to_string()),
+                    ("answer", json_string(answer)),
+                ];
+                fields.extend(extra.iter().cloned());
+                fields.push(("runtime_ms", format!("{:.3}", runtime.as_secs_f64() * 1e3)));
+                fields.push(("input_hash", json_string(&self.input_hash)));
+                let body: Vec<_> = fields
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", json_string(k), v))
+                    .collect();
+                println!("{{{}}}", body.join(", "));
+            }
+        }
+    }
+
+    /// Reports the answer of a part, printing `text` in text format.
+    pub fn part<A: Display, T: Display>(&self, part: u32, answer: A, text: T) {
+        self.emit(part, &answer.to_string(), &[], &text);
+    }
+
+    /// Reports a multi-line answer given as raw grid text, e.g. rendered
+    /// letters, together with the decoded string if there is one.
+    pub fn grid_part<T: Display>(&self, part: u32, grid: &str, decoded: Option<&str>, text: T) {
+        let extra = [
+            ("grid", json_string(grid)),
+            ("decoded", decoded.map_or("null".to_string(), json_string)),
+        ];
+        self.emit(part, decoded.unwrap_or(grid), &extra, &text);
+    }
+}
@@ -26,10 +26,14 @@ fn part2(entries: &[i32]) -> i32 {
     panic!("no matching entries found")
 }
 
-fn main() {
-    let input: Vec<i32> = common::get_input();
-    println!("Part1: {}", part1(&input));
-    println!("Part2: {}", part2(&input));
+fn main() -> Result<(), common::InputError> {
+    let report = common::Report::new(2020, 1)?;
+    let input: Vec<i32> = common::get_input()?;
+    let result1 = part1(&input);
+    report.part(1, result1, format!("Part1: {}", result1));
+    let result2 = part2(&input);
+    report.part(2, result2, format!("Part2: {}", result2));
+    Ok(())
 }
 
 #[cfg(test)]
@@ -1,4 +1,5 @@
 mod common;
+use common::InputError;
 use std::str::FromStr;
 
 fn valid1(input: &Input) -> bool {
@@ -33,28 +34,26 @@ struct Input {
 }
 
 impl FromStr for Input {
-    type Err = String;
+    type Err = InputError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let missing = |what: &str| InputError::at(s, s.len(), 0, format!("missing {}", what));
         let mut split = s.split([' ', ':', '-'].as_ref());
-        let lower = split
-            .next()
-            .ok_or("missing lower index")?
+        let lower_str = split.next().ok_or_else(|| missing("lower index"))?;
+        let lower = lower_str
             .parse::<usize>()
-            .map_err(|err| err.to_string())?;
-        let upper = split
-            .next()
-            .ok_or("missing upper index")?
+            .map_err(|err| InputError::spanning(s, lower_str, err.to_string()))?;
+        let upper_str = split.next().ok_or_else(|| missing("upper index"))?;
+        let upper = upper_str
             .parse::<usize>()
-            .map_err(|err| err.to_string())?;
-        let letter = split
-            .next()
-            .ok_or("missing character")?
+            .map_err(|err| InputError::spanning(s, upper_str, err.to_string()))?;
+        let letter_str = split.next().ok_or_else(|| missing("character"))?;
+        let letter = letter_str
             .chars()
             .next()
-            .ok_or("no character")?;
-        split.next().ok_or("missing colon")?;
-        let password = split.next().ok_or("missing password")?.to_string();
+            .ok_or_else(|| InputError::spanning(s, letter_str, "no character"))?;
+        split.next().ok_or_else(|| missing("colon"))?;
+        let password = split.next().ok_or_else(|| missing("password"))?.to_string();
         Ok(Input {
             lower,
             upper,
@@ -64,10 +63,14 @@ impl FromStr for Input {
     }
 }
 
-fn main() {
-    let input: Vec<Input> = common::get_input();
-    println!("Part1: {}", part1(&input));
-    println!("Part2: {}", part2(&input));
+fn main() -> Result<(), InputError> {
+    let report = common::Report::new(2020, 2)?;
+    let input: Vec<Input> = common::get_input()?;
+    let result1 = part1(&input);
+    report.part(1, result1, format!("Part1: {}", result1));
+    let result2 = part2(&input);
+    report.part(2, result2, format!("Part2: {}", result2));
+    Ok(())
 }
 
 #[cfg(test)]
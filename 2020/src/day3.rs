@@ -1,14 +1,16 @@
 mod common;
-use std::str::FromStr;
-
-fn test_slope(map: &Map, x: usize, y: usize) -> usize {
-    let mut pos = Position::new(map);
-    let mut trees = 0;
-    while !pos.is_at_bottom() {
-        pos.update(x, y);
-        trees += pos.is_at_tree() as usize;
-    }
-    trees
+use common::geometry::Point2D;
+use common::grid::Grid;
+use common::InputError;
+
+fn test_slope(map: &Map, x: i64, y: i64) -> usize {
+    // the map repeats to the right, so only the rows limit the slope
+    let slope = Point2D::new(x, y);
+    (1..)
+        .map(|i| slope * i)
+        .take_while(|p| p.y < map.height() as i64)
+        .filter(|&p| *map.get_wrapping(p))
+        .count()
 }
 
 fn part1(map: &Map) -> usize {
@@ -24,87 +26,25 @@ fn part2(map: &Map) -> usize {
     result
 }
 
-#[derive(Debug)]
-struct Row {
-    row: Vec<bool>,
-}
+// trees are true, open squares false
+type Map = Grid<bool>;
 
-#[derive(Debug)]
-struct Map {
-    width: usize,
-    height: usize,
-    rows: Vec<Row>,
+fn parse_map(s: &str) -> Result<Map, InputError> {
+    Grid::parse(s, |c| match c {
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
+    })
 }
 
-struct Position<'a> {
-    x: usize,
-    y: usize,
-    map: &'a Map,
-}
-
-impl<'a> Position<'a> {
-    fn new(map: &'a Map) -> Position<'a> {
-        Position { x: 0, y: 0, map }
-    }
-
-    fn update(&mut self, x_shift: usize, y_shift: usize) {
-        self.x = (self.x + x_shift) % self.map.width;
-        self.y = (self.y + y_shift) % self.map.height;
-    }
-
-    fn is_at_bottom(&self) -> bool {
-        self.y + 1 == self.map.height
-    }
-
-    fn is_at_tree(&self) -> bool {
-        self.map.rows[self.y].row[self.x]
-    }
-}
-
-impl FromStr for Row {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let row: Vec<_> = s
-            .chars()
-            .filter(|&c| c == '#' || c == '.')
-            .map(|c| c == '#')
-            .collect();
-        Ok(Row { row })
-    }
-}
-
-impl FromStr for Map {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let rows = s
-            .lines()
-            .map(|l| l.parse::<Row>())
-            .collect::<Result<Vec<_>, _>>()?;
-        if rows.is_empty() {
-            return Err("no rows".to_string());
-        }
-        let width = rows[0].row.len();
-        if width == 0 {
-            return Err("first row empty".to_string());
-        }
-        if rows.iter().any(|r| r.row.len() != width) {
-            return Err("rows of different width".to_string());
-        }
-        let height = rows.len();
-        Ok(Map {
-            width,
-            height,
-            rows,
-        })
-    }
-}
-
-fn main() {
-    let input = common::get_content().parse::<Map>().expect("invalid input");
-    println!("Part1: {}", part1(&input));
-    println!("Part2: {}", part2(&input));
+fn main() -> Result<(), InputError> {
+    let report = common::Report::new(2020, 3)?;
+    let input: Map = common::parse_content_with(parse_map)?;
+    let result1 = part1(&input);
+    report.part(1, result1, format!("Part1: {}", result1));
+    let result2 = part2(&input);
+    report.part(2, result2, format!("Part2: {}", result2));
+    Ok(())
 }
 
 #[cfg(test)]
@@ -123,7 +63,7 @@ mod tests {
             #.##...#...\n\
             #...##....#\n\
             .#..#...#.#";
-        input.parse::<Map>().unwrap()
+        parse_map(input).unwrap()
     }
 
     #[test]
@@ -135,4 +75,13 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(&test_input()), 336);
     }
+
+    #[test]
+    fn test_parse_error() {
+        let err = parse_map("..#.\n.#x.\n").unwrap_err();
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(err.column(), Some(3));
+        let err = parse_map("..#.\n.#.\n").unwrap_err();
+        assert_eq!(err.message(), "row has width 3 instead of 4");
+    }
 }
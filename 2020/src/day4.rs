@@ -1,4 +1,5 @@
 mod common;
+use common::InputError;
 use std::str::FromStr;
 
 fn part1(passports: &[Passport]) -> usize {
@@ -35,7 +36,7 @@ impl Passport {
         }
     }
 
-    fn set_field(&mut self, key: &str, value: &str) -> Result<(), String> {
+    fn set_field(&mut self, key: &str, value: &str) -> Result<(), InputError> {
         match key {
             "byr" => self.byr = Some(value.to_string()),
             "iyr" => self.iyr = Some(value.to_string()),
@@ -45,7 +46,7 @@ impl Passport {
             "ecl" => self.ecl = Some(value.to_string()),
             "pid" => self.pid = Some(value.to_string()),
             "cid" => self.cid = Some(value.to_string()),
-            _ => return Err("invalid key: ".to_string() + key),
+            _ => return Err(InputError::spanning(key, key, "invalid key")),
         }
         Ok(())
     }
@@ -85,42 +86,64 @@ impl Passport {
 }
 
 impl FromStr for Passport {
-    type Err = String;
+    type Err = InputError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut passport = Passport::new();
         for entry in s.split_whitespace() {
             let mut split = entry.splitn(2, ':');
-            let key = split.next().ok_or("no key")?;
-            let value = split.next().ok_or("no value")?;
-            passport.set_field(key, value)?;
+            let key = split
+                .next()
+                .ok_or_else(|| InputError::spanning(s, entry, "no key"))?;
+            let value = split
+                .next()
+                .ok_or_else(|| InputError::spanning(s, entry, "no value"))?;
+            passport
+                .set_field(key, value)
+                .map_err(|e| e.within(s, key))?;
         }
         Ok(passport)
     }
 }
 
-fn parse_input(input: &str) -> Result<Vec<Passport>, String> {
+fn parse_input(input: &str) -> Result<Vec<Passport>, InputError> {
     let mut passports = Vec::new();
-    let mut cur_string = String::new();
-    for line in input.lines() {
-        if line.is_empty() {
-            passports.push(cur_string.parse::<Passport>()?);
-            cur_string.clear()
-        } else {
-            cur_string.push_str(line);
-            cur_string.push('\n');
+    // passports are separated by empty lines
+    let mut start = 0;
+    let mut end = 0;
+    for line in input.split_inclusive('\n') {
+        if line.trim_end().is_empty() {
+            if start < end {
+                let block = &input[start..end];
+                passports.push(
+                    block
+                        .parse::<Passport>()
+                        .map_err(|e| e.within(input, block))?,
+                );
+            }
+            start = end + line.len();
         }
+        end += line.len();
     }
-    if !cur_string.is_empty() {
-        passports.push(cur_string.parse::<Passport>()?);
+    if start < input.len() {
+        let block = &input[start..];
+        passports.push(
+            block
+                .parse::<Passport>()
+                .map_err(|e| e.within(input, block))?,
+        );
     }
     Ok(passports)
 }
 
-fn main() {
-    let input = parse_input(&common::get_content()).expect("could not parse input");
-    println!("Part1: {}", part1(&input));
-    println!("Part2: {}", part2(&input));
+fn main() -> Result<(), InputError> {
+    let report = common::Report::new(2020, 4)?;
+    let input = common::parse_content_with(parse_input)?;
+    let result1 = part1(&input);
+    report.part(1, result1, format!("Part1: {}", result1));
+    let result2 = part2(&input);
+    report.part(2, result2, format!("Part2: {}", result2));
+    Ok(())
 }
 
 #[cfg(test)]
@@ -141,7 +164,7 @@ mod tests {
         \n\
         hcl:#cfa07d eyr:2025 pid:166559648\n\
         iyr:2011 ecl:brn hgt:59in";
-        parse_input(&input).unwrap()
+        parse_input(input).unwrap()
     }
 
     fn test_invalid() -> Vec<Passport> {
@@ -158,7 +181,7 @@ mod tests {
         hgt:59cm ecl:zzz\n\
         eyr:2038 hcl:74454a iyr:2023\n\
         pid:3556412378 byr:2007";
-        parse_input(&input).unwrap()
+        parse_input(input).unwrap()
     }
 
     fn test_valid() -> Vec<Passport> {
@@ -174,7 +197,7 @@ mod tests {
         eyr:2022\n\
         \n\
         iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719";
-        parse_input(&input).unwrap()
+        parse_input(input).unwrap()
     }
 
     #[test]
@@ -187,4 +210,17 @@ mod tests {
         assert_eq!(part2(&test_invalid()), 0);
         assert_eq!(part2(&test_valid()), 4);
     }
+
+    #[test]
+    fn test_parse_error() {
+        let input = "ecl:gry pid:860033327\n\
+        \n\
+        eyr:2020 hcl:#fffffd\n\
+        byr:1937 foo:bar";
+        let err = parse_input(input).unwrap_err();
+        assert_eq!(err.message(), "invalid key");
+        assert_eq!(err.line(), Some(4));
+        assert_eq!(err.column(), Some(10));
+        assert_eq!(err.line_text(), "byr:1937 foo:bar");
+    }
 }
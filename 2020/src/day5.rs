@@ -1,4 +1,5 @@
 mod common;
+use common::InputError;
 use std::str::FromStr;
 
 fn part1(codes: &[Seat]) -> u32 {
@@ -28,51 +29,58 @@ impl Seat {
     }
 }
 
-fn binary_search<I, T>(
+fn binary_search<I>(
     mut lower: u32,
     mut upper: u32,
-    down: T,
-    up: T,
+    down: char,
+    up: char,
+    s: &str,
     iter: &mut I,
-) -> Result<u32, String>
+) -> Result<u32, InputError>
 where
-    I: Iterator<Item = T>,
-    T: std::fmt::Display + Eq,
+    I: Iterator<Item = (usize, char)>,
 {
     assert!(lower < upper);
     while upper - lower >= 2 {
         let mid = lower + ((upper - lower) / 2);
-        let dir = iter.next().ok_or_else(|| "not enough directions".to_string())?;
+        let (i, dir) = iter
+            .next()
+            .ok_or_else(|| InputError::at(s, s.len(), 0, "not enough directions"))?;
         if dir == down {
             upper = mid;
         } else if dir == up {
             lower = mid;
         } else {
-            return Err(format!("invalid direction: {}", dir));
+            return Err(InputError::at(
+                s,
+                i,
+                dir.len_utf8(),
+                format!("invalid direction: {}", dir),
+            ));
         }
     }
     Ok(lower)
 }
 
 impl FromStr for Seat {
-    type Err = String;
+    type Err = InputError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut chars = s.chars();
-        let row = binary_search(0, 128, 'F', 'B', &mut chars)?;
-        let column = binary_search(0, 8, 'L', 'R', &mut chars)?;
+        let mut chars = s.char_indices();
+        let row = binary_search(0, 128, 'F', 'B', s, &mut chars)?;
+        let column = binary_search(0, 8, 'L', 'R', s, &mut chars)?;
         Ok(Seat { row, column })
     }
 }
 
-fn main() {
-    let input = common::get_lines();
-    let seats: Vec<_> = input
-        .iter()
-        .map(|s| s.parse::<Seat>().expect("could not parse seat"))
-        .collect();
-    println!("Part1: {}", part1(&seats));
-    println!("Part2: {}", part2(&seats));
+fn main() -> Result<(), InputError> {
+    let report = common::Report::new(2020, 5)?;
+    let seats: Vec<Seat> = common::get_input()?;
+    let result1 = part1(&seats);
+    report.part(1, result1, format!("Part1: {}", result1));
+    let result2 = part2(&seats);
+    report.part(2, result2, format!("Part2: {}", result2));
+    Ok(())
 }
 
 #[cfg(test)]
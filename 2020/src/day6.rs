@@ -39,11 +39,15 @@ fn parse_input(input: &str) -> Vec<Vec<HashSet<char>>> {
     groups
 }
 
-fn main() {
-    let input = common::get_content();
+fn main() -> Result<(), common::InputError> {
+    let report = common::Report::new(2020, 6)?;
+    let input = common::get_content()?;
     let answers = parse_input(&input);
-    println!("Part1: {}", part1(&answers));
-    println!("Part2: {}", part2(&answers));
+    let result1 = part1(&answers);
+    report.part(1, result1, format!("Part1: {}", result1));
+    let result2 = part2(&answers);
+    report.part(2, result2, format!("Part2: {}", result2));
+    Ok(())
 }
 
 #[cfg(test)]
@@ -1,8 +1,8 @@
 mod common;
+use common::graph::{Cycle, Direction, Graph, NodeId};
+use common::InputError;
 
 use regex::Regex;
-use std::collections::hash_map::{Entry, HashMap};
-use std::collections::VecDeque;
 use std::str::FromStr;
 
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
@@ -23,11 +23,13 @@ struct BagRule {
 }
 
 impl FromStr for Bag {
-    type Err = &'static str;
+    type Err = InputError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let re = Regex::new("(?P<colour>[a-z ]*) bag(s)?").unwrap();
-        let caps = re.captures(s).ok_or("could not parse bag")?;
+        let caps = re
+            .captures(s)
+            .ok_or_else(|| InputError::spanning(s, s, "could not parse bag"))?;
         Ok(Bag {
             colour: caps["colour"].to_string(),
         })
@@ -35,120 +37,86 @@ impl FromStr for Bag {
 }
 
 impl FromStr for BagAmount {
-    type Err = &'static str;
+    type Err = InputError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let re = Regex::new("(?P<amount>[0-9]*) (?P<bag>[a-z ]*)").unwrap();
-        let caps = re.captures(s).ok_or("could not parse bag amount")?;
-        let bag = caps["bag"].parse::<Bag>()?;
+        let caps = re
+            .captures(s)
+            .ok_or_else(|| InputError::spanning(s, s, "could not parse bag amount"))?;
+        let bag_str = &caps["bag"];
+        let bag = bag_str.parse::<Bag>().map_err(|e| e.within(s, bag_str))?;
         let amount = caps["amount"]
             .parse::<usize>()
-            .map_err(|_| "could not parse amount")?;
+            .map_err(|_| InputError::spanning(s, &caps["amount"], "could not parse amount"))?;
         Ok(BagAmount { bag, amount })
     }
 }
 
 impl FromStr for BagRule {
-    type Err = &'static str;
+    type Err = InputError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let re = Regex::new("(?P<outer>[a-z ]*) contain (?P<inner>(no other bags|[a-z0-9 ,]*)).")
             .unwrap();
-        let caps = re.captures(s).ok_or("could not parse reaction")?;
-        let outer = caps["outer"].parse::<Bag>()?;
+        let caps = re
+            .captures(s)
+            .ok_or_else(|| InputError::spanning(s, s, "could not parse bag rule"))?;
+        let outer_str = &caps["outer"];
+        let outer = outer_str
+            .parse::<Bag>()
+            .map_err(|e| e.within(s, outer_str))?;
         let inner_str = &caps["inner"];
         let inner: Vec<_> = if inner_str == "no other bags" {
             Vec::new()
         } else {
             inner_str
                 .split(", ")
-                .map(|s| s.parse::<BagAmount>())
+                .map(|b| b.parse::<BagAmount>().map_err(|e| e.within(s, b)))
                 .collect::<Result<Vec<_>, _>>()?
         };
         Ok(BagRule { outer, inner })
     }
 }
 
-type NodeId = usize;
-
-#[derive(Debug)]
-struct WeightedEdge {
-    weight: usize,
-    target: NodeId,
-}
-
-#[derive(Debug)]
-struct Node {
-    predecessors: Vec<WeightedEdge>,
-    successors: Vec<WeightedEdge>,
-}
-
+// edges go from the outer to the inner bags, weighted with their amount
 #[derive(Debug)]
 struct BagGraph {
-    node_ids: HashMap<Bag, NodeId>,
-    nodes: Vec<Node>,
+    graph: Graph<usize>,
 }
 
 impl BagGraph {
-    fn node_for(&mut self, bag: Bag) -> NodeId {
-        match self.node_ids.entry(bag) {
-            Entry::Occupied(e) => *e.get(),
-            Entry::Vacant(e) => {
-                let next_id = self.nodes.len();
-                self.nodes.push(Node {
-                    predecessors: Vec::new(),
-                    successors: Vec::new(),
-                });
-                e.insert(next_id);
-                next_id
-            }
-        }
-    }
-
-    fn get(&self, bag: Bag) -> Option<NodeId> {
-        self.node_ids.get(&bag).copied()
-    }
-
-    fn node(&self, id: NodeId) -> &Node {
-        &self.nodes[id]
-    }
-
-    fn node_mut(&mut self, id: NodeId) -> &mut Node {
-        &mut self.nodes[id]
-    }
-
     fn add_rule(&mut self, rule: BagRule) {
-        let id = self.node_for(rule.outer);
+        let id = self.graph.node(&rule.outer.colour);
         for inner in rule.inner {
-            let succ_id = self.node_for(inner.bag);
-            self.node_mut(succ_id).predecessors.push(WeightedEdge {
-                weight: inner.amount,
-                target: id,
-            });
-            self.node_mut(id).successors.push(WeightedEdge {
-                weight: inner.amount,
-                target: succ_id,
-            });
-        }
-    }
-
-    fn new() -> BagGraph {
-        BagGraph {
-            node_ids: HashMap::new(),
-            nodes: Vec::new(),
+            let inner_id = self.graph.node(&inner.bag.colour);
+            self.graph.add_edge(id, inner_id, inner.amount);
         }
     }
 
     fn build<I: Iterator<Item = BagRule>>(rules: I) -> BagGraph {
-        let mut graph = BagGraph::new();
+        let mut graph = BagGraph {
+            graph: Graph::new(),
+        };
         for rule in rules {
             graph.add_rule(rule);
         }
         graph
     }
 
-    fn size(&self) -> usize {
-        self.nodes.len()
+    fn get(&self, bag: Bag) -> Option<NodeId> {
+        self.graph.id(&bag.colour)
+    }
+
+    // DOT graph of the rules, labelling each edge with the number of inner
+    // bags and highlighting the bags inside of the given bag
+    fn to_dot(&self, highlight: Option<Bag>) -> String {
+        let highlight = highlight
+            .and_then(|bag| self.get(bag))
+            .map(|id| self.graph.dfs(id, Direction::Forward))
+            .unwrap_or_default();
+        self.graph
+            .to_dot(|count| Some(count.to_string()), &highlight)
     }
 }
 
@@ -157,55 +125,43 @@ fn part1(graph: &BagGraph) -> usize {
         colour: "shiny gold".to_string(),
     };
     let initial_node = graph.get(target).unwrap();
-
-    let mut queue = VecDeque::new();
-    queue.push_back(initial_node);
-    let mut visited = vec![false; graph.size()];
-    visited[initial_node] = true;
-
-    let mut count = 0;
-    while let Some(id) = queue.pop_front() {
-        for edge in &graph.node(id).predecessors {
-            let pred_id = edge.target;
-            if !visited[pred_id] {
-                visited[pred_id] = true;
-                count += 1;
-                queue.push_back(pred_id);
-            }
-        }
-    }
-    count
+    let reachable = graph.graph.reachable(initial_node, Direction::Backward);
+    // the bag itself does not count
+    reachable.iter().filter(|&&r| r).count() - 1
 }
 
-fn part2_dfs(graph: &BagGraph, id: NodeId, mut cache: &mut [isize]) -> usize {
-    if cache[id] >= 0 {
-        cache[id] as usize
-    } else {
-        let mut count = 0;
-        for edge in &graph.node(id).successors {
-            let inner = part2_dfs(graph, edge.target, &mut cache);
-            count += edge.weight * (1 + inner);
-        }
-        cache[id] = count as isize;
-        count
-    }
-}
-
-fn part2(graph: &BagGraph) -> usize {
+fn part2(graph: &BagGraph) -> Result<usize, InputError> {
     let target = Bag {
         colour: "shiny gold".to_string(),
     };
     let initial_node = graph.get(target).unwrap();
-
-    let mut cache = vec![-1; graph.size()];
-    part2_dfs(graph, initial_node, &mut cache)
+    graph
+        .graph
+        .fold_successors(initial_node, |_, inner| {
+            inner
+                .iter()
+                .map(|&(&amount, count)| amount * (1 + count))
+                .sum()
+        })
+        .map_err(|Cycle(cycle)| {
+            let names: Vec<_> = cycle.iter().map(|&c| graph.graph.name(c)).collect();
+            InputError::new(format!("bags contain each other: {}", names.join(" -> ")))
+        })
 }
 
-fn main() {
-    let rules: Vec<BagRule> = common::get_input();
+fn main() -> Result<(), InputError> {
+    let report = common::Report::new(2020, 7)?;
+    let rules: Vec<BagRule> = common::get_input()?;
     let graph = BagGraph::build(rules.into_iter());
-    println!("Part1: {}", part1(&graph));
-    println!("Part2: {}", part2(&graph));
+    if let Some(filename) = common::option("dot") {
+        let highlight = common::option("highlight").map(|colour| Bag { colour });
+        common::write_file(&filename, graph.to_dot(highlight).as_bytes())?;
+    }
+    let result1 = part1(&graph);
+    report.part(1, result1, format!("Part1: {}", result1));
+    let result2 = part2(&graph)?;
+    report.part(2, result2, format!("Part2: {}", result2));
+    Ok(())
 }
 
 #[cfg(test)]
@@ -231,7 +187,7 @@ mod tests {
     }
 
     fn test_input2() -> Vec<BagRule> {
-        let rule_strings = vec![
+        let rule_strings = [
             "shiny gold bags contain 2 dark red bags.",
             "dark red bags contain 2 dark orange bags.",
             "dark orange bags contain 2 dark yellow bags.",
@@ -257,10 +213,27 @@ mod tests {
     fn test_part2() {
         let rules1: Vec<BagRule> = test_input();
         let graph1 = BagGraph::build(rules1.into_iter());
-        assert_eq!(part2(&graph1), 32);
+        assert_eq!(part2(&graph1).unwrap(), 32);
 
         let rules2: Vec<BagRule> = test_input2();
         let graph2 = BagGraph::build(rules2.into_iter());
-        assert_eq!(part2(&graph2), 126);
+        assert_eq!(part2(&graph2).unwrap(), 126);
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let rules: Vec<BagRule> = test_input2();
+        let graph = BagGraph::build(rules.into_iter().skip(5));
+        let dot = graph.to_dot(Some(Bag {
+            colour: "dark blue".to_string(),
+        }));
+        assert_eq!(
+            dot,
+            "digraph {\n    \
+             0 [label=\"dark blue\", color=red, penwidth=2];\n    \
+             1 [label=\"dark violet\", color=red, penwidth=2];\n    \
+             0 -> 1 [label=\"2\", color=red, penwidth=2];\n\
+             }\n"
+        );
     }
 }
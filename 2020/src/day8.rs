@@ -1,4 +1,5 @@
 mod common;
+use common::InputError;
 
 use std::str::FromStr;
 
@@ -10,20 +11,30 @@ enum Instruction {
 }
 
 impl FromStr for Instruction {
-    type Err = String;
+    type Err = InputError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut split = s.splitn(2, ' ');
-        let op_str = split.next().ok_or("no operation")?;
-        let arg_str = split.next().ok_or("no argument")?;
-        let arg = arg_str
-            .parse::<isize>()
-            .map_err(|err| format!("invalid argument: {}", err))?;
+        let op_str = split
+            .next()
+            .ok_or_else(|| InputError::spanning(s, s, "no operation"))?;
+        let arg_str = split
+            .next()
+            .ok_or_else(|| InputError::at(s, s.len(), 0, "no argument"))?;
+        let arg = arg_str.parse::<isize>().map_err(|err| {
+            InputError::spanning(s, arg_str, format!("invalid argument: {}", err))
+        })?;
         let instruction = match op_str {
             "nop" => Instruction::Nop(arg),
             "jmp" => Instruction::Jmp(arg),
             "acc" => Instruction::Acc(arg),
-            _ => return Err(format!("invalid operation: {}", op_str)),
+            _ => {
+                return Err(InputError::spanning(
+                    s,
+                    op_str,
+                    format!("invalid operation: {}", op_str),
+                ))
+            }
         };
         Ok(instruction)
     }
@@ -35,12 +46,12 @@ struct Program {
 }
 
 impl FromStr for Program {
-    type Err = String;
+    type Err = InputError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let instructions: Vec<_> = s
             .lines()
-            .map(|l| l.parse::<Instruction>())
+            .map(|l| l.parse::<Instruction>().map_err(|e| e.within(s, l)))
             .collect::<Result<Vec<_>, _>>()?;
         Ok(Program { instructions })
     }
@@ -96,21 +107,25 @@ fn swap_nop_jmp(program: &mut Program, loc: usize) {
     }
 }
 
-fn part2(mut program: &mut Program) -> isize {
+fn part2(program: &mut Program) -> isize {
     for loc in 0..program.instructions.len() {
-        swap_nop_jmp(&mut program, loc);
+        swap_nop_jmp(program, loc);
         if let ExecutionResult::Terminate(val) = program.execute() {
             return val;
         }
-        swap_nop_jmp(&mut program, loc);
+        swap_nop_jmp(program, loc);
     }
     panic!("could not fix program")
 }
 
-fn main() {
-    let mut program = common::get_content().parse::<Program>().unwrap();
-    println!("Part1: {}", part1(&program));
-    println!("Part2: {}", part2(&mut program));
+fn main() -> Result<(), InputError> {
+    let report = common::Report::new(2020, 8)?;
+    let mut program: Program = common::parse_content()?;
+    let result1 = part1(&program);
+    report.part(1, result1, format!("Part1: {}", result1));
+    let result2 = part2(&mut program);
+    report.part(2, result2, format!("Part2: {}", result2));
+    Ok(())
 }
 
 #[cfg(test)]
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Command line arguments: positional arguments, options given as
+/// `--name value` or `--name=value`, and flags without a value.
+#[derive(Debug, Default)]
+pub struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    /// Parses the arguments, where `flags` names the options without value.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I, flags: &[&str]) -> Result<Args, String> {
+        let mut result = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if let Some(name) = arg.strip_prefix("--") {
+                if let Some((name, value)) = name.split_once('=') {
+                    result.options.insert(name.to_string(), value.to_string());
+                } else if flags.contains(&name) {
+                    result.flags.push(name.to_string());
+                } else {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("no value given for --{}", name))?;
+                    result.options.insert(name.to_string(), value);
+                }
+            } else {
+                result.positional.push(arg);
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn positional(&self) -> &[String] {
+        &self.positional
+    }
+
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(|v| v.as_str())
+    }
+
+    /// Parses the value of an option, if it is given.
+    pub fn parsed_option<T: FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+        self.option(name)
+            .map(|v| {
+                v.parse()
+                    .map_err(|_| format!("invalid value for --{}: {}", name, v))
+            })
+            .transpose()
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|a| a.to_string()), &["no-build"])
+    }
+
+    #[test]
+    fn test_parse() {
+        let args = parse(&["run-all", "--jobs", "4", "--no-build", "--timeout=2.5"]).unwrap();
+        assert_eq!(args.positional(), ["run-all"]);
+        assert_eq!(args.option("jobs"), Some("4"));
+        assert_eq!(args.parsed_option::<f64>("timeout"), Ok(Some(2.5)));
+        assert_eq!(args.parsed_option::<f64>("budget"), Ok(None));
+        assert!(args.flag("no-build"));
+        assert!(!args.flag("jobs"));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(parse(&["run-all", "--jobs"]).is_err());
+        let args = parse(&["--jobs", "many"]).unwrap();
+        assert!(args.parsed_option::<usize>("jobs").is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+/// Environment variable with the value of the session cookie.
+pub const SESSION_VAR: &str = "AOC_SESSION";
+/// Environment variable overriding the base URL.
+pub const BASE_URL_VAR: &str = "AOC_BASE_URL";
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum Fetched {
+    Cached(PathBuf),
+    Downloaded(PathBuf),
+}
+
+/// Downloads puzzle inputs into the `data` folder of their year.
+pub struct Fetcher {
+    base_url: String,
+    session: Option<String>,
+}
+
+impl Fetcher {
+    pub fn new(base_url: &str, session: Option<String>) -> Fetcher {
+        Fetcher {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            session,
+        }
+    }
+
+    /// Fetcher with the session and, unless given, the base URL from the environment.
+    pub fn from_env(base_url: Option<&str>) -> Fetcher {
+        let base_url = base_url
+            .map(|u| u.to_string())
+            .or_else(|| std::env::var(BASE_URL_VAR).ok())
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+        let session = std::env::var(SESSION_VAR).ok().filter(|s| !s.is_empty());
+        Fetcher::new(&base_url, session)
+    }
+
+    pub fn url(&self, year: u32, day: u32) -> String {
+        format!("{}/{}/day/{}/input", self.base_url, year, day)
+    }
+
+    /// Downloads the input of a day.
+    pub fn download(&self, year: u32, day: u32) -> Result<String, String> {
+        let session = self
+            .session
+            .as_ref()
+            .ok_or_else(|| format!("no session token given, set {}", SESSION_VAR))?;
+        let url = self.url(year, day);
+        let response = ureq::get(&url)
+            .set("Cookie", &format!("session={}", session))
+            .set("User-Agent", "github.com/meyerphi/advent-of-code")
+            .call()
+            .map_err(|e| format!("could not download {}: {}", url, e))?;
+        response
+            .into_string()
+            .map_err(|e| format!("could not read response from {}: {}", url, e))
+    }
+
+    /// Returns the cached input file of a day, downloading it if there is none.
+    /// Empty files are placeholders and do not count as cached.
+    pub fn fetch(&self, root: &Path, year: u32, day: u32) -> Result<Fetched, String> {
+        let year_dir = root.join(year.to_string());
+        if !year_dir.is_dir() {
+            return Err(format!("no folder for year {}", year));
+        }
+        let data_dir = year_dir.join("data");
+        let path = data_dir.join(format!("{}.in", day));
+        if fs::metadata(&path).is_ok_and(|m| m.len() > 0) {
+            return Ok(Fetched::Cached(path));
+        }
+        let input = self.download(year, day)?;
+        fs::create_dir_all(&data_dir)
+            .map_err(|e| format!("could not create {}: {}", data_dir.display(), e))?;
+        // write to a temporary file first so that no partial input gets cached
+        let partial = data_dir.join(format!("{}.in.part", day));
+        fs::write(&partial, input)
+            .and_then(|_| fs::rename(&partial, &path))
+            .map_err(|e| format!("could not write {}: {}", path.display(), e))?;
+        Ok(Fetched::Downloaded(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    /// Serves `responses` in order, recording the request line and cookie of each request.
+    fn stub_server(responses: Vec<(u16, &'static str)>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        thread::spawn(move || {
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                let mut cookie = String::new();
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(value) = header.strip_prefix("Cookie: ") {
+                        cookie = value.trim().to_string();
+                    }
+                }
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push(format!("{} {}", request.trim(), cookie));
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Stub\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+        (base_url, requests)
+    }
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("aoc-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("2019")).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_fetch_and_cache() {
+        let (base_url, requests) = stub_server(vec![(200, "1\n2\n3\n")]);
+        let root = temp_root("fetch");
+        let fetcher = Fetcher::new(&base_url, Some("secret".to_string()));
+        let path = root.join("2019").join("data").join("7.in");
+
+        assert_eq!(
+            fetcher.fetch(&root, 2019, 7),
+            Ok(Fetched::Downloaded(path.clone()))
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1\n2\n3\n");
+        // the stub server only answers once, so this has to come from the cache
+        assert_eq!(fetcher.fetch(&root, 2019, 7), Ok(Fetched::Cached(path)));
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec!["GET /2019/day/7/input HTTP/1.1 session=secret"]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_fetch_errors() {
+        let (base_url, _) = stub_server(vec![(404, "Not Found")]);
+        let root = temp_root("fetch-errors");
+        let path = root.join("2019").join("data").join("1.in");
+
+        let fetcher = Fetcher::new(&base_url, None);
+        assert!(fetcher.fetch(&root, 2019, 1).is_err());
+
+        let fetcher = Fetcher::new(&base_url, Some("secret".to_string()));
+        assert!(fetcher.fetch(&root, 2021, 1).is_err());
+        assert!(fetcher.fetch(&root, 2019, 1).is_err());
+        assert!(!path.exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
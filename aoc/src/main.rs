@@ -0,0 +1,108 @@
+mod args;
+mod fetch;
+mod run;
+mod scaffold;
+mod solutions;
+
+use args::Args;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const USAGE: &str = "usage: aoc <command> [options]
+
+commands:
+    run-all     build and run all solutions in parallel and report their runtime
+                --jobs N        number of worker threads (default: number of cpus)
+                --timeout SECS  kill solutions running longer (default: 10)
+                --budget SECS   report solutions running longer (default: 1)
+                --no-build      do not build the solutions before
+    fetch <year> <day>
+                download the input of a day into the data folder of its year,
+                unless it is there already; the session token is read from
+                AOC_SESSION, the base URL from AOC_BASE_URL
+                --base-url URL  base URL (default: https://adventofcode.com)
+    new <year> <day>
+                create the source file of a day from a template, register it
+                in the manifest of its year and create an empty input file
+
+options:
+    --root DIR  repository root (default: parent of this crate)";
+
+fn root(args: &Args) -> PathBuf {
+    match args.option("root") {
+        Some(root) => PathBuf::from(root),
+        None => PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/..")),
+    }
+}
+
+fn seconds(args: &Args, name: &str, default: f64) -> Result<Duration, String> {
+    let secs = args.parsed_option(name)?.unwrap_or(default);
+    Duration::try_from_secs_f64(secs).map_err(|_| format!("invalid value for --{}: {}", name, secs))
+}
+
+fn run_all(args: &Args) -> Result<(), String> {
+    let default_jobs = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let config = run::Config {
+        root: root(args),
+        jobs: args.parsed_option("jobs")?.unwrap_or(default_jobs),
+        timeout: seconds(args, "timeout", 10.0)?,
+        budget: seconds(args, "budget", 1.0)?,
+        build: !args.flag("no-build"),
+    };
+    print!("{}", run::run(&config)?);
+    Ok(())
+}
+
+fn year_and_day(args: &Args) -> Result<(u32, u32), String> {
+    match args.positional() {
+        [_, year, day] => {
+            let year = year
+                .parse()
+                .map_err(|_| format!("invalid year: {}", year))?;
+            let day = day
+                .parse()
+                .ok()
+                .filter(|d| (1..=25).contains(d))
+                .ok_or_else(|| format!("invalid day: {}", day))?;
+            Ok((year, day))
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn fetch(args: &Args) -> Result<(), String> {
+    let (year, day) = year_and_day(args)?;
+    let fetcher = fetch::Fetcher::from_env(args.option("base-url"));
+    match fetcher.fetch(&root(args), year, day)? {
+        fetch::Fetched::Cached(path) => println!("Using cached {}", path.display()),
+        fetch::Fetched::Downloaded(path) => println!("Downloaded {}", path.display()),
+    }
+    Ok(())
+}
+
+fn new(args: &Args) -> Result<(), String> {
+    let (year, day) = year_and_day(args)?;
+    let changed = scaffold::new_day(&root(args), year, day)?;
+    if changed.is_empty() {
+        println!("Day {} of {} exists already", day, year);
+    }
+    for path in changed {
+        println!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+fn main() {
+    let result = Args::parse(std::env::args().skip(1), &["no-build"]).and_then(|args| {
+        match args.positional().first().map(|c| c.as_str()) {
+            Some("run-all") => run_all(&args),
+            Some("fetch") => fetch(&args),
+            Some("new") => new(&args),
+            _ => Err(USAGE.to_string()),
+        }
+    });
+    if let Err(message) = result {
+        eprintln!("{}", message);
+        std::process::exit(1);
+    }
+}
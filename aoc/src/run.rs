@@ -0,0 +1,328 @@
+use crate::solutions::{self, Solution};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub struct Config {
+    pub root: PathBuf,
+    pub jobs: usize,
+    pub timeout: Duration,
+    pub budget: Duration,
+    pub build: bool,
+}
+
+#[derive(PartialEq, Debug)]
+pub enum Status {
+    Ok,
+    Failed(String),
+    TimedOut,
+    NoInput,
+}
+
+#[derive(Debug)]
+pub struct Outcome {
+    pub solution: Solution,
+    pub status: Status,
+    pub runtime: Duration,
+    /// Part number and runtime in milliseconds as reported by the solution.
+    pub parts: Vec<(u32, f64)>,
+}
+
+/// Output of a process that finished, or `None` for the status if it was
+/// killed after the timeout.
+pub struct ProcessOutput {
+    pub status: Option<ExitStatus>,
+    pub runtime: Duration,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+fn read_all<R: Read + Send + 'static>(stream: Option<R>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut s = String::new();
+        if let Some(mut stream) = stream {
+            let _ = stream.read_to_string(&mut s);
+        }
+        s
+    })
+}
+
+/// Runs the command, killing it if it does not finish within the timeout.
+pub fn run_with_timeout(mut command: Command, timeout: Duration) -> io::Result<ProcessOutput> {
+    let start = Instant::now();
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // read concurrently so that a full pipe does not block the child
+    let stdout = read_all(child.stdout.take());
+    let stderr = read_all(child.stderr.take());
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if start.elapsed() >= timeout {
+            child.kill()?;
+            child.wait()?;
+            break None;
+        }
+        thread::sleep(Duration::from_millis(5));
+    };
+    let runtime = start.elapsed();
+    Ok(ProcessOutput {
+        status,
+        runtime,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+// value of a number field in a JSON line as printed by the solutions
+fn json_number(line: &str, key: &str) -> Option<f64> {
+    let pattern = format!("\"{}\": ", key);
+    let start = line.find(&pattern)? + pattern.len();
+    let rest = &line[start..];
+    let end = rest.find([',', '}'])?;
+    rest[..end].trim().parse().ok()
+}
+
+pub fn parse_parts(stdout: &str) -> Vec<(u32, f64)> {
+    stdout
+        .lines()
+        .filter_map(|l| {
+            Some((
+                json_number(l, "part")? as u32,
+                json_number(l, "runtime_ms")?,
+            ))
+        })
+        .collect()
+}
+
+/// Message of a failed run, which is the first line of the error a
+/// solution returned or the message of a panic. Later lines of an error
+/// only point out where in the input it is.
+pub fn failure_message(stderr: &str) -> String {
+    let mut lines = stderr.lines().map(str::trim).filter(|l| !l.is_empty());
+    match lines.next() {
+        None => String::new(),
+        Some(line) if line.starts_with("thread '") && line.contains("panicked at") => {
+            lines.next().unwrap_or(line).to_string()
+        }
+        Some(line) => line.strip_prefix("Error: ").unwrap_or(line).to_string(),
+    }
+}
+
+fn run_solution(solution: Solution, config: &Config) -> Outcome {
+    let input = solution.input(&config.root);
+    if !input.is_file() {
+        return Outcome {
+            solution,
+            status: Status::NoInput,
+            runtime: Duration::default(),
+            parts: Vec::new(),
+        };
+    }
+    let mut command = Command::new(solution.binary(&config.root));
+    command.arg(&input).args(["--format", "json"]);
+    let (status, runtime, parts) = match run_with_timeout(command, config.timeout) {
+        Err(err) => (
+            Status::Failed(err.to_string()),
+            Duration::default(),
+            Vec::new(),
+        ),
+        Ok(output) => {
+            let status = match output.status {
+                None => Status::TimedOut,
+                Some(s) if s.success() => Status::Ok,
+                Some(s) => Status::Failed(format!("{}: {}", s, failure_message(&output.stderr))),
+            };
+            (status, output.runtime, parse_parts(&output.stdout))
+        }
+    };
+    Outcome {
+        solution,
+        status,
+        runtime,
+        parts,
+    }
+}
+
+fn build(root: &Path, years: &[u32]) -> Result<(), String> {
+    for year in years {
+        eprintln!("Building {}", year);
+        let status = Command::new("cargo")
+            .args(["build", "--release", "--bins"])
+            .current_dir(root.join(year.to_string()))
+            .status()
+            .map_err(|e| format!("could not run cargo: {}", e))?;
+        if !status.success() {
+            return Err(format!("building {} failed", year));
+        }
+    }
+    Ok(())
+}
+
+/// Runs the solutions on a pool of `config.jobs` worker threads.
+pub fn run_all(solutions: Vec<Solution>, config: &Config) -> Vec<Outcome> {
+    let queue = Mutex::new(solutions.into_iter());
+    let outcomes = Mutex::new(Vec::new());
+    thread::scope(|s| {
+        for _ in 0..config.jobs.max(1) {
+            s.spawn(|| loop {
+                let next = queue.lock().unwrap().next();
+                match next {
+                    Some(solution) => {
+                        let outcome = run_solution(solution, config);
+                        outcomes.lock().unwrap().push(outcome);
+                    }
+                    None => break,
+                }
+            });
+        }
+    });
+    outcomes.into_inner().unwrap()
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1e3
+}
+
+/// Report of the outcomes, slowest solution first.
+pub fn format_report(outcomes: &mut [Outcome], budget: Duration) -> String {
+    outcomes.sort_by(|a, b| {
+        b.runtime
+            .cmp(&a.runtime)
+            .then_with(|| a.solution.cmp(&b.solution))
+    });
+    let mut report = format!(
+        "{:<4} {:>3} {:>10}  {:<8} {}\n",
+        "year", "day", "runtime", "status", "parts"
+    );
+    for o in outcomes.iter() {
+        let status = match &o.status {
+            Status::Ok if o.runtime > budget => "SLOW".to_string(),
+            Status::Ok => "ok".to_string(),
+            Status::TimedOut => "TIMEOUT".to_string(),
+            Status::NoInput => "no input".to_string(),
+            Status::Failed(message) => format!("FAILED {}", message),
+        };
+        let runtime = if o.status == Status::NoInput {
+            "-".to_string()
+        } else {
+            format!("{:.1} ms", millis(o.runtime))
+        };
+        let parts: Vec<_> = o
+            .parts
+            .iter()
+            .map(|(part, ms)| format!("part {}: {:.1} ms", part, ms))
+            .collect();
+        let line = format!(
+            "{:<4} {:>3} {:>10}  {:<8} {}",
+            o.solution.year,
+            o.solution.day,
+            runtime,
+            status,
+            parts.join(", ")
+        );
+        report.push_str(line.trim_end());
+        report.push('\n');
+    }
+    let over_budget = outcomes
+        .iter()
+        .filter(|o| o.status == Status::TimedOut || (o.status == Status::Ok && o.runtime > budget))
+        .count();
+    let failed = outcomes
+        .iter()
+        .filter(|o| matches!(o.status, Status::Failed(_)))
+        .count();
+    report.push_str(&format!(
+        "{} solutions, {} over the budget of {:.0} ms, {} failed\n",
+        outcomes.len(),
+        over_budget,
+        millis(budget),
+        failed
+    ));
+    report
+}
+
+/// Builds and runs all registered solutions, returning the report.
+pub fn run(config: &Config) -> Result<String, String> {
+    let solutions = solutions::registered(&config.root)
+        .map_err(|e| format!("could not read solutions: {}", e))?;
+    if config.build {
+        let mut years: Vec<_> = solutions.iter().map(|s| s.year).collect();
+        years.dedup();
+        build(&config.root, &years)?;
+    }
+    let mut outcomes = run_all(solutions, config);
+    Ok(format_report(&mut outcomes, config.budget))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_parts() {
+        let stdout = "{\"year\": 2019, \"day\": 1, \"part\": 1, \"answer\": \"3\", \"runtime_ms\": 0.027, \"input_hash\": \"7a\"}\n\
+                      {\"year\": 2019, \"day\": 1, \"part\": 2, \"answer\": \"4\", \"runtime_ms\": 12.5, \"input_hash\": \"7a\"}\n";
+        assert_eq!(parse_parts(stdout), vec![(1, 0.027), (2, 12.5)]);
+    }
+
+    #[test]
+    fn test_failure_message() {
+        let error = "Error: could not parse number\n \
+                     --> 7.in:1:5\n  \
+                     |\n\
+                     1 | 1,2,x\n  \
+                     |     ^\n";
+        assert_eq!(failure_message(error), "could not parse number");
+        let panic = "thread 'main' (42) panicked at src/common/mod.rs:19:24:\n\
+                     no filename given\n\
+                     note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace\n";
+        assert_eq!(failure_message(panic), "no filename given");
+        assert_eq!(failure_message("\nkilled\n"), "killed");
+        assert_eq!(failure_message(""), "");
+    }
+
+    #[test]
+    fn test_report() {
+        let outcome = |day, status, ms| Outcome {
+            solution: Solution { year: 2019, day },
+            status,
+            runtime: Duration::from_millis(ms),
+            parts: Vec::new(),
+        };
+        let mut outcomes = vec![
+            outcome(1, Status::Ok, 3),
+            outcome(2, Status::NoInput, 0),
+            outcome(3, Status::Ok, 1500),
+            outcome(4, Status::TimedOut, 10000),
+        ];
+        let report = format_report(&mut outcomes, Duration::from_secs(1));
+        let days: Vec<_> = outcomes.iter().map(|o| o.solution.day).collect();
+        assert_eq!(days, vec![4, 3, 1, 2]);
+        assert!(report.contains("2019   3  1500.0 ms  SLOW\n"));
+        assert!(report.ends_with("4 solutions, 2 over the budget of 1000 ms, 0 failed\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_timeout() {
+        let mut command = Command::new("sleep");
+        command.arg("10");
+        let output = run_with_timeout(command, Duration::from_millis(50)).unwrap();
+        assert!(output.status.is_none());
+        assert!(output.runtime < Duration::from_secs(5));
+
+        let mut command = Command::new("echo");
+        command.arg("done");
+        let output = run_with_timeout(command, Duration::from_secs(5)).unwrap();
+        assert!(output.status.unwrap().success());
+        assert_eq!(output.stdout, "done\n");
+    }
+}
@@ -0,0 +1,194 @@
+use crate::solutions;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const TEMPLATE: &str = "mod common;
+use common::InputError;
+
+fn part1(input: &[String]) -> usize {
+    input.len()
+}
+
+fn part2(input: &[String]) -> usize {
+    input.len()
+}
+
+fn main() -> Result<(), InputError> {
+    let report = common::Report::new({year}, {day})?;
+    let input = common::get_lines()?;
+    let result1 = part1(&input);
+    report.part(1, result1, format!(\"Part1: {}\", result1));
+    let result2 = part2(&input);
+    report.part(2, result2, format!(\"Part2: {}\", result2));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_input() -> Vec<String> {
+        let input = \"\";
+        input.lines().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(part1(&test_input()), 0);
+    }
+
+    #[test]
+    fn test_part2() {
+        assert_eq!(part2(&test_input()), 0);
+    }
+}
+";
+
+pub fn source(year: u32, day: u32) -> String {
+    TEMPLATE
+        .replacen("{year}", &year.to_string(), 1)
+        .replacen("{day}", &day.to_string(), 1)
+}
+
+fn bin_entry(day: u32) -> String {
+    format!("[[bin]]\nname=\"day{0}\"\npath=\"src/day{0}.rs\"\n", day)
+}
+
+/// Inserts a `[[bin]]` entry for the day into the manifest, keeping the
+/// entries ordered by day. Returns `None` if there is an entry already.
+pub fn insert_bin(manifest: &str, day: u32) -> Option<String> {
+    if solutions::bin_days(manifest).contains(&day) {
+        return None;
+    }
+    // byte offsets of the [[bin]] sections
+    let mut offset = 0;
+    let mut sections = Vec::new();
+    for line in manifest.split_inclusive('\n') {
+        if line.trim() == "[[bin]]" {
+            sections.push(offset);
+        }
+        offset += line.len();
+    }
+    let next = sections.iter().enumerate().find(|&(i, &start)| {
+        let end = sections.get(i + 1).copied().unwrap_or(manifest.len());
+        solutions::bin_days(&manifest[start..end])
+            .first()
+            .is_some_and(|&d| d > day)
+    });
+    let mut result = String::with_capacity(manifest.len() + 64);
+    match next {
+        Some((_, &start)) => {
+            result.push_str(&manifest[..start]);
+            result.push_str(&bin_entry(day));
+            result.push('\n');
+            result.push_str(&manifest[start..]);
+        }
+        None => {
+            result.push_str(manifest.trim_end());
+            result.push_str("\n\n");
+            result.push_str(&bin_entry(day));
+        }
+    }
+    Some(result)
+}
+
+fn create_new(path: &Path, content: &str) -> Result<bool, String> {
+    if path.exists() {
+        return Ok(false);
+    }
+    fs::write(path, content)
+        .map(|_| true)
+        .map_err(|e| format!("could not write {}: {}", path.display(), e))
+}
+
+/// Creates the source file, manifest entry and empty input of a new day.
+/// Returns the files that were created or changed, existing ones are kept.
+pub fn new_day(root: &Path, year: u32, day: u32) -> Result<Vec<PathBuf>, String> {
+    let year_dir = root.join(year.to_string());
+    let manifest_path = year_dir.join("Cargo.toml");
+    let manifest = fs::read_to_string(&manifest_path)
+        .map_err(|e| format!("could not read {}: {}", manifest_path.display(), e))?;
+    let mut changed = Vec::new();
+
+    let source_path = year_dir.join("src").join(format!("day{}.rs", day));
+    if create_new(&source_path, &source(year, day))? {
+        changed.push(source_path);
+    }
+    if let Some(manifest) = insert_bin(&manifest, day) {
+        fs::write(&manifest_path, manifest)
+            .map_err(|e| format!("could not write {}: {}", manifest_path.display(), e))?;
+        changed.push(manifest_path);
+    }
+    let data_dir = year_dir.join("data");
+    fs::create_dir_all(&data_dir)
+        .map_err(|e| format!("could not create {}: {}", data_dir.display(), e))?;
+    let input_path = data_dir.join(format!("{}.in", day));
+    if create_new(&input_path, "")? {
+        changed.push(input_path);
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = "[package]\n\
+                            name = \"aoc2019\"\n\
+                            \n\
+                            [[bin]]\n\
+                            name=\"day14\"\n\
+                            path=\"src/day14.rs\"\n\
+                            \n\
+                            [[bin]]\n\
+                            name=\"day16\"\n\
+                            path=\"src/day16.rs\"";
+
+    #[test]
+    fn test_insert_bin() {
+        let inserted = insert_bin(MANIFEST, 15).unwrap();
+        assert_eq!(solutions::bin_days(&inserted), vec![14, 15, 16]);
+        assert!(inserted.contains(
+            "path=\"src/day14.rs\"\n\n[[bin]]\nname=\"day15\"\npath=\"src/day15.rs\"\n\n[[bin]]\n"
+        ));
+
+        let appended = insert_bin(MANIFEST, 17).unwrap();
+        assert_eq!(solutions::bin_days(&appended), vec![14, 16, 17]);
+        assert!(appended.ends_with(
+            "path=\"src/day16.rs\"\n\n[[bin]]\nname=\"day17\"\npath=\"src/day17.rs\"\n"
+        ));
+
+        let first = insert_bin("[package]\nname = \"aoc2021\"\n", 1).unwrap();
+        assert_eq!(solutions::bin_days(&first), vec![1]);
+
+        assert_eq!(insert_bin(MANIFEST, 16), None);
+    }
+
+    #[test]
+    fn test_new_day() {
+        let root = std::env::temp_dir().join(format!("aoc-new-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let year_dir = root.join("2019");
+        fs::create_dir_all(year_dir.join("src")).unwrap();
+        fs::write(year_dir.join("Cargo.toml"), MANIFEST).unwrap();
+
+        let changed = new_day(&root, 2019, 15).unwrap();
+        assert_eq!(changed.len(), 3);
+        let source = fs::read_to_string(year_dir.join("src").join("day15.rs")).unwrap();
+        assert!(source.starts_with("mod common;\n"));
+        assert!(source.contains("common::Report::new(2019, 15)?"));
+        assert_eq!(
+            fs::read_to_string(year_dir.join("data").join("15.in")).unwrap(),
+            ""
+        );
+
+        // running it again must not change or overwrite anything
+        fs::write(year_dir.join("data").join("15.in"), "input").unwrap();
+        assert_eq!(new_day(&root, 2019, 15).unwrap(), Vec::<PathBuf>::new());
+        assert_eq!(
+            fs::read_to_string(year_dir.join("data").join("15.in")).unwrap(),
+            "input"
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
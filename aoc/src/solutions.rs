@@ -0,0 +1,108 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A solution registered as `[[bin]]` named `dayN` in the manifest of a year.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub struct Solution {
+    pub year: u32,
+    pub day: u32,
+}
+
+impl Solution {
+    pub fn year_dir(&self, root: &Path) -> PathBuf {
+        root.join(self.year.to_string())
+    }
+
+    pub fn binary(&self, root: &Path) -> PathBuf {
+        self.year_dir(root)
+            .join("target")
+            .join("release")
+            .join(format!("day{}", self.day))
+    }
+
+    pub fn input(&self, root: &Path) -> PathBuf {
+        self.year_dir(root)
+            .join("data")
+            .join(format!("{}.in", self.day))
+    }
+}
+
+/// Years in the repository, i.e. folders with a four digit name and a manifest.
+pub fn years(root: &Path) -> io::Result<Vec<u32>> {
+    let mut years = Vec::new();
+    for entry in fs::read_dir(root)? {
+        let path = entry?.path();
+        let year = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .filter(|n| n.len() == 4)
+            .and_then(|n| n.parse().ok());
+        if let Some(year) = year {
+            if path.join("Cargo.toml").is_file() {
+                years.push(year);
+            }
+        }
+    }
+    years.sort_unstable();
+    Ok(years)
+}
+
+/// Days with a `[[bin]]` entry named `dayN` in the given manifest.
+pub fn bin_days(manifest: &str) -> Vec<u32> {
+    let mut days = Vec::new();
+    let mut in_bin = false;
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_bin = line == "[[bin]]";
+        } else if in_bin {
+            if let Some((key, value)) = line.split_once('=') {
+                if key.trim() == "name" {
+                    let name = value.trim().trim_matches('"');
+                    if let Some(day) = name.strip_prefix("day").and_then(|d| d.parse().ok()) {
+                        days.push(day);
+                    }
+                }
+            }
+        }
+    }
+    days
+}
+
+/// All registered solutions of all years, ordered by year and day.
+pub fn registered(root: &Path) -> io::Result<Vec<Solution>> {
+    let mut solutions = Vec::new();
+    for year in years(root)? {
+        let manifest = fs::read_to_string(root.join(year.to_string()).join("Cargo.toml"))?;
+        solutions.extend(
+            bin_days(&manifest)
+                .into_iter()
+                .map(|day| Solution { year, day }),
+        );
+    }
+    solutions.sort();
+    Ok(solutions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bin_days() {
+        let manifest = "[package]\n\
+                        name = \"aoc2019\"\n\
+                        \n\
+                        [dependencies]\n\
+                        num = \"0.2\"\n\
+                        \n\
+                        [[bin]]\n\
+                        name=\"day1\"\n\
+                        path=\"src/day1.rs\"\n\
+                        \n\
+                        [[bin]]\n\
+                        path = \"src/day16.rs\"\n\
+                        name = \"day16\"\n";
+        assert_eq!(bin_days(manifest), vec![1, 16]);
+    }
+}